@@ -0,0 +1,504 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use dnt::Dependency;
+use dnt::FileTransformCache;
+use dnt::GlobalName;
+use dnt::JsrSpecifierMode;
+use dnt::LockfileOptions;
+use dnt::MappedSpecifier;
+use dnt::ModuleKind;
+use dnt::ModuleShim;
+use dnt::ModuleSpecifier;
+use dnt::OutsideRootDirMode;
+use dnt::PackageMappedSpecifier;
+use dnt::PackageShim;
+use dnt::RemoteLayout;
+use dnt::ScriptTarget;
+use dnt::Shim;
+use dnt::TransformOptions;
+use serde::Deserialize;
+
+const DENO_CONFIG_FILE_NAMES: [&str; 2] = ["deno.json", "deno.jsonc"];
+
+/// Configuration file for the CLI. This mirrors the `TransformOptions`
+/// of the Deno `transform.ts` API. Paths are relative to the config file.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Config {
+  /// Directory to write the output files to.
+  pub out_dir: String,
+  /// Defaults to the `exports` of the deno.json file.
+  #[serde(default)]
+  pub entry_points: Vec<String>,
+  #[serde(default)]
+  pub test_entry_points: Vec<String>,
+  #[serde(default)]
+  pub shims: Vec<ShimConfig>,
+  #[serde(default)]
+  pub test_shims: Vec<ShimConfig>,
+  #[serde(default)]
+  pub mappings: HashMap<String, MappedSpecifierConfig>,
+  pub target: Option<ScriptTarget>,
+  pub node_version_range: Option<String>,
+  pub module_kind: Option<ModuleKind>,
+  #[serde(default)]
+  pub node_test: bool,
+  pub import_map: Option<String>,
+  /// Path to a deno.json or deno.jsonc file. Defaults to one
+  /// of those files beside the config file.
+  pub config_file: Option<String>,
+  #[serde(default)]
+  pub error_on_import_map_diagnostics: bool,
+  #[serde(default)]
+  pub jsr_specifier_mode: JsrSpecifierMode,
+  pub jsr_url: Option<String>,
+  pub root_dir: Option<String>,
+  #[serde(default)]
+  pub outside_root_dir_mode: OutsideRootDirMode,
+  #[serde(default)]
+  pub preserve_module_format_extensions: bool,
+  #[serde(default)]
+  pub dedupe_remote_modules: bool,
+  #[serde(default)]
+  pub remote_layout: RemoteLayout,
+  pub remote_path_max_length: Option<usize>,
+  #[serde(default)]
+  pub source_maps: bool,
+  pub transform_cache_dir: Option<String>,
+  pub lockfile: Option<LockfileConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum MappedSpecifierConfig {
+  /// Path or url of a module, or the name of a package.
+  Text(String),
+  Package(PackageMappedSpecifier),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum ShimConfig {
+  Package(PackageShimConfig),
+  Module(ModuleShimConfig),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PackageShimConfig {
+  pub package: PackageMappedSpecifier,
+  pub types_package: Option<Dependency>,
+  pub global_names: Vec<GlobalNameConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct ModuleShimConfig {
+  pub module: String,
+  pub global_names: Vec<GlobalNameConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+pub enum GlobalNameConfig {
+  Name(String),
+  GlobalName(GlobalName),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct LockfileConfig {
+  pub path: String,
+  #[serde(default)]
+  pub write: bool,
+}
+
+/// Options resolved from the config file.
+pub struct ResolvedConfig {
+  pub out_dir: PathBuf,
+  pub lockfile_path: Option<PathBuf>,
+  pub transform_options: TransformOptions,
+}
+
+impl Config {
+  pub fn from_text(text: &str) -> Result<Self> {
+    let value = jsonc_parser::parse_to_serde_value(
+      text,
+      &jsonc_parser::ParseOptions {
+        allow_comments: true,
+        allow_loose_object_property_names: false,
+        allow_trailing_commas: true,
+      },
+    )?
+    .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+    Ok(serde_json::from_value(value)?)
+  }
+
+  pub fn resolve(self, base_dir: &Path) -> Result<ResolvedConfig> {
+    let config_file = match &self.config_file {
+      Some(config_file) => Some(value_to_url(config_file, base_dir)?),
+      None => DENO_CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| base_dir.join(name))
+        .find(|path| path.is_file())
+        .map(|path| path_to_url(&path))
+        .transpose()?,
+    };
+    if self.entry_points.is_empty() && config_file.is_none() {
+      bail!("Specify one or more entry points in the config file.");
+    }
+    let resolve_specifiers = |values: Vec<String>| {
+      values
+        .iter()
+        .map(|v| value_to_url(v, base_dir))
+        .collect::<Result<Vec<_>>>()
+    };
+    let resolve_shims = |shims: Vec<ShimConfig>| {
+      shims
+        .into_iter()
+        .map(|s| s.resolve(base_dir))
+        .collect::<Result<Vec<_>>>()
+    };
+    let mut specifier_mappings = HashMap::new();
+    for (specifier, mapping) in self.mappings {
+      specifier_mappings.insert(
+        value_to_url(&specifier, base_dir)?,
+        mapping.resolve(base_dir)?,
+      );
+    }
+    let lockfile_path = self
+      .lockfile
+      .as_ref()
+      .map(|l| normalize_path(&base_dir.join(&l.path)));
+
+    Ok(ResolvedConfig {
+      out_dir: normalize_path(&base_dir.join(self.out_dir)),
+      transform_options: TransformOptions {
+        entry_points: resolve_specifiers(self.entry_points)?,
+        test_entry_points: resolve_specifiers(self.test_entry_points)?,
+        shims: resolve_shims(self.shims)?,
+        test_shims: resolve_shims(self.test_shims)?,
+        loader: Some(Rc::new(dnt::DefaultLoader::new())),
+        specifier_mappings,
+        target: self.target.unwrap_or(ScriptTarget::ES2021),
+        node_version_range: self.node_version_range,
+        module_kind: self.module_kind,
+        node_test: self.node_test,
+        import_map: self
+          .import_map
+          .map(|v| value_to_url(&v, base_dir))
+          .transpose()?,
+        config_file,
+        error_on_import_map_diagnostics: self.error_on_import_map_diagnostics,
+        jsr_specifier_mode: self.jsr_specifier_mode,
+        jsr_url: self
+          .jsr_url
+          .map(|v| ModuleSpecifier::parse(&v))
+          .transpose()
+          .context("Error parsing jsrUrl.")?,
+        root_dir: self
+          .root_dir
+          .map(|v| value_to_url(&v, base_dir))
+          .transpose()?,
+        outside_root_dir_mode: self.outside_root_dir_mode,
+        preserve_module_format_extensions: self
+          .preserve_module_format_extensions,
+        dedupe_remote_modules: self.dedupe_remote_modules,
+        remote_layout: self.remote_layout,
+        remote_path_max_length: self.remote_path_max_length,
+        source_maps: self.source_maps,
+        transform_cache: self.transform_cache_dir.map(|dir| {
+          Rc::new(FileTransformCache::new(normalize_path(&base_dir.join(dir))))
+            as Rc<dyn dnt::TransformCache>
+        }),
+        lockfile: match (self.lockfile, &lockfile_path) {
+          (Some(lockfile), Some(lockfile_path)) => Some(LockfileOptions {
+            specifier: path_to_url(lockfile_path)?,
+            text: None,
+            write: lockfile.write,
+          }),
+          _ => None,
+        },
+      },
+      lockfile_path,
+    })
+  }
+}
+
+impl MappedSpecifierConfig {
+  fn resolve(self, base_dir: &Path) -> Result<MappedSpecifier> {
+    Ok(match self {
+      MappedSpecifierConfig::Text(value) => {
+        if is_path_or_url(&value) {
+          MappedSpecifier::Module(value_to_url(&value, base_dir)?)
+        } else {
+          MappedSpecifier::Package(PackageMappedSpecifier {
+            name: value,
+            version: None,
+            sub_path: None,
+            peer_dependency: false,
+          })
+        }
+      }
+      MappedSpecifierConfig::Package(package) => {
+        MappedSpecifier::Package(package)
+      }
+    })
+  }
+}
+
+impl ShimConfig {
+  fn resolve(self, base_dir: &Path) -> Result<Shim> {
+    Ok(match self {
+      ShimConfig::Package(shim) => Shim::Package(PackageShim {
+        package: shim.package,
+        types_package: shim.types_package,
+        global_names: resolve_global_names(shim.global_names),
+      }),
+      ShimConfig::Module(shim) => Shim::Module(ModuleShim {
+        module: if is_path_or_url(&shim.module) {
+          value_to_url(shim.module.trim(), base_dir)?.to_string()
+        } else {
+          shim.module.trim().to_string()
+        },
+        global_names: resolve_global_names(shim.global_names),
+      }),
+    })
+  }
+}
+
+fn resolve_global_names(names: Vec<GlobalNameConfig>) -> Vec<GlobalName> {
+  names
+    .into_iter()
+    .map(|name| match name {
+      GlobalNameConfig::Name(name) => GlobalName {
+        name,
+        export_name: None,
+        type_only: false,
+      },
+      GlobalNameConfig::GlobalName(name) => name,
+    })
+    .collect()
+}
+
+/// Resolves a path relative to the base directory or
+/// takes the value as is when it's a url.
+fn value_to_url(value: &str, base_dir: &Path) -> Result<ModuleSpecifier> {
+  let lower_value = value.to_lowercase();
+  let is_url = ["http:", "https:", "npm:", "jsr:", "node:", "file:"]
+    .iter()
+    .any(|scheme| lower_value.starts_with(scheme));
+  if is_url {
+    ModuleSpecifier::parse(value)
+      .with_context(|| format!("Error parsing {}.", value))
+  } else {
+    path_to_url(&normalize_path(&base_dir.join(value)))
+  }
+}
+
+fn path_to_url(path: &Path) -> Result<ModuleSpecifier> {
+  match ModuleSpecifier::from_file_path(path) {
+    Ok(url) => Ok(url),
+    Err(()) => bail!("Error converting {} to a url.", path.display()),
+  }
+}
+
+/// Gets if the value is a path or url rather than a package name. Paths
+/// must be relative (ex. `./mod.ts`) or absolute because package names
+/// may contain a period (ex. `chart.js`).
+fn is_path_or_url(value: &str) -> bool {
+  let value = value.trim();
+  let has_scheme = value
+    .split_once("://")
+    .map(|(scheme, _)| {
+      !scheme.is_empty() && scheme.chars().all(|c| c.is_ascii_alphabetic())
+    })
+    .unwrap_or(false);
+  has_scheme
+    || value.starts_with("./")
+    || value.starts_with("../")
+    || value.starts_with('/')
+    || Path::new(value).is_absolute()
+}
+
+/// Removes the `.` and `..` components of an absolute path.
+fn normalize_path(path: &Path) -> PathBuf {
+  let mut result = PathBuf::new();
+  for component in path.components() {
+    match component {
+      Component::CurDir => {}
+      Component::ParentDir => {
+        result.pop();
+      }
+      component => result.push(component),
+    }
+  }
+  result
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn resolves_config() {
+    let config = Config::from_text(
+      r#"{
+        // comments are allowed
+        "outDir": "./npm",
+        "entryPoints": ["./mod.ts"],
+        "testEntryPoints": ["../other/mod.test.ts"],
+        "shims": [{
+          "package": { "name": "@deno/shim-deno", "version": "~0.1.0" },
+          "globalNames": ["Deno", { "name": "fetch", "typeOnly": true }]
+        }],
+        "testShims": [{ "module": "./shims.ts", "globalNames": ["a"] }],
+        "mappings": {
+          "https://deno.land/x/code/mod.ts": "code-package",
+          "https://cdn.jsdelivr.net/npm/chart.js": "chart.js",
+          "./local.ts": "./node_local.ts",
+          "npm:chalk@5": { "name": "chalk", "version": "^5.0.0" }
+        },
+        "target": "ES2020",
+        "nodeVersionRange": ">=18",
+        "moduleKind": "commonJs",
+        "importMap": "./import_map.json",
+        "configFile": "./deno.jsonc",
+        "rootDir": "./src",
+        "outsideRootDirMode": "external",
+        "preserveModuleFormatExtensions": true,
+        "dedupeRemoteModules": true,
+        "remoteLayout": "contentHash",
+        "remotePathMaxLength": 100,
+        "lockfile": { "path": "./deno.lock", "write": true },
+      }"#,
+    )
+    .unwrap();
+    let base_dir = if cfg!(windows) {
+      PathBuf::from("C:\\project")
+    } else {
+      PathBuf::from("/project")
+    };
+    let base_url = path_to_url(&base_dir).unwrap().to_string();
+    let resolved = config.resolve(&base_dir).unwrap();
+    assert_eq!(resolved.out_dir, base_dir.join("npm"));
+    assert_eq!(resolved.lockfile_path, Some(base_dir.join("deno.lock")));
+
+    let options = resolved.transform_options;
+    assert_eq!(
+      options.entry_points,
+      vec![path_to_url(&base_dir.join("mod.ts")).unwrap()]
+    );
+    assert_eq!(
+      options.test_entry_points,
+      vec![
+        path_to_url(&base_dir.parent().unwrap().join("other/mod.test.ts"))
+          .unwrap()
+      ]
+    );
+    assert!(matches!(options.target, ScriptTarget::ES2020));
+    assert_eq!(options.node_version_range.as_deref(), Some(">=18"));
+    assert_eq!(options.module_kind, Some(ModuleKind::CommonJs));
+    assert_eq!(
+      options.root_dir.unwrap().to_string(),
+      format!("{}/src", base_url)
+    );
+    assert_eq!(options.outside_root_dir_mode, OutsideRootDirMode::External);
+    assert!(options.preserve_module_format_extensions);
+    assert!(options.dedupe_remote_modules);
+    assert_eq!(options.remote_layout, RemoteLayout::ContentHash);
+    assert_eq!(options.remote_path_max_length, Some(100));
+    assert_eq!(
+      options.import_map.unwrap().to_string(),
+      format!("{}/import_map.json", base_url)
+    );
+    assert_eq!(
+      options.config_file.unwrap().to_string(),
+      format!("{}/deno.jsonc", base_url)
+    );
+
+    let Shim::Package(shim) = &options.shims[0] else {
+      panic!("expected package shim");
+    };
+    assert_eq!(shim.package.name, "@deno/shim-deno");
+    assert_eq!(shim.global_names[0].name, "Deno");
+    assert_eq!(shim.global_names[0].type_only, false);
+    assert_eq!(shim.global_names[1].name, "fetch");
+    assert_eq!(shim.global_names[1].type_only, true);
+    let Shim::Module(shim) = &options.test_shims[0] else {
+      panic!("expected module shim");
+    };
+    assert_eq!(shim.module, format!("{}/shims.ts", base_url));
+
+    let get_mapping = |specifier: &str| {
+      options
+        .specifier_mappings
+        .get(&ModuleSpecifier::parse(specifier).unwrap())
+        .unwrap()
+    };
+    let MappedSpecifier::Package(package) =
+      get_mapping("https://deno.land/x/code/mod.ts")
+    else {
+      panic!("expected package mapping");
+    };
+    assert_eq!(package.name, "code-package");
+    assert_eq!(package.version, None);
+    let MappedSpecifier::Package(package) =
+      get_mapping("https://cdn.jsdelivr.net/npm/chart.js")
+    else {
+      panic!("expected package mapping");
+    };
+    assert_eq!(package.name, "chart.js");
+    let MappedSpecifier::Module(module) =
+      get_mapping(&format!("{}/local.ts", base_url))
+    else {
+      panic!("expected module mapping");
+    };
+    assert_eq!(module.to_string(), format!("{}/node_local.ts", base_url));
+    let MappedSpecifier::Package(package) = get_mapping("npm:chalk@5") else {
+      panic!("expected package mapping");
+    };
+    assert_eq!(package.version, Some("^5.0.0".to_string()));
+
+    let lockfile = options.lockfile.unwrap();
+    assert_eq!(
+      lockfile.specifier.to_string(),
+      format!("{}/deno.lock", base_url)
+    );
+    assert!(lockfile.write);
+  }
+
+  #[test]
+  fn errors_unknown_property() {
+    let err = Config::from_text(
+      r#"{ "outDir": "npm", "entryPoints": [], "entrypoint": "" }"#,
+    )
+    .err()
+    .unwrap();
+    assert!(err.to_string().starts_with("unknown field `entrypoint`"));
+  }
+
+  #[test]
+  fn paths_or_urls() {
+    assert!(is_path_or_url("./mod.ts"));
+    assert!(is_path_or_url("../mod"));
+    assert!(is_path_or_url("https://deno.land/x/mod"));
+    assert!(is_path_or_url("/project/mod.ts"));
+    assert!(!is_path_or_url("mod.ts"));
+    assert!(!is_path_or_url("chart.js"));
+    assert!(!is_path_or_url("lodash.merge"));
+    assert!(!is_path_or_url("chalk"));
+    assert!(!is_path_or_url("@deno/shim-deno"));
+  }
+}
@@ -0,0 +1,119 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use dnt::OutputFile;
+
+use crate::config::Config;
+
+mod config;
+
+const DEFAULT_CONFIG_FILE_NAMES: [&str; 2] = ["dnt.jsonc", "dnt.json"];
+
+const HELP_TEXT: &str = "Transforms Deno code to Node/canonical TypeScript.
+
+Usage: dnt [config-file]
+
+The config file defaults to dnt.jsonc or dnt.json in the current directory.
+
+Options:
+  -h, --help     Print help
+  -V, --version  Print version";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+  if let Err(err) = run().await {
+    eprintln!("error: {:#}", err);
+    std::process::exit(1);
+  }
+}
+
+async fn run() -> Result<()> {
+  let args = std::env::args().skip(1).collect::<Vec<_>>();
+  let config_path = match args.as_slice() {
+    [] => find_default_config_file()?,
+    [arg] if arg == "-h" || arg == "--help" => {
+      println!("{}", HELP_TEXT);
+      return Ok(());
+    }
+    [arg] if arg == "-V" || arg == "--version" => {
+      println!("dnt {}", env!("CARGO_PKG_VERSION"));
+      return Ok(());
+    }
+    [arg] if !arg.starts_with('-') => PathBuf::from(arg),
+    _ => bail!("Invalid arguments.\n\n{}", HELP_TEXT),
+  };
+
+  let config_path = std::fs::canonicalize(&config_path)
+    .with_context(|| format!("Error finding {}", config_path.display()))?;
+  let config_text = std::fs::read_to_string(&config_path)
+    .with_context(|| format!("Error reading {}", config_path.display()))?;
+  let config = Config::from_text(&config_text)
+    .with_context(|| format!("Error parsing {}", config_path.display()))?;
+  let config = config.resolve(config_path.parent().unwrap())?;
+
+  let output = dnt::transform(config.transform_options).await?;
+  for diagnostic in &output.diagnostics {
+    eprintln!(
+      "{}[{}]: {}",
+      diagnostic.severity, diagnostic.code, diagnostic
+    );
+  }
+
+  let files = output.main.files.iter().chain(output.test.files.iter());
+  let file_count = write_files(&config.out_dir, files)?;
+  if let (Some(lockfile_path), Some(lockfile_text)) =
+    (&config.lockfile_path, &output.lockfile_text)
+  {
+    std::fs::write(lockfile_path, lockfile_text)
+      .with_context(|| format!("Error writing {}", lockfile_path.display()))?;
+  }
+
+  println!("Wrote {} files to {}", file_count, config.out_dir.display());
+  Ok(())
+}
+
+fn find_default_config_file() -> Result<PathBuf> {
+  for file_name in DEFAULT_CONFIG_FILE_NAMES {
+    let path = PathBuf::from(file_name);
+    if path.is_file() {
+      return Ok(path);
+    }
+  }
+  bail!(
+    "Could not find a {} file in the current directory.\n\n{}",
+    DEFAULT_CONFIG_FILE_NAMES.join(" or "),
+    HELP_TEXT
+  )
+}
+
+fn write_files<'a>(
+  out_dir: &Path,
+  files: impl Iterator<Item = &'a OutputFile>,
+) -> Result<usize> {
+  let mut count = 0;
+  for file in files {
+    let file_path = out_dir.join(&file.file_path);
+    write_file(&file_path, &file.file_text)?;
+    if let Some(source_map) = &file.source_map {
+      let mut source_map_path = file_path.into_os_string();
+      source_map_path.push(".map");
+      write_file(Path::new(&source_map_path), source_map)?;
+    }
+    count += 1;
+  }
+  Ok(count)
+}
+
+fn write_file(file_path: &Path, text: &str) -> Result<()> {
+  if let Some(parent) = file_path.parent() {
+    std::fs::create_dir_all(parent)
+      .with_context(|| format!("Error creating {}", parent.display()))?;
+  }
+  std::fs::write(file_path, text)
+    .with_context(|| format!("Error writing {}", file_path.display()))
+}
@@ -25,6 +25,8 @@ Deno.test("script target should have expected outputs", () => {
     "ES2021": ts.ScriptTarget.ES2021,
     "ES2022": ts.ScriptTarget.ES2022,
     "ES2023": ts.ScriptTarget.ES2023,
+    "ES2024": ts.ScriptTarget.ES2023,
+    "ES2025": ts.ScriptTarget.ES2023,
     "Latest": ts.ScriptTarget.Latest,
   };
 
@@ -51,6 +53,20 @@ Deno.test("compiler lib option should have expected outputs", () => {
     "ES2021": ["lib.es2021.d.ts"],
     "ES2022": ["lib.es2022.d.ts"],
     "ES2023": ["lib.es2023.d.ts"],
+    "ES2024": [
+      "lib.es2023.d.ts",
+      "lib.esnext.collection.d.ts",
+      "lib.esnext.object.d.ts",
+      "lib.esnext.promise.d.ts",
+      "lib.esnext.string.d.ts",
+    ],
+    "ES2025": [
+      "lib.es2023.d.ts",
+      "lib.esnext.collection.d.ts",
+      "lib.esnext.object.d.ts",
+      "lib.esnext.promise.d.ts",
+      "lib.esnext.string.d.ts",
+    ],
     "Latest": ["lib.esnext.d.ts"],
   };
 
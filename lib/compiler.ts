@@ -40,6 +40,11 @@ export function getCompilerScriptTarget(target: ScriptTarget) {
       return ts.ScriptTarget.ES2022;
     case "ES2023":
       return ts.ScriptTarget.ES2023;
+    // the bundled TypeScript version doesn't have these yet, so
+    // emit for the closest previous edition
+    case "ES2024":
+    case "ES2025":
+      return ts.ScriptTarget.ES2023;
     case "Latest":
       return ts.ScriptTarget.Latest;
     default:
@@ -126,6 +131,7 @@ export type LibName =
   | "ESNext.String"
   | "ESNext.Promise"
   | "ESNext.WeakRef"
+  | "ESNext.Object"
   | "ESNext.Decorators"
   | "Decorators"
   | "Decorators.Legacy";
@@ -154,6 +160,17 @@ export function getCompilerLibOption(target: ScriptTarget): LibName[] {
       return ["ES2022"];
     case "ES2023":
       return ["ES2023"];
+    // the bundled TypeScript version doesn't have lib files for these
+    // editions yet, so add the ESNext libs with their built-ins
+    case "ES2024":
+    case "ES2025":
+      return [
+        "ES2023",
+        "ESNext.Collection",
+        "ESNext.Object",
+        "ESNext.Promise",
+        "ESNext.String",
+      ];
     case "Latest":
       return ["ESNext"];
     default: {
@@ -14,6 +14,7 @@ Deno.test("single entrypoint", () => {
     transformOutput: {
       main: {
         files: [],
+        shimUsage: [],
         dependencies: [{
           name: "dep",
           version: "^1.0.0",
@@ -23,6 +24,7 @@ Deno.test("single entrypoint", () => {
       test: {
         entryPoints: [],
         files: [],
+        shimUsage: [],
         dependencies: [{
           name: "test-dep",
           version: "0.1.0",
@@ -31,7 +33,7 @@ Deno.test("single entrypoint", () => {
           version: "~0.1.0",
         }],
       },
-      warnings: [],
+      diagnostics: [],
     },
     entryPoints: [{
       name: ".",
@@ -243,15 +245,17 @@ Deno.test("exports have default last", () => {
     transformOutput: {
       main: {
         files: [],
+        shimUsage: [],
         dependencies: [],
         entryPoints: ["mod.ts"],
       },
       test: {
         entryPoints: [],
         files: [],
+        shimUsage: [],
         dependencies: [],
       },
-      warnings: [],
+      diagnostics: [],
     },
     entryPoints: [
       {
@@ -287,6 +291,7 @@ Deno.test("multiple entrypoints", () => {
     transformOutput: {
       main: {
         files: [],
+        shimUsage: [],
         dependencies: [{
           name: "@deno/shim-deno",
           version: "~0.1.0",
@@ -296,9 +301,10 @@ Deno.test("multiple entrypoints", () => {
       test: {
         entryPoints: [],
         files: [],
+        shimUsage: [],
         dependencies: [],
       },
-      warnings: [],
+      diagnostics: [],
     },
     entryPoints: [{
       name: ".",
@@ -363,6 +369,7 @@ Deno.test("binary entrypoints", () => {
     transformOutput: {
       main: {
         files: [],
+        shimUsage: [],
         dependencies: [{
           name: "@deno/shim-deno",
           version: "~0.1.0",
@@ -372,9 +379,10 @@ Deno.test("binary entrypoints", () => {
       test: {
         entryPoints: [],
         files: [],
+        shimUsage: [],
         dependencies: [],
       },
-      warnings: [],
+      diagnostics: [],
     },
     entryPoints: [{
       name: ".",
@@ -433,6 +441,7 @@ Deno.test("peer dependencies", () => {
     transformOutput: {
       main: {
         files: [],
+        shimUsage: [],
         dependencies: [{
           name: "dep",
           version: "^1.0.0",
@@ -446,6 +455,7 @@ Deno.test("peer dependencies", () => {
       test: {
         entryPoints: [],
         files: [],
+        shimUsage: [],
         dependencies: [{
           name: "test-dep",
           version: "0.1.0",
@@ -456,7 +466,7 @@ Deno.test("peer dependencies", () => {
           version: "~0.1.0",
         }],
       },
-      warnings: [],
+      diagnostics: [],
     },
     entryPoints: [{
       name: ".",
@@ -68,4 +68,6 @@ export type ScriptTarget =
   | "ES2021"
   | "ES2022"
   | "ES2023"
+  | "ES2024"
+  | "ES2025"
   | "Latest";
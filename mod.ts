@@ -124,6 +124,36 @@ export interface BuildOptions {
   package: PackageJson;
   /** Path or url to import map. */
   importMap?: string;
+  /** Path or url to a deno.json or deno.jsonc file to use the `imports`,
+   * `scopes`, and `compilerOptions.jsxImportSource` of. The `importMap`
+   * takes precedence over the config file's imports. */
+  configFile?: string;
+  /** Fail the build when the import map has diagnostics (ex. invalid
+   * addresses or unsupported keys) instead of logging them as warnings.
+   * @default false
+   */
+  errorOnImportMapDiagnostics?: boolean;
+  /** How to handle `jsr:` specifiers.
+   *
+   * * `"npm"` - Map them to packages on JSR's npm compatibility layer (ex. `@jsr/std__path`).
+   *   This requires `@jsr:registry=https://npm.jsr.io` in the npm client's `.npmrc`.
+   * * `"vendor"` - Download the package's modules from the registry and include them in the output.
+   * @default "npm"
+   */
+  jsrSpecifierMode?: "npm" | "vendor";
+  /** Directory to cache the analysis of each module in. When provided,
+   * modules that haven't changed since the last build are not re-analyzed.
+   */
+  transformCacheDir?: string;
+  /** Lockfile (deno.lock) to verify the checksums of remote modules against. */
+  lockfile?: {
+    /** Path to the lockfile. */
+    path: string;
+    /** Add the checksums of remote modules that aren't in the lockfile.
+     * @default false
+     */
+    write?: boolean;
+  };
   /** Package manager used to install dependencies and run npm scripts.
    * This also can be an absolute path to the executable file of package manager.
    * @default "npm"
@@ -223,8 +253,12 @@ export async function build(options: BuildOptions): Promise<void> {
 
   log("Transforming...");
   const transformOutput = await transformEntryPoints();
-  for (const warning of transformOutput.warnings) {
-    warn(warning);
+  for (const diagnostic of transformOutput.diagnostics) {
+    warn(diagnostic.message);
+  }
+  if (options.lockfile != null && transformOutput.lockfileText != null) {
+    log("Updating lockfile...");
+    Deno.writeTextFileSync(options.lockfile.path, transformOutput.lockfileText);
   }
 
   const createdDirectories = new Set<string>();
@@ -577,6 +611,11 @@ export async function build(options: BuildOptions): Promise<void> {
       mappings: options.mappings,
       target: scriptTarget,
       importMap: options.importMap,
+      configFile: options.configFile,
+      errorOnImportMapDiagnostics: options.errorOnImportMapDiagnostics,
+      jsrSpecifierMode: options.jsrSpecifierMode,
+      transformCacheDir: options.transformCacheDir,
+      lockfile: options.lockfile,
       internalWasmUrl: options.internalWasmUrl,
     });
   }
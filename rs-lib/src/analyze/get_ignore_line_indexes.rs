@@ -3,19 +3,25 @@
 use std::collections::HashSet;
 
 use deno_ast::view::*;
+use deno_ast::ModuleSpecifier;
 use deno_ast::RootNode;
 use deno_ast::SourceRangedForSpanned;
+use deno_ast::SourceTextInfoProvider;
+
+use crate::Diagnostic;
+use crate::DiagnosticCode;
+use crate::DiagnosticRange;
 
 pub struct IgnoredLineIndexes {
-  pub warnings: Vec<String>,
+  pub diagnostics: Vec<Diagnostic>,
   pub line_indexes: HashSet<usize>,
 }
 
 pub fn get_ignore_line_indexes(
-  specifier: &str,
+  specifier: &ModuleSpecifier,
   program: Program,
 ) -> IgnoredLineIndexes {
-  let mut warnings = Vec::new();
+  let mut diagnostics = Vec::new();
   let mut line_indexes = HashSet::new();
   for comment in program.comment_container().all_comments() {
     let lowercase_text = comment.text.trim().to_lowercase();
@@ -29,13 +35,21 @@ pub fn get_ignore_line_indexes(
       }
     }
     if starts_with_deno_shim_ignore {
-      warnings.push(
-        format!("deno-shim-ignore has been renamed to dnt-shim-ignore. Please rename it in {}", specifier)
+      diagnostics.push(
+        Diagnostic::warning(
+          DiagnosticCode::DenoShimIgnoreRenamed,
+          Some(specifier.clone()),
+          format!("deno-shim-ignore has been renamed to dnt-shim-ignore. Please rename it in {}", specifier),
+        )
+        .with_range(DiagnosticRange::from_source_range(
+          comment.range(),
+          program.text_info(),
+        )),
       );
     }
   }
   IgnoredLineIndexes {
-    warnings,
+    diagnostics,
     line_indexes,
   }
 }
@@ -0,0 +1,190 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use deno_ast::ModuleSpecifier;
+use deno_graph::source::CacheSetting;
+use serde_json::Value;
+
+use crate::Loader;
+
+/// Where the import map of a config file is.
+#[derive(Debug)]
+pub enum ConfigFileImportMap {
+  /// The `imports` and `scopes` in the config file.
+  Inline(Value),
+  /// Specifier from the `importMap` property.
+  Specifier(ModuleSpecifier),
+}
+
+/// The settings dnt uses from a deno.json or deno.jsonc file.
+#[derive(Debug)]
+pub struct ConfigFile {
+  pub specifier: ModuleSpecifier,
+  pub import_map: Option<ConfigFileImportMap>,
+  /// `compilerOptions.jsxImportSource`
+  pub jsx_import_source: Option<String>,
+  /// Modules in the `exports`.
+  pub exports: Vec<ModuleSpecifier>,
+}
+
+impl ConfigFile {
+  pub async fn load(
+    specifier: &ModuleSpecifier,
+    loader: &dyn Loader,
+  ) -> Result<Self> {
+    let response = loader
+      .load(specifier.clone(), CacheSetting::Use, None)
+      .await?
+      .ok_or_else(|| anyhow!("Could not find {}", specifier))?;
+    Self::from_text(specifier.clone(), &String::from_utf8(response.content)?)
+      .with_context(|| format!("Error parsing config file {}", specifier))
+  }
+
+  pub fn from_text(specifier: ModuleSpecifier, text: &str) -> Result<Self> {
+    let value = jsonc_parser::parse_to_serde_value(
+      text,
+      &jsonc_parser::ParseOptions {
+        allow_comments: true,
+        allow_loose_object_property_names: false,
+        allow_trailing_commas: true,
+      },
+    )?
+    .unwrap_or_else(|| Value::Object(Default::default()));
+    let Value::Object(mut value) = value else {
+      bail!("Expected an object.");
+    };
+
+    let import_map =
+      if value.contains_key("imports") || value.contains_key("scopes") {
+        let mut import_map = serde_json::Map::new();
+        for key in ["imports", "scopes"] {
+          if let Some(value) = value.remove(key) {
+            import_map.insert(key.to_string(), value);
+          }
+        }
+        Some(ConfigFileImportMap::Inline(Value::Object(import_map)))
+      } else {
+        match value.get("importMap") {
+          Some(Value::String(import_map)) => {
+            Some(ConfigFileImportMap::Specifier(specifier.join(import_map)?))
+          }
+          Some(_) => bail!("Expected \"importMap\" to be a string."),
+          None => None,
+        }
+      };
+
+    let jsx_import_source = match value
+      .get("compilerOptions")
+      .and_then(|o| o.get("jsxImportSource"))
+    {
+      Some(Value::String(source)) => Some(source.to_string()),
+      Some(_) => {
+        bail!("Expected \"compilerOptions.jsxImportSource\" to be a string.")
+      }
+      None => None,
+    };
+
+    let exports = match value.get("exports") {
+      Some(Value::String(export)) => vec![specifier.join(export)?],
+      Some(Value::Object(exports)) => exports
+        .iter()
+        .map(|(name, export)| match export {
+          Value::String(export) => Ok(specifier.join(export)?),
+          _ => bail!("Expected export \"{}\" to be a string.", name),
+        })
+        .collect::<Result<Vec<_>>>()?,
+      Some(_) => bail!("Expected \"exports\" to be a string or object."),
+      None => Vec::new(),
+    };
+
+    Ok(Self {
+      specifier,
+      import_map,
+      jsx_import_source,
+      exports,
+    })
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn reads_config_file() {
+    let specifier =
+      ModuleSpecifier::parse("file:///project/deno.jsonc").unwrap();
+    let config_file = ConfigFile::from_text(
+      specifier,
+      r#"{
+        // comment
+        "imports": { "a": "./a.ts" },
+        "scopes": { "https://deno.land/": { "b": "./b.ts" } },
+        "importMap": "./import_map.json",
+        "compilerOptions": { "jsxImportSource": "npm:preact" },
+        "exports": { ".": "./mod.ts", "./other": "./src/other.ts" },
+      }"#,
+    )
+    .unwrap();
+    let Some(ConfigFileImportMap::Inline(import_map)) = config_file.import_map
+    else {
+      panic!("expected inline import map");
+    };
+    assert_eq!(
+      import_map,
+      serde_json::json!({
+        "imports": { "a": "./a.ts" },
+        "scopes": { "https://deno.land/": { "b": "./b.ts" } },
+      })
+    );
+    assert_eq!(
+      config_file.jsx_import_source,
+      Some("npm:preact".to_string())
+    );
+    assert_eq!(
+      config_file
+        .exports
+        .iter()
+        .map(|e| e.as_str())
+        .collect::<Vec<_>>(),
+      vec!["file:///project/mod.ts", "file:///project/src/other.ts"]
+    );
+  }
+
+  #[test]
+  fn import_map_property() {
+    let specifier =
+      ModuleSpecifier::parse("file:///project/deno.json").unwrap();
+    let config_file = ConfigFile::from_text(
+      specifier,
+      r#"{ "importMap": "./import_map.json", "exports": "./mod.ts" }"#,
+    )
+    .unwrap();
+    let Some(ConfigFileImportMap::Specifier(import_map)) =
+      config_file.import_map
+    else {
+      panic!("expected import map specifier");
+    };
+    assert_eq!(import_map.as_str(), "file:///project/import_map.json");
+    assert_eq!(config_file.jsx_import_source, None);
+    assert_eq!(config_file.exports.len(), 1);
+  }
+
+  #[test]
+  fn errors_invalid_property_types() {
+    let specifier =
+      ModuleSpecifier::parse("file:///project/deno.json").unwrap();
+    let err = ConfigFile::from_text(specifier, r#"{ "exports": 5 }"#)
+      .err()
+      .unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Expected \"exports\" to be a string or object."
+    );
+  }
+}
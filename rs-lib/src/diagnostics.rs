@@ -0,0 +1,177 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::bail;
+use deno_ast::ModuleSpecifier;
+use deno_ast::SourceRange;
+use deno_ast::SourceTextInfo;
+
+/// Stable code that identifies the kind of a diagnostic.
+#[cfg_attr(
+  feature = "serialization",
+  derive(serde::Serialize, serde::Deserialize)
+)]
+#[cfg_attr(feature = "serialization", serde(rename_all = "kebab-case"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+  /// The import map has an entry that was skipped.
+  ImportMap,
+  /// More than one declaration file was specified for a module.
+  DuplicateDeclarationFile,
+  /// A `deno-shim-ignore` comment should be `dnt-shim-ignore`.
+  DenoShimIgnoreRenamed,
+  /// A dynamic import's specifier couldn't be analyzed.
+  DynamicImport,
+  /// A `Deno.test` or `t.step` call couldn't be converted to `node:test`.
+  DenoTest,
+  /// A built-in without a polyfill is missing in a version of the
+  /// Node.js version range.
+  UnsupportedBuiltIn,
+  /// A `using` declaration couldn't be downleveled.
+  UsingDeclaration,
+}
+
+impl DiagnosticCode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      DiagnosticCode::ImportMap => "import-map",
+      DiagnosticCode::DuplicateDeclarationFile => "duplicate-declaration-file",
+      DiagnosticCode::DenoShimIgnoreRenamed => "deno-shim-ignore-renamed",
+      DiagnosticCode::DynamicImport => "dynamic-import",
+      DiagnosticCode::DenoTest => "deno-test",
+      DiagnosticCode::UnsupportedBuiltIn => "unsupported-built-in",
+      DiagnosticCode::UsingDeclaration => "using-declaration",
+    }
+  }
+}
+
+impl FromStr for DiagnosticCode {
+  type Err = anyhow::Error;
+
+  fn from_str(text: &str) -> Result<Self, Self::Err> {
+    match text {
+      "import-map" => Ok(DiagnosticCode::ImportMap),
+      "duplicate-declaration-file" => {
+        Ok(DiagnosticCode::DuplicateDeclarationFile)
+      }
+      "deno-shim-ignore-renamed" => Ok(DiagnosticCode::DenoShimIgnoreRenamed),
+      "dynamic-import" => Ok(DiagnosticCode::DynamicImport),
+      "deno-test" => Ok(DiagnosticCode::DenoTest),
+      "unsupported-built-in" => Ok(DiagnosticCode::UnsupportedBuiltIn),
+      "using-declaration" => Ok(DiagnosticCode::UsingDeclaration),
+      _ => bail!("Unknown diagnostic code: {}", text),
+    }
+  }
+}
+
+impl fmt::Display for DiagnosticCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(self.as_str())
+  }
+}
+
+#[cfg_attr(
+  feature = "serialization",
+  derive(serde::Serialize, serde::Deserialize)
+)]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+  Error,
+  Warning,
+}
+
+impl fmt::Display for DiagnosticSeverity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DiagnosticSeverity::Error => f.write_str("error"),
+      DiagnosticSeverity::Warning => f.write_str("warning"),
+    }
+  }
+}
+
+/// Zero-indexed line and character (column) in a module.
+#[cfg_attr(
+  feature = "serialization",
+  derive(serde::Serialize, serde::Deserialize)
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiagnosticPosition {
+  pub line: usize,
+  pub character: usize,
+}
+
+#[cfg_attr(
+  feature = "serialization",
+  derive(serde::Serialize, serde::Deserialize)
+)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DiagnosticRange {
+  pub start: DiagnosticPosition,
+  pub end: DiagnosticPosition,
+}
+
+impl DiagnosticRange {
+  pub(crate) fn from_source_range(
+    range: SourceRange,
+    text_info: &SourceTextInfo,
+  ) -> Self {
+    let get_position = |pos| {
+      let index = text_info.line_and_column_index(pos);
+      DiagnosticPosition {
+        line: index.line_index,
+        character: index.column_index,
+      }
+    };
+    Self {
+      start: get_position(range.start),
+      end: get_position(range.end),
+    }
+  }
+}
+
+/// A problem found while transforming that didn't stop the transform.
+#[cfg_attr(
+  feature = "serialization",
+  derive(serde::Serialize, serde::Deserialize)
+)]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+  pub code: DiagnosticCode,
+  pub severity: DiagnosticSeverity,
+  /// Module or file (ex. import map) the diagnostic originated from.
+  pub specifier: Option<ModuleSpecifier>,
+  /// Range in the module, when the diagnostic is for a specific location.
+  pub range: Option<DiagnosticRange>,
+  pub message: String,
+}
+
+impl Diagnostic {
+  pub fn warning(
+    code: DiagnosticCode,
+    specifier: Option<ModuleSpecifier>,
+    message: String,
+  ) -> Self {
+    Self {
+      code,
+      severity: DiagnosticSeverity::Warning,
+      specifier,
+      range: None,
+      message,
+    }
+  }
+
+  pub fn with_range(mut self, range: DiagnosticRange) -> Self {
+    self.range = Some(range);
+    self
+  }
+}
+
+impl fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.message)
+  }
+}
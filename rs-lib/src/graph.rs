@@ -2,15 +2,22 @@
 
 use std::collections::BTreeMap;
 use std::collections::HashMap;
-use std::fmt::Write;
 use std::rc::Rc;
 
+use crate::config_file::ConfigFile;
+use crate::config_file::ConfigFileImportMap;
+use crate::diagnostics::Diagnostic;
+use crate::diagnostics::DiagnosticCode;
+use crate::graph_error::ModuleGraphError;
 use crate::loader::get_all_specifier_mappers;
 use crate::loader::Loader;
 use crate::loader::SourceLoader;
+use crate::lockfile::Lockfile;
 use crate::parser::ScopeAnalysisParser;
 use crate::specifiers::get_specifiers;
 use crate::specifiers::Specifiers;
+use crate::JsrSpecifierMode;
+use crate::LockfileOptions;
 use crate::MappedSpecifier;
 
 use anyhow::anyhow;
@@ -20,6 +27,8 @@ use anyhow::Result;
 use deno_ast::ModuleSpecifier;
 use deno_ast::ParsedSource;
 use deno_graph::source::CacheSetting;
+use deno_graph::source::JsrUrlProvider;
+use deno_graph::source::Locker;
 use deno_graph::source::ResolutionMode;
 use deno_graph::source::ResolveError;
 use deno_graph::CapturingModuleAnalyzer;
@@ -27,37 +36,82 @@ use deno_graph::Module;
 use deno_graph::ParsedSourceStore;
 use deno_graph::Range;
 use import_map::ImportMapOptions;
+use import_map::ImportMapWithDiagnostics;
 
 pub struct ModuleGraphOptions<'a> {
   pub entry_points: Vec<ModuleSpecifier>,
   pub test_entry_points: Vec<ModuleSpecifier>,
-  pub loader: Option<Rc<dyn Loader>>,
+  pub loader: Rc<dyn Loader>,
   pub specifier_mappings: &'a HashMap<ModuleSpecifier, MappedSpecifier>,
   pub import_map: Option<ModuleSpecifier>,
+  pub config_file: Option<ConfigFile>,
+  pub error_on_import_map_diagnostics: bool,
+  pub jsr_specifier_mode: JsrSpecifierMode,
+  pub jsr_url: Option<ModuleSpecifier>,
+  pub lockfile: Option<LockfileOptions>,
 }
 
 /// Wrapper around deno_graph::ModuleGraph.
 pub struct ModuleGraph {
   graph: deno_graph::ModuleGraph,
   capturing_analyzer: CapturingModuleAnalyzer,
+  updated_lockfile_text: Option<String>,
+  import_map_diagnostics: Vec<Diagnostic>,
 }
 
 impl ModuleGraph {
   pub async fn build_with_specifiers(
     options: ModuleGraphOptions<'_>,
   ) -> Result<(Self, Specifiers)> {
-    let loader = options.loader.unwrap_or_else(|| {
-      #[cfg(feature = "tokio-loader")]
-      return Rc::new(crate::loader::DefaultLoader::new());
-      #[cfg(not(feature = "tokio-loader"))]
-      panic!("You must provide a loader or use the 'tokio-loader' feature.")
-    });
-    let resolver = match options.import_map {
+    let loader = options.loader;
+    let (config_import_map, jsx_import_source) = match options.config_file {
+      Some(config_file) => {
+        let import_map = match config_file.import_map {
+          Some(ConfigFileImportMap::Inline(value)) => Some(
+            parse_import_map(config_file.specifier.clone(), value)
+              .with_context(|| {
+                format!("Error parsing import map in {}", config_file.specifier)
+              })?,
+          ),
+          Some(ConfigFileImportMap::Specifier(import_map_url)) => Some(
+            load_import_map(&import_map_url, &*loader)
+              .await
+              .context("Error loading import map.")?,
+          ),
+          None => None,
+        };
+        (import_map, config_file.jsx_import_source)
+      }
+      None => (None, None),
+    };
+    // an explicitly provided import map takes precedence
+    let import_map = match options.import_map {
       Some(import_map_url) => Some(
-        ImportMapResolver::load(&import_map_url, &*loader)
+        load_import_map(&import_map_url, &*loader)
           .await
           .context("Error loading import map.")?,
       ),
+      None => config_import_map,
+    };
+    let (import_map, import_map_diagnostics) = match import_map {
+      Some(result) => {
+        let diagnostics = get_import_map_diagnostics(
+          &result,
+          options.error_on_import_map_diagnostics,
+        )?;
+        (Some(result.import_map), diagnostics)
+      }
+      None => (None, Vec::new()),
+    };
+    let resolver = GraphResolver {
+      import_map,
+      jsx_import_source,
+    };
+    let write_lockfile = options.lockfile.as_ref().is_some_and(|l| l.write);
+    let mut lockfile = match options.lockfile {
+      Some(lockfile_options) => {
+        Some(Lockfile::load(lockfile_options, &*loader).await?)
+      }
       None => None,
     };
     let loader = SourceLoader::new(
@@ -65,6 +119,7 @@ impl ModuleGraph {
       get_all_specifier_mappers(),
       options.specifier_mappings,
     );
+    let jsr_url_provider = options.jsr_url.map(RegistryJsrUrlProvider::new);
     let source_parser = ScopeAnalysisParser;
     let capturing_analyzer =
       CapturingModuleAnalyzer::new(Some(Box::new(source_parser)), None);
@@ -81,43 +136,56 @@ impl ModuleGraph {
         deno_graph::BuildOptions {
           is_dynamic: false,
           imports: Default::default(),
-          resolver: resolver.as_ref().map(|r| r.as_resolver()),
-          locker: None,
+          resolver: Some(&resolver),
+          locker: lockfile.as_mut().map(|l| l as &mut dyn Locker),
           module_analyzer: &capturing_analyzer,
           reporter: None,
           npm_resolver: None,
-          file_system: Default::default(),
-          jsr_url_provider: Default::default(),
+          file_system: &loader,
+          jsr_url_provider: jsr_url_provider
+            .as_ref()
+            .map(|p| p as &dyn JsrUrlProvider)
+            .unwrap_or_default(),
           executor: Default::default(),
-          passthrough_jsr_specifiers: false,
+          passthrough_jsr_specifiers: options.jsr_specifier_mode
+            == JsrSpecifierMode::Npm,
         },
       )
       .await;
 
-    let mut error_message = String::new();
-    for error in graph.module_errors() {
-      if !error_message.is_empty() {
-        error_message.push_str("\n\n");
-      }
-      if let Some(range) = error.maybe_referrer() {
-        write!(error_message, "{:#}\n    at {}", error, range).unwrap();
-      } else {
-        write!(error_message, "{:#}", error).unwrap();
-      }
-      if !error_message.contains(error.specifier().as_str()) {
-        error_message.push_str(&format!(" ({})", error.specifier()));
-      }
-    }
-    if !error_message.is_empty() {
-      bail!("{}", error_message);
+    let mut loader_specifiers = loader.into_specifiers();
+    if let Some(error) = ModuleGraphError::from_graph(
+      &graph,
+      lockfile.as_ref().map(|l| l.specifier()),
+      &loader_specifiers.mapped_modules.values().collect(),
+    ) {
+      return Err(error.into());
     }
 
     let graph = Self {
       graph,
       capturing_analyzer,
+      updated_lockfile_text: lockfile
+        .filter(|l| write_lockfile && l.has_changes())
+        .map(|l| l.to_text()),
+      import_map_diagnostics,
     };
 
-    let loader_specifiers = loader.into_specifiers();
+    // jsr specifiers are passed through as external modules, so they never
+    // reach the loader and need their package mappings applied here
+    for module in graph.all_modules() {
+      if let Module::External(module) = module {
+        if module.specifier.scheme() == "jsr" {
+          if let Some(MappedSpecifier::Package(mapping)) =
+            options.specifier_mappings.get(&module.specifier)
+          {
+            loader_specifiers
+              .mapped_packages
+              .insert(module.specifier.clone(), mapping.clone());
+          }
+        }
+      }
+    }
 
     let not_found_module_mappings = options
       .specifier_mappings
@@ -161,6 +229,17 @@ impl ModuleGraph {
     Ok((graph, specifiers))
   }
 
+  /// Text of the lockfile with the checksums that were added while
+  /// building the graph, when it should be written.
+  pub fn updated_lockfile_text(&self) -> Option<&String> {
+    self.updated_lockfile_text.as_ref()
+  }
+
+  /// Diagnostics from parsing the import map.
+  pub fn import_map_diagnostics(&self) -> &[Diagnostic] {
+    &self.import_map_diagnostics
+  }
+
   pub fn redirects(&self) -> &BTreeMap<ModuleSpecifier, ModuleSpecifier> {
     &self.graph.redirects
   }
@@ -219,7 +298,7 @@ impl ModuleGraph {
   }
 }
 
-fn format_specifiers_for_message(
+pub fn format_specifiers_for_message(
   mut specifiers: Vec<&ModuleSpecifier>,
 ) -> String {
   specifiers.sort();
@@ -230,57 +309,118 @@ fn format_specifiers_for_message(
     .join("\n")
 }
 
-#[derive(Debug)]
-struct ImportMapResolver(import_map::ImportMap);
+struct RegistryJsrUrlProvider(ModuleSpecifier);
 
-impl ImportMapResolver {
-  pub async fn load(
-    import_map_url: &ModuleSpecifier,
-    loader: &dyn Loader,
-  ) -> Result<Self> {
-    let response = loader
-      .load(import_map_url.clone(), CacheSetting::Use, None)
-      .await?
-      .ok_or_else(|| anyhow!("Could not find {}", import_map_url))?;
-    let value = jsonc_parser::parse_to_serde_value(
-      &String::from_utf8(response.content)?,
-      &jsonc_parser::ParseOptions {
-        allow_comments: true,
-        allow_loose_object_property_names: true,
-        allow_trailing_commas: true,
-      },
-    )?
-    .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
-    let result = import_map::parse_from_value_with_options(
-      import_map_url.clone(),
-      value,
-      ImportMapOptions {
-        address_hook: None,
-        expand_imports: true,
-      },
-    )?;
-    // if !result.diagnostics.is_empty() {
-    //   todo: surface diagnostics maybe? It seems like this should not be hard error according to import map spec
-    //   bail!("Import map diagnostics:\n{}", result.diagnostics.into_iter().map(|d| format!("  - {}", d)).collect::<Vec<_>>().join("\n"));
-    //}
-    Ok(ImportMapResolver(result.import_map))
+impl RegistryJsrUrlProvider {
+  pub fn new(mut url: ModuleSpecifier) -> Self {
+    // package urls are joined onto this, so it must be a directory
+    if !url.path().ends_with('/') {
+      url.set_path(&format!("{}/", url.path()));
+    }
+    Self(url)
   }
+}
 
-  pub fn as_resolver(&self) -> &dyn deno_graph::source::Resolver {
-    self
+impl JsrUrlProvider for RegistryJsrUrlProvider {
+  fn url(&self) -> &ModuleSpecifier {
+    &self.0
+  }
+}
+
+async fn load_import_map(
+  import_map_url: &ModuleSpecifier,
+  loader: &dyn Loader,
+) -> Result<ImportMapWithDiagnostics> {
+  let response = loader
+    .load(import_map_url.clone(), CacheSetting::Use, None)
+    .await?
+    .ok_or_else(|| anyhow!("Could not find {}", import_map_url))?;
+  let value = jsonc_parser::parse_to_serde_value(
+    &String::from_utf8(response.content)?,
+    &jsonc_parser::ParseOptions {
+      allow_comments: true,
+      allow_loose_object_property_names: true,
+      allow_trailing_commas: true,
+    },
+  )?
+  .unwrap_or_else(|| serde_json::Value::Object(Default::default()));
+  parse_import_map(import_map_url.clone(), value)
+}
+
+fn parse_import_map(
+  base_url: ModuleSpecifier,
+  value: serde_json::Value,
+) -> Result<ImportMapWithDiagnostics> {
+  Ok(import_map::parse_from_value_with_options(
+    base_url,
+    value,
+    ImportMapOptions {
+      address_hook: None,
+      expand_imports: true,
+    },
+  )?)
+}
+
+/// Import map diagnostics aren't errors according to the import map
+/// spec since the invalid entries are skipped, so they're surfaced
+/// as warnings unless opted into erroring.
+fn get_import_map_diagnostics(
+  result: &ImportMapWithDiagnostics,
+  error_on_diagnostics: bool,
+) -> Result<Vec<Diagnostic>> {
+  let base_url = result.import_map.base_url();
+  if error_on_diagnostics && !result.diagnostics.is_empty() {
+    bail!(
+      "Import map diagnostics in {}:\n{}",
+      base_url,
+      result
+        .diagnostics
+        .iter()
+        .map(|d| format!("  - {}", d))
+        .collect::<Vec<_>>()
+        .join("\n")
+    );
   }
+  Ok(
+    result
+      .diagnostics
+      .iter()
+      .map(|d| {
+        Diagnostic::warning(
+          DiagnosticCode::ImportMap,
+          Some(base_url.clone()),
+          format!("Import map diagnostic in {}: {}", base_url, d),
+        )
+      })
+      .collect(),
+  )
+}
+
+#[derive(Debug)]
+struct GraphResolver {
+  import_map: Option<import_map::ImportMap>,
+  jsx_import_source: Option<String>,
 }
 
-impl deno_graph::source::Resolver for ImportMapResolver {
+impl deno_graph::source::Resolver for GraphResolver {
+  fn default_jsx_import_source(&self) -> Option<String> {
+    self.jsx_import_source.clone()
+  }
+
   fn resolve(
     &self,
     specifier: &str,
     referrer_range: &Range,
     _mode: ResolutionMode,
   ) -> Result<ModuleSpecifier, ResolveError> {
-    self
-      .0
-      .resolve(specifier, &referrer_range.specifier)
-      .map_err(|err| ResolveError::Other(err.into()))
+    match &self.import_map {
+      Some(import_map) => import_map
+        .resolve(specifier, &referrer_range.specifier)
+        .map_err(|err| ResolveError::Other(err.into())),
+      None => Ok(deno_graph::resolve_import(
+        specifier,
+        &referrer_range.specifier,
+      )?),
+    }
   }
 }
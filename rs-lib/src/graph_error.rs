@@ -0,0 +1,257 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::fmt::Write;
+
+use deno_ast::ModuleSpecifier;
+use deno_graph::Module;
+use deno_graph::ModuleError;
+use deno_graph::ModuleLoadError;
+use deno_graph::Range;
+use deno_graph::Resolution;
+use deno_graph::ResolutionError;
+
+use crate::DiagnosticPosition;
+use crate::DiagnosticRange;
+
+/// Error for when one or more modules in the module graph failed.
+///
+/// This can be retrieved from the error returned by `transform` via
+/// `err.downcast_ref::<ModuleGraphError>()`.
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleGraphError {
+  pub failures: Vec<ModuleFailure>,
+}
+
+impl std::error::Error for ModuleGraphError {}
+
+impl fmt::Display for ModuleGraphError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    for (i, failure) in self.failures.iter().enumerate() {
+      if i > 0 {
+        f.write_str("\n\n")?;
+      }
+      f.write_str(&failure.message)?;
+    }
+    Ok(())
+  }
+}
+
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModuleFailureKind {
+  /// The module could not be found.
+  NotFound,
+  /// The module's source code could not be parsed.
+  Parse,
+  /// The module's specifier could not be resolved (ex. a `jsr:` package
+  /// version that doesn't exist or a bare specifier that isn't mapped).
+  Resolution,
+  /// The module's source doesn't match its checksum.
+  ChecksumMismatch,
+  /// The loader errored loading the module.
+  Load,
+  /// The module's media type or import attribute type isn't supported.
+  Unsupported,
+}
+
+/// Where a failed module was imported.
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleFailureReferrer {
+  pub specifier: ModuleSpecifier,
+  pub range: DiagnosticRange,
+}
+
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ModuleFailure {
+  pub kind: ModuleFailureKind,
+  /// Specifier of the module that failed. For an import that couldn't
+  /// be resolved, this is the module with the import.
+  pub specifier: ModuleSpecifier,
+  pub referrer: Option<ModuleFailureReferrer>,
+  /// Source code of the import, or of the parse error, with the
+  /// range underlined.
+  pub code_frame: Option<String>,
+  /// Human readable description of the failure.
+  pub message: String,
+}
+
+impl ModuleGraphError {
+  /// Gets the module errors and the import resolution errors of the
+  /// graph, if any. The imports of modules that other modules are mapped
+  /// to aren't checked because they may import Node packages.
+  pub(crate) fn from_graph(
+    graph: &deno_graph::ModuleGraph,
+    lockfile_specifier: Option<&ModuleSpecifier>,
+    mapped_modules: &HashSet<&ModuleSpecifier>,
+  ) -> Option<Self> {
+    let mut failures = graph
+      .module_errors()
+      .map(|error| ModuleFailure::new(graph, error, lockfile_specifier))
+      .collect::<Vec<_>>();
+    for module in graph.modules() {
+      let Module::Js(module) = module else {
+        continue;
+      };
+      if mapped_modules.contains(&module.specifier) {
+        continue;
+      }
+      for dependency in module.dependencies.values() {
+        // dynamic imports are only resolved when they're executed
+        if dependency.is_dynamic {
+          continue;
+        }
+        for resolution in [&dependency.maybe_code, &dependency.maybe_type] {
+          if let Resolution::Err(error) = resolution {
+            failures.push(ModuleFailure::from_resolution_error(graph, error));
+          }
+        }
+      }
+    }
+    if failures.is_empty() {
+      None
+    } else {
+      Some(Self { failures })
+    }
+  }
+}
+
+impl ModuleFailure {
+  fn new(
+    graph: &deno_graph::ModuleGraph,
+    error: &ModuleError,
+    lockfile_specifier: Option<&ModuleSpecifier>,
+  ) -> Self {
+    let kind = match error {
+      ModuleError::Missing(..) | ModuleError::MissingDynamic(..) => {
+        ModuleFailureKind::NotFound
+      }
+      ModuleError::ParseErr(..) => ModuleFailureKind::Parse,
+      ModuleError::LoadingErr(_, _, load_error) => match load_error {
+        ModuleLoadError::HttpsChecksumIntegrity(_) => {
+          ModuleFailureKind::ChecksumMismatch
+        }
+        ModuleLoadError::Jsr(_)
+        | ModuleLoadError::Npm(_)
+        | ModuleLoadError::NodeUnknownBuiltinModule(_) => {
+          ModuleFailureKind::Resolution
+        }
+        ModuleLoadError::Decode(_)
+        | ModuleLoadError::Loader(_)
+        | ModuleLoadError::TooManyRedirects => ModuleFailureKind::Load,
+      },
+      ModuleError::UnsupportedMediaType(..)
+      | ModuleError::InvalidTypeAssertion { .. }
+      | ModuleError::UnsupportedImportAttributeType { .. } => {
+        ModuleFailureKind::Unsupported
+      }
+    };
+
+    let mut message = String::new();
+    if let Some(lockfile_specifier) = lockfile_specifier {
+      if kind == ModuleFailureKind::ChecksumMismatch {
+        write!(
+          message,
+          "The source of {} does not match its checksum in the lockfile at {}. If the modification is expected, remove the module's entry from the lockfile.\n\n",
+          error.specifier(),
+          lockfile_specifier,
+        )
+        .unwrap();
+      }
+    }
+    if let Some(range) = error.maybe_referrer() {
+      write!(message, "{:#}\n    at {}", error, range).unwrap();
+    } else {
+      write!(message, "{:#}", error).unwrap();
+    }
+    if !message.contains(error.specifier().as_str()) {
+      write!(message, " ({})", error.specifier()).unwrap();
+    }
+
+    let code_frame = match error {
+      // the parse diagnostic includes the code frame after its message
+      ModuleError::ParseErr(_, diagnostic) => diagnostic
+        .to_string()
+        .split_once("\n\n")
+        .map(|(_, code_frame)| code_frame.to_string())
+        .filter(|code_frame| !code_frame.is_empty()),
+      _ => error
+        .maybe_referrer()
+        .and_then(|range| get_code_frame(graph, range)),
+    };
+
+    Self {
+      kind,
+      specifier: error.specifier().clone(),
+      referrer: error.maybe_referrer().map(get_referrer),
+      code_frame,
+      message,
+    }
+  }
+}
+
+impl ModuleFailure {
+  fn from_resolution_error(
+    graph: &deno_graph::ModuleGraph,
+    error: &ResolutionError,
+  ) -> Self {
+    let range = error.range();
+    Self {
+      kind: ModuleFailureKind::Resolution,
+      specifier: range.specifier.clone(),
+      referrer: Some(get_referrer(range)),
+      code_frame: get_code_frame(graph, range),
+      message: error.to_string_with_range(),
+    }
+  }
+}
+
+fn get_referrer(range: &Range) -> ModuleFailureReferrer {
+  ModuleFailureReferrer {
+    specifier: range.specifier.clone(),
+    range: DiagnosticRange {
+      start: DiagnosticPosition {
+        line: range.start.line,
+        character: range.start.character,
+      },
+      end: DiagnosticPosition {
+        line: range.end.line,
+        character: range.end.character,
+      },
+    },
+  }
+}
+
+/// Gets the line of the range in the referrer with the range underlined.
+fn get_code_frame(
+  graph: &deno_graph::ModuleGraph,
+  range: &Range,
+) -> Option<String> {
+  let source = match graph.get(&range.specifier)? {
+    Module::Js(module) => &module.source,
+    Module::Json(module) => &module.source,
+    _ => return None,
+  };
+  let line = source.lines().nth(range.start.line)?.trim_end();
+  let line_len = line.chars().count();
+  let start = range.start.character.min(line_len);
+  let end = if range.end.line == range.start.line {
+    range.end.character.clamp(start, line_len)
+  } else {
+    line_len
+  };
+  Some(format!(
+    "  {}\n  {}{}",
+    line,
+    " ".repeat(start),
+    "~".repeat((end - start).max(1)),
+  ))
+}
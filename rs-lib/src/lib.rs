@@ -10,6 +10,7 @@ use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
 use std::rc::Rc;
+use std::str::FromStr;
 
 use analyze::get_top_level_decls;
 use anyhow::Context;
@@ -17,46 +18,86 @@ use anyhow::Result;
 
 use analyze::get_ignore_line_indexes;
 use anyhow::bail;
+use config_file::ConfigFile;
 use deno_ast::apply_text_changes;
 use deno_ast::TextChange;
 use deno_graph::Module;
+use deno_semver::jsr::JsrPackageReqReference;
 use deno_semver::npm::NpmPackageReqReference;
+use deno_semver::VersionReq;
 use graph::ModuleGraphOptions;
 use mappings::Mappings;
+use mappings::MappingsOptions;
 use mappings::SYNTHETIC_SPECIFIERS;
 use mappings::SYNTHETIC_TEST_SPECIFIERS;
 use polyfills::build_polyfill_file;
 use polyfills::polyfills_for_target;
+use polyfills::unsupported_built_ins_for_range;
 use polyfills::Polyfill;
+use polyfills::EXPLICIT_RESOURCE_MANAGEMENT_POLYFILL_NAME;
+use source_map::create_source_map;
+use source_map::shift_source_map_for_insertion;
 use specifiers::Specifiers;
+use transform_cache::get_cache_key;
+use transform_cache::ModuleTransform;
 use utils::get_relative_specifier;
+use utils::get_shim_binding_name;
 use utils::prepend_statement_to_text;
 use visitors::fill_polyfills;
+use visitors::find_unsupported_built_ins;
 use visitors::get_deno_comment_directive_text_changes;
+use visitors::get_deno_test_text_changes;
 use visitors::get_global_text_changes;
 use visitors::get_import_exports_text_changes;
+use visitors::get_import_meta_text_changes;
+use visitors::get_using_text_changes;
 use visitors::FillPolyfillsParams;
+use visitors::FindUnsupportedBuiltInsParams;
+use visitors::GetDenoTestTextChangesParams;
 use visitors::GetGlobalTextChangesParams;
 use visitors::GetImportExportsTextChangesParams;
+use visitors::GetUsingTextChangesParams;
 
 pub use deno_ast::ModuleSpecifier;
 pub use deno_graph::source::CacheSetting;
+pub use deno_graph::source::DirEntry;
+pub use deno_graph::source::DirEntryKind;
 pub use deno_graph::source::LoaderChecksum;
+pub use diagnostics::Diagnostic;
+pub use diagnostics::DiagnosticCode;
+pub use diagnostics::DiagnosticPosition;
+pub use diagnostics::DiagnosticRange;
+pub use diagnostics::DiagnosticSeverity;
+pub use graph_error::ModuleFailure;
+pub use graph_error::ModuleFailureKind;
+pub use graph_error::ModuleFailureReferrer;
+pub use graph_error::ModuleGraphError;
+#[cfg(feature = "tokio-loader")]
+pub use loader::DefaultLoader;
 pub use loader::LoadResponse;
 pub use loader::Loader;
+pub use transform_cache::FileTransformCache;
+pub use transform_cache::InMemoryTransformCache;
+pub use transform_cache::TransformCache;
 pub use utils::url_to_file_path;
 
 use crate::declaration_file_resolution::TypesDependency;
 use crate::utils::strip_bom;
 
 mod analyze;
+mod config_file;
 mod declaration_file_resolution;
+mod diagnostics;
 mod graph;
+mod graph_error;
 mod loader;
+mod lockfile;
 mod mappings;
 mod parser;
 mod polyfills;
+mod source_map;
 mod specifiers;
+mod transform_cache;
 mod utils;
 mod visitors;
 
@@ -66,6 +107,36 @@ mod visitors;
 pub struct OutputFile {
   pub file_path: PathBuf,
   pub file_text: String,
+  /// Source map that maps the output text back to the original module.
+  /// Only provided when `TransformOptions::source_maps` is set.
+  #[cfg_attr(
+    feature = "serialization",
+    serde(skip_serializing_if = "Option::is_none")
+  )]
+  pub source_map: Option<String>,
+}
+
+/// Where an output file uses the globals of the environment's shims.
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FileShimUsage {
+  pub file_path: PathBuf,
+  /// Module the file was created from.
+  pub specifier: ModuleSpecifier,
+  /// Shimmed global names used in the file, sorted by name.
+  pub shims: Vec<ShimUsage>,
+}
+
+#[cfg_attr(feature = "serialization", derive(serde::Serialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ShimUsage {
+  /// Shim global name (ex. `Deno` or `Deno.readTextFile`) or `globalThis`
+  /// when it was replaced with the shim's `dntGlobalThis`.
+  pub name: String,
+  /// Ranges in the original module where the name was used.
+  pub ranges: Vec<DiagnosticRange>,
 }
 
 #[cfg_attr(feature = "serialization", derive(serde::Serialize))]
@@ -85,7 +156,11 @@ pub struct Dependency {
 pub struct TransformOutput {
   pub main: TransformOutputEnvironment,
   pub test: TransformOutputEnvironment,
-  pub warnings: Vec<String>,
+  /// Problems found while transforming that didn't stop the transform.
+  pub diagnostics: Vec<Diagnostic>,
+  /// Updated text of the lockfile to write when `LockfileOptions::write`
+  /// is set and checksums were added to it.
+  pub lockfile_text: Option<String>,
 }
 
 #[cfg_attr(feature = "serialization", derive(serde::Serialize))]
@@ -95,6 +170,8 @@ pub struct TransformOutputEnvironment {
   pub entry_points: Vec<PathBuf>,
   pub files: Vec<OutputFile>,
   pub dependencies: Vec<Dependency>,
+  /// Files that use the shims, in the same order as `files`.
+  pub shim_usage: Vec<FileShimUsage>,
 }
 
 #[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
@@ -134,6 +211,23 @@ impl PackageMappedSpecifier {
     }
   }
 
+  /// Maps a `jsr:` specifier to its package in JSR's npm compatibility
+  /// layer (ex. `jsr:@std/path@^1/join` to `@jsr/std__path/join`).
+  pub fn from_jsr_specifier(jsr_specifier: &JsrPackageReqReference) -> Self {
+    let name = &jsr_specifier.req().name;
+    Self {
+      name: format!(
+        "@jsr/{}",
+        name.trim_start_matches('@').replacen('/', "__", 1)
+      ),
+      version: Some(jsr_specifier.req().version_req.version_text().to_string()),
+      sub_path: jsr_specifier
+        .sub_path()
+        .map(|s| s.trim_start_matches("./").to_string()),
+      peer_dependency: false,
+    }
+  }
+
   pub(crate) fn module_specifier_text(&self) -> String {
     if let Some(path) = &self.sub_path {
       format!("{}/{}", self.name, path)
@@ -147,15 +241,32 @@ impl PackageMappedSpecifier {
 #[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
 #[derive(Clone, Debug)]
 pub struct GlobalName {
-  /// Name to use as the global name.
+  /// Name to use as the global name. This may be a member of a global
+  /// (ex. `Deno.readTextFile`) to only shim that member.
   pub name: String,
-  /// Optional name of the export from the package.
+  /// Optional name of the export from the package. Defaults to the
+  /// name or, for a member, the member's name (ex. `readTextFile`).
   pub export_name: Option<String>,
   /// Whether this is a name that only exists as a type declaration.
   #[serde(default)]
   pub type_only: bool,
 }
 
+impl GlobalName {
+  /// Whether this is a member of a global (ex. `Deno.readTextFile`).
+  pub fn is_member(&self) -> bool {
+    self.name.contains('.')
+  }
+
+  /// Name of the export from the shim's package or module.
+  pub fn export_name(&self) -> &str {
+    match &self.export_name {
+      Some(export_name) => export_name,
+      None => self.name.rsplit('.').next().unwrap(),
+    }
+  }
+}
+
 #[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
 #[cfg_attr(
   feature = "serialization",
@@ -216,8 +327,8 @@ impl ModuleShim {
 }
 
 // make sure to update `ScriptTarget` in the TS code when changing the names on this
-#[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
-#[derive(Clone, Copy, Debug)]
+/// The numbers match TypeScript's `ts.ScriptTarget` values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ScriptTarget {
   ES3 = 0,
   ES5 = 1,
@@ -230,7 +341,152 @@ pub enum ScriptTarget {
   ES2021 = 8,
   ES2022 = 9,
   ES2023 = 10,
-  Latest = 11,
+  ES2024 = 11,
+  ES2025 = 12,
+  Latest = 99,
+}
+
+impl ScriptTarget {
+  const ALL: [ScriptTarget; 14] = [
+    ScriptTarget::ES3,
+    ScriptTarget::ES5,
+    ScriptTarget::ES2015,
+    ScriptTarget::ES2016,
+    ScriptTarget::ES2017,
+    ScriptTarget::ES2018,
+    ScriptTarget::ES2019,
+    ScriptTarget::ES2020,
+    ScriptTarget::ES2021,
+    ScriptTarget::ES2022,
+    ScriptTarget::ES2023,
+    ScriptTarget::ES2024,
+    ScriptTarget::ES2025,
+    ScriptTarget::Latest,
+  ];
+
+  pub fn from_number(value: u32) -> Option<Self> {
+    Self::ALL.into_iter().find(|t| *t as u32 == value)
+  }
+}
+
+impl FromStr for ScriptTarget {
+  type Err = anyhow::Error;
+
+  fn from_str(text: &str) -> Result<Self, Self::Err> {
+    match Self::ALL.into_iter().find(|t| format!("{:?}", t) == text) {
+      Some(target) => Ok(target),
+      None => bail!("Unknown script target: {}", text),
+    }
+  }
+}
+
+/// Deserializes from the name (ex. `"ES2022"`) or the number.
+#[cfg(feature = "serialization")]
+impl<'de> serde::Deserialize<'de> for ScriptTarget {
+  fn deserialize<D: serde::Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum NameOrNumber {
+      Name(String),
+      Number(u32),
+    }
+
+    match NameOrNumber::deserialize(deserializer)? {
+      NameOrNumber::Name(name) => {
+        name.parse().map_err(serde::de::Error::custom)
+      }
+      NameOrNumber::Number(value) => {
+        Self::from_number(value).ok_or_else(|| {
+          serde::de::Error::custom(format!("Unknown script target: {}", value))
+        })
+      }
+    }
+  }
+}
+
+/// How `jsr:` specifiers are handled in the output.
+#[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsrSpecifierMode {
+  /// Map them to packages on JSR's npm compatibility layer (ex. `@jsr/std__path`).
+  #[default]
+  Npm,
+  /// Resolve them against the registry and include the package's modules
+  /// in the output the same way as remote modules.
+  Vendor,
+}
+
+/// How the output paths of remote modules in the `deps` directory
+/// are chosen.
+#[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RemoteLayout {
+  /// Mirror the host and path of the url
+  /// (ex. `deps/deno.land/x/oak@v12.6.0/mod.ts`). Adding a module may
+  /// rename others when their paths collide or are shortened.
+  #[default]
+  Mirror,
+  /// Use a directory named with a short hash of the module's url and
+  /// source (ex. `deps/3f2a9c1b0d/mod.ts`). Adding a module never
+  /// renames others.
+  ContentHash,
+  /// Use a directory for each package, suffixed with a short hash of the
+  /// package's url, with the path within the package
+  /// (ex. `deps/oak@v12.6.0_3f2a9c1b/mod.ts`). Modules that aren't in a
+  /// package use a directory for the host. Paths with uppercase letters
+  /// are suffixed with a hash of the url. Adding a module only renames
+  /// another in the same package when its path differs by just the
+  /// extension and the new one is a `.ts` module (or `.js` module).
+  Flat,
+}
+
+/// What's done with local modules that are outside of the root directory.
+#[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutsideRootDirMode {
+  /// Error listing the local modules outside of the root directory.
+  #[default]
+  Error,
+  /// Write them to an `_external` directory, at their path from the
+  /// closest directory they share with the root directory
+  /// (ex. `/project/fixtures/data.json` with the root directory
+  /// `/project/src` is written to `_external/fixtures/data.json`).
+  External,
+}
+
+/// Module system the output code will run as, which determines how
+/// `import.meta` is rewritten.
+#[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ModuleKind {
+  /// ES modules. `import.meta.main` and `import.meta.resolve` are rewritten.
+  Esm,
+  /// CommonJS modules. `import.meta.url`, `import.meta.main` and
+  /// `import.meta.resolve` are rewritten.
+  CommonJs,
+}
+
+/// Lockfile (deno.lock) to verify the checksums of remote modules against.
+#[cfg_attr(feature = "serialization", derive(serde::Deserialize))]
+#[cfg_attr(feature = "serialization", serde(rename_all = "camelCase"))]
+#[derive(Clone, Debug)]
+pub struct LockfileOptions {
+  /// Specifier of the lockfile (ex. `file:///project/deno.lock`).
+  pub specifier: ModuleSpecifier,
+  /// Text of the lockfile. When not provided, the lockfile is
+  /// loaded from the specifier using the loader.
+  pub text: Option<String>,
+  /// Add the checksums of remote modules that aren't in the lockfile
+  /// and provide the result in `TransformOutput::lockfile_text`. The
+  /// lockfile is created when it doesn't exist.
+  #[cfg_attr(feature = "serialization", serde(default))]
+  pub write: bool,
 }
 
 pub struct TransformOptions {
@@ -244,8 +500,60 @@ pub struct TransformOptions {
   /// Version of ECMAScript that the final code will target.
   /// This controls whether certain polyfills should occur.
   pub target: ScriptTarget,
+  /// Range of Node.js versions the output should run on (ex. `>=18`),
+  /// in the format of package.json's `engines.node`. When provided, the
+  /// polyfills are chosen based on the built-ins of these versions instead
+  /// of the target, and built-ins that can't be polyfilled are warned about.
+  pub node_version_range: Option<String>,
+  /// Module system of the output code. When provided, `import.meta`
+  /// properties Node doesn't have are rewritten for it. Otherwise,
+  /// `import.meta` is left as is.
+  pub module_kind: Option<ModuleKind>,
+  /// Convert `Deno.test` and `t.step` registrations in the test modules
+  /// to `node:test`, so the tests can run with `node --test`.
+  pub node_test: bool,
   /// Optional import map.
   pub import_map: Option<ModuleSpecifier>,
+  /// Optional deno.json or deno.jsonc file. Its `imports` and `scopes`
+  /// are used as the import map when `import_map` isn't provided,
+  /// `compilerOptions.jsxImportSource` is the default JSX import source,
+  /// and its `exports` are the entry points when none are provided.
+  pub config_file: Option<ModuleSpecifier>,
+  /// Error when the import map has diagnostics (ex. invalid addresses)
+  /// instead of providing them as warnings.
+  pub error_on_import_map_diagnostics: bool,
+  /// How `jsr:` specifiers should be handled.
+  pub jsr_specifier_mode: JsrSpecifierMode,
+  /// Base url of the JSR registry to use when vendoring `jsr:` specifiers.
+  /// Defaults to `https://jsr.io/`.
+  pub jsr_url: Option<ModuleSpecifier>,
+  /// Directory the output paths of local modules are relative to.
+  /// Defaults to the closest directory all the local modules share.
+  pub root_dir: Option<ModuleSpecifier>,
+  /// What's done with local modules that are outside of `root_dir`.
+  pub outside_root_dir_mode: OutsideRootDirMode,
+  /// Keep the `.mts`, `.cts`, `.mjs` and `.cjs` extensions of modules in
+  /// their output paths and in the specifiers that import them, so each
+  /// module keeps its module format. Otherwise, `.mts` and `.mjs` modules
+  /// are output as `.js` files and imported with a `.js` extension.
+  pub preserve_module_format_extensions: bool,
+  /// Write byte-identical remote modules that are reachable through
+  /// several urls to a single output file instead of one per url.
+  pub dedupe_remote_modules: bool,
+  /// How the output paths of remote modules are chosen. The content hash
+  /// and flat layouts keep the paths of existing modules when modules
+  /// from other packages are added.
+  pub remote_layout: RemoteLayout,
+  /// Maximum length of a remote module's path within the `deps` directory.
+  /// Defaults to 180.
+  pub remote_path_max_length: Option<usize>,
+  /// Whether to create a source map for each transformed module.
+  pub source_maps: bool,
+  /// Optional cache of the analysis of each module, which allows
+  /// skipping unchanged modules on subsequent runs.
+  pub transform_cache: Option<Rc<dyn TransformCache>>,
+  /// Optional lockfile to verify remote modules against.
+  pub lockfile: Option<LockfileOptions>,
 }
 
 struct EnvironmentContext<'a> {
@@ -255,13 +563,54 @@ struct EnvironmentContext<'a> {
   shim_file_specifier: &'a ModuleSpecifier,
   shim_global_names: HashSet<&'a str>,
   shims: &'a Vec<Shim>,
-  used_shim: bool,
+  used_shim_names: HashSet<String>,
+  used_shim_global_this: bool,
+}
+
+impl<'a> EnvironmentContext<'a> {
+  fn add_found_polyfills(&mut self, names: &[String]) {
+    for name in names {
+      if let Some(index) = self
+        .searching_polyfills
+        .iter()
+        .position(|p| p.name() == name)
+      {
+        let polyfill = self.searching_polyfills.remove(index);
+        self.found_polyfills.push(polyfill);
+      }
+    }
+  }
 }
 
-pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
+pub async fn transform(
+  mut options: TransformOptions,
+) -> Result<TransformOutput> {
+  let loader = options.loader.take().unwrap_or_else(|| {
+    #[cfg(feature = "tokio-loader")]
+    return Rc::new(crate::loader::DefaultLoader::new());
+    #[cfg(not(feature = "tokio-loader"))]
+    panic!("You must provide a loader or use the 'tokio-loader' feature.")
+  });
+  let config_file = match &options.config_file {
+    Some(specifier) => Some(ConfigFile::load(specifier, &*loader).await?),
+    None => None,
+  };
+  if options.entry_points.is_empty() {
+    if let Some(config_file) = &config_file {
+      options.entry_points.clone_from(&config_file.exports);
+    }
+  }
   if options.entry_points.is_empty() {
     anyhow::bail!("at least one entry point must be specified");
   }
+  let node_version_range = match &options.node_version_range {
+    Some(text) => Some(parse_node_version_range(text)?),
+    None => None,
+  };
+  let unsupported_built_ins = node_version_range
+    .as_ref()
+    .map(unsupported_built_ins_for_range)
+    .unwrap_or_default();
 
   let (module_graph, specifiers) =
     crate::graph::ModuleGraph::build_with_specifiers(ModuleGraphOptions {
@@ -283,12 +632,27 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
         )
         .collect(),
       specifier_mappings: &options.specifier_mappings,
-      loader: options.loader,
+      loader,
       import_map: options.import_map,
+      config_file,
+      error_on_import_map_diagnostics: options.error_on_import_map_diagnostics,
+      jsr_specifier_mode: options.jsr_specifier_mode,
+      jsr_url: options.jsr_url,
+      lockfile: options.lockfile,
     })
     .await?;
 
-  let mappings = Mappings::new(&module_graph, &specifiers)?;
+  let mappings = Mappings::new(MappingsOptions {
+    module_graph: &module_graph,
+    specifiers: &specifiers,
+    root_dir: options.root_dir.as_ref(),
+    outside_root_dir_mode: options.outside_root_dir_mode,
+    dedupe_remote_modules: options.dedupe_remote_modules,
+    remote_layout: options.remote_layout,
+    remote_path_max_length: options.remote_path_max_length,
+    preserve_module_format_extensions: options
+      .preserve_module_format_extensions,
+  })?;
   let all_package_specifier_mappings: HashMap<ModuleSpecifier, String> =
     specifiers
       .main
@@ -298,7 +662,8 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
       .map(|m| (m.0.clone(), m.1.module_specifier_text()))
       .collect();
 
-  let mut warnings = get_declaration_warnings(&specifiers);
+  let mut diagnostics = module_graph.import_map_diagnostics().to_vec();
+  diagnostics.extend(get_declaration_diagnostics(&specifiers));
   let mut main_env_context = EnvironmentContext {
     environment: TransformOutputEnvironment {
       entry_points: options
@@ -309,7 +674,11 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
       dependencies: get_dependencies(specifiers.main.mapped),
       ..Default::default()
     },
-    searching_polyfills: polyfills_for_target(options.target),
+    searching_polyfills: polyfills_for_target(
+      options.target,
+      node_version_range.as_ref(),
+      options.module_kind,
+    ),
     found_polyfills: Default::default(),
     shim_file_specifier: &SYNTHETIC_SPECIFIERS.shims,
     shim_global_names: options
@@ -318,7 +687,8 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
       .flat_map(|s| s.global_names().iter().map(|s| s.name.as_str()))
       .collect(),
     shims: &options.shims,
-    used_shim: false,
+    used_shim_names: HashSet::new(),
+    used_shim_global_this: false,
   };
   let mut test_env_context = EnvironmentContext {
     environment: TransformOutputEnvironment {
@@ -330,7 +700,11 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
       dependencies: get_dependencies(specifiers.test.mapped),
       ..Default::default()
     },
-    searching_polyfills: polyfills_for_target(options.target),
+    searching_polyfills: polyfills_for_target(
+      options.target,
+      node_version_range.as_ref(),
+      options.module_kind,
+    ),
     found_polyfills: Default::default(),
     shim_file_specifier: &SYNTHETIC_TEST_SPECIFIERS.shims,
     shim_global_names: options
@@ -339,15 +713,26 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
       .flat_map(|s| s.global_names().iter().map(|s| s.name.as_str()))
       .collect(),
     shims: &options.test_shims,
-    used_shim: false,
+    used_shim_names: HashSet::new(),
+    used_shim_global_this: false,
   };
 
   for specifier in specifiers
     .local
     .iter()
     .chain(specifiers.remote.iter())
-    .chain(specifiers.types.values().map(|d| &d.selected.specifier))
+    .chain(
+      specifiers
+        .types
+        .iter()
+        .filter(|(code_specifier, _)| !mappings.is_duplicate(code_specifier))
+        .map(|(_, d)| &d.selected.specifier),
+    )
   {
+    if mappings.is_duplicate(specifier) {
+      // the identical module writes the output file
+      continue;
+    }
     let module = module_graph.get(specifier);
     let env_context = if specifiers.test_modules.contains(specifier) {
       &mut test_env_context
@@ -355,96 +740,293 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
       &mut main_env_context
     };
 
-    let file_text = match module {
-      Module::Js(_) => {
+    let (original_text, text_changes) = match module {
+      Module::Js(js_module) => {
         let parsed_source = module_graph.get_parsed_source(specifier);
-        let text_changes = parsed_source
-          .with_view(|program| -> Result<Vec<TextChange>> {
-            let ignore_line_indexes = get_ignore_line_indexes(
-              parsed_source.specifier().as_str(),
-              program,
-            );
-            let top_level_decls =
-              get_top_level_decls(program, parsed_source.top_level_context());
-            warnings.extend(ignore_line_indexes.warnings);
-
-            fill_polyfills(&mut FillPolyfillsParams {
-              found_polyfills: &mut env_context.found_polyfills,
-              searching_polyfills: &mut env_context.searching_polyfills,
-              program,
-              unresolved_context: parsed_source.unresolved_context(),
-              top_level_decls: &top_level_decls,
-            });
-
-            let mut text_changes = Vec::new();
-
-            // shim changes
+        let output_file_path = mappings.get_file_path(specifier);
+        let is_test_module = specifiers.test_modules.contains(specifier);
+        let shim_relative_specifier = get_relative_specifier(
+          output_file_path,
+          mappings.get_file_path(env_context.shim_file_specifier),
+          mappings.preserve_module_format_extensions(),
+        );
+        let maybe_cache_key = options.transform_cache.as_ref().map(|_| {
+          let mut shim_global_names = env_context
+            .shim_global_names
+            .iter()
+            .copied()
+            .collect::<Vec<_>>();
+          shim_global_names.sort();
+          let mut inputs = vec![
+            specifier.to_string(),
+            js_module.source.to_string(),
+            format!("{:?}", options.target),
+            options.node_version_range.clone().unwrap_or_default(),
+            format!("{:?}", options.module_kind),
+            (options.node_test && is_test_module).to_string(),
+            output_file_path.to_string_lossy().to_string(),
+            shim_relative_specifier.clone(),
+            shim_global_names.join(","),
+          ];
+          // the output location of dependencies changes the specifiers
+          for value in js_module.dependencies.keys() {
+            inputs.push(value.clone());
+            if let Some(resolved) =
+              module_graph.resolve_dependency(value, specifier)
             {
-              let shim_relative_specifier = get_relative_specifier(
-                mappings.get_file_path(specifier),
-                mappings.get_file_path(env_context.shim_file_specifier),
+              inputs.push(
+                match all_package_specifier_mappings.get(&resolved) {
+                  Some(bare_specifier) => bare_specifier.clone(),
+                  None => mappings
+                    .maybe_file_path(&resolved)
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                },
               );
-              let result =
-                get_global_text_changes(&GetGlobalTextChangesParams {
+            }
+          }
+          get_cache_key(&inputs.iter().map(|s| s.as_str()).collect::<Vec<_>>())
+        });
+        let maybe_cached = options.transform_cache.as_ref().and_then(|cache| {
+          let data = cache.get(maybe_cache_key.as_ref()?)?;
+          ModuleTransform::from_bytes(&data, parsed_source.text())
+        });
+        let module_transform = match maybe_cached {
+          Some(module_transform) => module_transform,
+          None => {
+            let mut module_found_polyfills = Vec::new();
+            let mut module_searching_polyfills;
+            let (found_polyfills, searching_polyfills) =
+              if options.transform_cache.is_some() {
+                // search for all the polyfills so the cached result
+                // doesn't depend on the modules before it
+                module_searching_polyfills = polyfills_for_target(
+                  options.target,
+                  node_version_range.as_ref(),
+                  options.module_kind,
+                );
+                (&mut module_found_polyfills, &mut module_searching_polyfills)
+              } else {
+                (
+                  &mut env_context.found_polyfills,
+                  &mut env_context.searching_polyfills,
+                )
+              };
+            let found_polyfills_start = found_polyfills.len();
+            let module_transform = parsed_source
+              .with_view(|program| -> Result<ModuleTransform> {
+                let ignore_line_indexes =
+                  get_ignore_line_indexes(parsed_source.specifier(), program);
+                let top_level_decls = get_top_level_decls(
+                  program,
+                  parsed_source.top_level_context(),
+                );
+
+                fill_polyfills(&mut FillPolyfillsParams {
+                  found_polyfills,
+                  searching_polyfills,
                   program,
                   unresolved_context: parsed_source.unresolved_context(),
-                  shim_specifier: &shim_relative_specifier,
-                  shim_global_names: &env_context.shim_global_names,
-                  ignore_line_indexes: &ignore_line_indexes.line_indexes,
                   top_level_decls: &top_level_decls,
                 });
-              text_changes.extend(result.text_changes);
-              if result.imported_shim {
-                env_context.used_shim = true;
-              }
-            }
 
-            text_changes
-              .extend(get_deno_comment_directive_text_changes(program));
-            text_changes.extend(get_import_exports_text_changes(
-              &GetImportExportsTextChangesParams {
-                specifier,
-                module_graph: &module_graph,
-                mappings: &mappings,
-                program,
-                package_specifier_mappings: &all_package_specifier_mappings,
-              },
-            )?);
-
-            Ok(text_changes)
-          })
-          .with_context(|| {
-            format!(
-              "Issue getting text changes from {}",
-              parsed_source.specifier()
-            )
-          })?;
+                let deno_test_result = if options.node_test && is_test_module {
+                  Some(get_deno_test_text_changes(
+                    &GetDenoTestTextChangesParams {
+                      specifier,
+                      program,
+                      unresolved_context: parsed_source.unresolved_context(),
+                      top_level_decls: &top_level_decls,
+                    },
+                  ))
+                } else {
+                  None
+                };
+                let replaced_ranges = deno_test_result
+                  .as_ref()
+                  .map(|r| r.replaced_ranges.clone())
+                  .unwrap_or_default();
+
+                let mut text_changes = Vec::new();
+
+                // shim changes
+                let result =
+                  get_global_text_changes(&GetGlobalTextChangesParams {
+                    program,
+                    unresolved_context: parsed_source.unresolved_context(),
+                    shim_specifier: &shim_relative_specifier,
+                    shim_global_names: &env_context.shim_global_names,
+                    ignore_line_indexes: &ignore_line_indexes.line_indexes,
+                    top_level_decls: &top_level_decls,
+                    ignore_ranges: &replaced_ranges,
+                  });
+                text_changes.extend(result.text_changes);
+
+                text_changes
+                  .extend(get_deno_comment_directive_text_changes(program));
+                if let Some(module_kind) = options.module_kind {
+                  text_changes
+                    .extend(get_import_meta_text_changes(program, module_kind));
+                }
+                // the polyfill is only found when `using` needs downleveling
+                // and a module without `using` declarations isn't changed
+                let mut using_diagnostics = Vec::new();
+                if found_polyfills.iter().any(|p| {
+                  p.name() == EXPLICIT_RESOURCE_MANAGEMENT_POLYFILL_NAME
+                }) {
+                  let using_result =
+                    get_using_text_changes(&GetUsingTextChangesParams {
+                      specifier,
+                      program,
+                    });
+                  text_changes.extend(using_result.text_changes);
+                  using_diagnostics = using_result.diagnostics;
+                }
+                let import_exports_result = get_import_exports_text_changes(
+                  &GetImportExportsTextChangesParams {
+                    specifier,
+                    module_graph: &module_graph,
+                    mappings: &mappings,
+                    program,
+                    package_specifier_mappings: &all_package_specifier_mappings,
+                  },
+                )?;
+                text_changes.extend(import_exports_result.text_changes);
+                let mut diagnostics = ignore_line_indexes.diagnostics;
+                diagnostics.extend(import_exports_result.diagnostics);
+                diagnostics.extend(using_diagnostics);
+                let found_built_ins =
+                  find_unsupported_built_ins(&FindUnsupportedBuiltInsParams {
+                    program,
+                    unresolved_context: parsed_source.unresolved_context(),
+                    top_level_decls: &top_level_decls,
+                    built_ins: &unsupported_built_ins,
+                  });
+                for (built_in, range) in found_built_ins {
+                  diagnostics.push(
+                    Diagnostic::warning(
+                      DiagnosticCode::UnsupportedBuiltIn,
+                      Some(specifier.clone()),
+                      format!(
+                        concat!(
+                          "{} was added in Node.js {}, so it's missing in ",
+                          "some versions of the range {} and it can't be ",
+                          "polyfilled.",
+                        ),
+                        built_in.name,
+                        built_in.node_version,
+                        options.node_version_range.as_deref().unwrap_or(""),
+                      ),
+                    )
+                    .with_range(
+                      DiagnosticRange::from_source_range(
+                        range,
+                        parsed_source.text_info_lazy(),
+                      ),
+                    ),
+                  );
+                }
+                if let Some(deno_test_result) = deno_test_result {
+                  // the text in these ranges was replaced by the conversion
+                  text_changes.retain(|change| {
+                    !replaced_ranges.iter().any(|range| {
+                      range.start <= change.range.start
+                        && change.range.end <= range.end
+                    })
+                  });
+                  text_changes.extend(deno_test_result.text_changes);
+                  diagnostics.extend(deno_test_result.diagnostics);
+                }
 
-        apply_text_changes(parsed_source.text(), text_changes)
+                Ok(ModuleTransform {
+                  text_changes,
+                  polyfills: found_polyfills[found_polyfills_start..]
+                    .iter()
+                    .map(|p| p.name().to_string())
+                    .collect(),
+                  diagnostics,
+                  shim_usages: result.shim_usages,
+                })
+              })
+              .with_context(|| {
+                format!(
+                  "Issue getting text changes from {}",
+                  parsed_source.specifier()
+                )
+              })?;
+            if let (Some(cache), Some(cache_key)) =
+              (&options.transform_cache, &maybe_cache_key)
+            {
+              cache.set(cache_key, module_transform.to_bytes());
+            }
+            module_transform
+          }
+        };
+
+        diagnostics.extend(module_transform.diagnostics);
+        for usage in &module_transform.shim_usages {
+          if usage.name == "globalThis" {
+            env_context.used_shim_global_this = true;
+          } else {
+            env_context.used_shim_names.insert(usage.name.clone());
+          }
+        }
+        if !module_transform.shim_usages.is_empty() {
+          env_context.environment.shim_usage.push(FileShimUsage {
+            file_path: mappings.get_file_path(specifier).to_owned(),
+            specifier: specifier.clone(),
+            shims: module_transform.shim_usages,
+          });
+        }
+        env_context.add_found_polyfills(&module_transform.polyfills);
+        (parsed_source.text().clone(), module_transform.text_changes)
       }
       Module::Json(module) => {
-        format!("export default {};", strip_bom(&module.source).trim(),)
+        let text = module.source.clone();
+        let json_text = strip_bom(&text);
+        let start = text.len() - json_text.trim_start().len();
+        let end = start + json_text.trim().len();
+        let text_changes = vec![
+          TextChange {
+            range: 0..start,
+            new_text: "export default ".to_string(),
+          },
+          TextChange {
+            range: end..text.len(),
+            new_text: ";".to_string(),
+          },
+        ];
+        (text, text_changes)
       }
       Module::Node(_) | Module::Npm(_) | Module::External(_) => {
         bail!("Not implemented module kind for {}", module.specifier())
       }
     };
 
+    let source_map = if options.source_maps {
+      Some(create_source_map(specifier, &original_text, &text_changes)?)
+    } else {
+      None
+    };
+    let file_text = apply_text_changes(&original_text, text_changes);
     let file_path = mappings.get_file_path(specifier).to_owned();
     env_context.environment.files.push(OutputFile {
       file_path,
       file_text,
+      source_map,
     });
   }
 
   check_add_polyfill_file_to_environment(
     &mut main_env_context,
     mappings.get_file_path(&SYNTHETIC_SPECIFIERS.polyfills),
-  );
+    &mappings,
+  )?;
   check_add_polyfill_file_to_environment(
     &mut test_env_context,
     mappings.get_file_path(&SYNTHETIC_TEST_SPECIFIERS.polyfills),
-  );
+    &mappings,
+  )?;
   check_add_shim_file_to_environment(
     &mut main_env_context,
     mappings.get_file_path(&SYNTHETIC_SPECIFIERS.shims),
@@ -473,10 +1055,20 @@ pub async fn transform(options: TransformOptions) -> Result<TransformOutput> {
   Ok(TransformOutput {
     main: main_env_context.environment,
     test: test_env_context.environment,
-    warnings,
+    diagnostics,
+    lockfile_text: module_graph.updated_lockfile_text().cloned(),
   })
 }
 
+fn parse_node_version_range(text: &str) -> Result<VersionReq> {
+  let range = VersionReq::parse_from_npm(text)
+    .with_context(|| format!("Invalid Node.js version range: {}", text))?;
+  if range.tag().is_some() {
+    bail!("Invalid Node.js version range: {}", text);
+  }
+  Ok(range)
+}
+
 fn add_shim_types_packages_to_test_environment<'a>(
   test_output_env: &mut TransformOutputEnvironment,
   all_shims: impl Iterator<Item = &'a Shim>,
@@ -493,13 +1085,15 @@ fn add_shim_types_packages_to_test_environment<'a>(
 fn check_add_polyfill_file_to_environment(
   env_context: &mut EnvironmentContext,
   polyfill_file_path: &Path,
-) {
+  mappings: &Mappings,
+) -> Result<()> {
   if let Some(polyfill_file_text) =
     build_polyfill_file(&env_context.found_polyfills)
   {
     env_context.environment.files.push(OutputFile {
       file_path: polyfill_file_path.to_path_buf(),
       file_text: polyfill_file_text,
+      source_map: None,
     });
 
     for entry_point in env_context.environment.entry_points.iter() {
@@ -509,14 +1103,29 @@ fn check_add_polyfill_file_to_environment(
         .iter_mut()
         .find(|f| &f.file_path == entry_point)
       {
-        prepend_statement_to_text(
+        let maybe_original_text =
+          file.source_map.is_some().then(|| file.file_text.clone());
+        let text_change = prepend_statement_to_text(
           &file.file_path,
           &mut file.file_text,
           &format!(
             "import \"{}\";",
-            get_relative_specifier(&file.file_path, polyfill_file_path)
+            get_relative_specifier(
+              &file.file_path,
+              polyfill_file_path,
+              mappings.preserve_module_format_extensions(),
+            )
           ),
         );
+        if let (Some(source_map), Some(original_text)) =
+          (&file.source_map, maybe_original_text)
+        {
+          file.source_map = Some(shift_source_map_for_insertion(
+            source_map,
+            &original_text,
+            &text_change,
+          )?);
+        }
       }
     }
   }
@@ -532,6 +1141,7 @@ fn check_add_polyfill_file_to_environment(
       }
     }
   }
+  Ok(())
 }
 
 fn check_add_shim_file_to_environment(
@@ -539,44 +1149,70 @@ fn check_add_shim_file_to_environment(
   shim_file_path: &Path,
   mappings: &Mappings,
 ) {
-  if env_context.used_shim {
-    let shim_file_text =
-      build_shim_file(env_context.shims, shim_file_path, mappings);
-    env_context.environment.files.push(OutputFile {
-      file_path: shim_file_path.to_path_buf(),
-      file_text: shim_file_text,
-    });
+  // only include the names that were used
+  let used_shims = env_context
+    .shims
+    .iter()
+    .filter_map(|shim| {
+      let global_names = shim
+        .global_names()
+        .iter()
+        .filter(|n| {
+          env_context.used_shim_names.contains(&n.name)
+            // dntGlobalThis needs all the globals
+            || env_context.used_shim_global_this
+              && !n.is_member()
+              && !n.type_only
+        })
+        .collect::<Vec<_>>();
+      if global_names.is_empty() {
+        None
+      } else {
+        Some((shim, global_names))
+      }
+    })
+    .collect::<Vec<_>>();
+  if used_shims.is_empty() && !env_context.used_shim_global_this {
+    return;
+  }
 
-    for shim in env_context.shims.iter() {
-      if let Shim::Package(shim) = shim {
-        if !env_context
-          .environment
-          .dependencies
-          .iter()
-          .any(|d| d.name == shim.package.name)
-        {
-          if let Some(version) = &shim.package.version {
-            env_context.environment.dependencies.push(Dependency {
-              name: shim.package.name.to_string(),
-              version: version.clone(),
-              peer_dependency: shim.package.peer_dependency,
-            });
-          }
+  let shim_file_text = build_shim_file(&used_shims, shim_file_path, mappings);
+  env_context.environment.files.push(OutputFile {
+    file_path: shim_file_path.to_path_buf(),
+    file_text: shim_file_text,
+    source_map: None,
+  });
+
+  for (shim, _) in used_shims.iter() {
+    if let Shim::Package(shim) = shim {
+      if !env_context
+        .environment
+        .dependencies
+        .iter()
+        .any(|d| d.name == shim.package.name)
+      {
+        if let Some(version) = &shim.package.version {
+          env_context.environment.dependencies.push(Dependency {
+            name: shim.package.name.to_string(),
+            version: version.clone(),
+            peer_dependency: shim.package.peer_dependency,
+          });
         }
       }
     }
   }
 
   fn build_shim_file(
-    shims: &[Shim],
+    shims: &[(&Shim, Vec<&GlobalName>)],
     shim_file_path: &Path,
     mappings: &Mappings,
   ) -> String {
     fn get_specifer_text(n: &GlobalName) -> String {
-      let name_text = if let Some(export_name) = &n.export_name {
-        format!("{} as {}", export_name, n.name)
+      let binding_name = get_shim_binding_name(&n.name);
+      let name_text = if n.export_name() != binding_name {
+        format!("{} as {}", n.export_name(), binding_name)
       } else {
-        n.name.to_string()
+        binding_name
       };
       if n.type_only {
         format!("type {}", name_text)
@@ -595,7 +1231,11 @@ fn check_add_shim_file_to_environment(
         Shim::Module(shim) => match shim.maybe_specifier() {
           Some(specifier) => {
             let to = mappings.get_file_path(&specifier);
-            get_relative_specifier(shim_file_path, to)
+            get_relative_specifier(
+              shim_file_path,
+              to,
+              mappings.preserve_module_format_extensions(),
+            )
           }
           None => shim.module.clone(),
         },
@@ -603,9 +1243,8 @@ fn check_add_shim_file_to_environment(
     }
 
     let mut text = String::new();
-    for shim in shims.iter() {
-      let declaration_names = shim
-        .global_names()
+    for (shim, global_names) in shims.iter() {
+      let declaration_names = global_names
         .iter()
         .filter(|n| !n.type_only)
         .collect::<Vec<_>>();
@@ -616,7 +1255,7 @@ fn check_add_shim_file_to_environment(
           "import {{ {} }} from \"{}\";\n",
           declaration_names
             .into_iter()
-            .map(get_specifer_text)
+            .map(|n| get_specifer_text(n))
             .collect::<Vec<_>>()
             .join(", "),
           &module_specifier_text,
@@ -625,10 +1264,9 @@ fn check_add_shim_file_to_environment(
 
       text.push_str(&format!(
         "export {{ {} }} from \"{}\";\n",
-        shim
-          .global_names()
+        global_names
           .iter()
-          .map(get_specifer_text)
+          .map(|n| get_specifer_text(n))
           .collect::<Vec<_>>()
           .join(", "),
         &module_specifier_text,
@@ -640,8 +1278,9 @@ fn check_add_shim_file_to_environment(
     }
 
     text.push_str("const dntGlobals = {\n");
-    for global_name in shims.iter().flat_map(|s| s.global_names().iter()) {
-      if !global_name.type_only {
+    for global_name in shims.iter().flat_map(|(_, names)| names.iter()) {
+      // members aren't globals
+      if !global_name.type_only && !global_name.is_member() {
         text.push_str(&format!("  {},\n", global_name.name));
       }
     }
@@ -679,14 +1318,14 @@ fn get_dependencies(
   dependencies
 }
 
-fn get_declaration_warnings(specifiers: &Specifiers) -> Vec<String> {
-  let mut messages = Vec::new();
+fn get_declaration_diagnostics(specifiers: &Specifiers) -> Vec<Diagnostic> {
+  let mut diagnostics = Vec::new();
   for (code_specifier, d) in specifiers.types.iter() {
     if d.selected.referrer.scheme() == "file" {
       let local_referrers =
         d.ignored.iter().filter(|d| d.referrer.scheme() == "file");
       for dep in local_referrers {
-        messages.push(get_dep_warning(
+        diagnostics.push(get_dep_diagnostic(
           code_specifier,
           dep,
           &d.selected,
@@ -695,7 +1334,7 @@ fn get_declaration_warnings(specifiers: &Specifiers) -> Vec<String> {
       }
     } else {
       for dep in d.ignored.iter() {
-        messages.push(get_dep_warning(
+        diagnostics.push(get_dep_diagnostic(
           code_specifier,
           dep,
           &d.selected,
@@ -704,15 +1343,19 @@ fn get_declaration_warnings(specifiers: &Specifiers) -> Vec<String> {
       }
     }
   }
-  return messages;
+  return diagnostics;
 
-  fn get_dep_warning(
+  fn get_dep_diagnostic(
     code_specifier: &ModuleSpecifier,
     dep: &TypesDependency,
     selected_dep: &TypesDependency,
     post_message: &str,
-  ) -> String {
-    format!("Duplicate declaration file found for {}\n  Specified {} in {}\n  Selected {}\n  {}", code_specifier, dep.specifier, dep.referrer, selected_dep.specifier, post_message)
+  ) -> Diagnostic {
+    Diagnostic::warning(
+      DiagnosticCode::DuplicateDeclarationFile,
+      Some(dep.referrer.clone()),
+      format!("Duplicate declaration file found for {}\n  Specified {} in {}\n  Selected {}\n  {}", code_specifier, dep.specifier, dep.referrer, selected_dep.specifier, post_message),
+    )
   }
 }
 
@@ -720,6 +1363,27 @@ fn get_declaration_warnings(specifiers: &Specifiers) -> Vec<String> {
 mod test {
   use super::*;
 
+  #[cfg(feature = "serialization")]
+  #[test]
+  fn script_target_deserializes_from_name_or_number() {
+    fn parse(value: serde_json::Value) -> Result<ScriptTarget, String> {
+      serde_json::from_value(value).map_err(|err| err.to_string())
+    }
+
+    assert_eq!(parse(serde_json::json!("ES2024")), Ok(ScriptTarget::ES2024));
+    assert_eq!(parse(serde_json::json!("Latest")), Ok(ScriptTarget::Latest));
+    assert_eq!(parse(serde_json::json!(12)), Ok(ScriptTarget::ES2025));
+    assert_eq!(parse(serde_json::json!(99)), Ok(ScriptTarget::Latest));
+    assert_eq!(
+      parse(serde_json::json!("ES2030")),
+      Err("Unknown script target: ES2030".to_string())
+    );
+    assert_eq!(
+      parse(serde_json::json!(13)),
+      Err("Unknown script target: 13".to_string())
+    );
+  }
+
   #[test]
   fn test_npm_mapper() {
     fn parse(specifier: &str) -> Option<PackageMappedSpecifier> {
@@ -800,4 +1464,49 @@ mod test {
       })
     );
   }
+
+  #[test]
+  fn test_jsr_mapper() {
+    fn parse(specifier: &str) -> Option<PackageMappedSpecifier> {
+      let jsr_specifier = JsrPackageReqReference::from_str(specifier).ok()?;
+      Some(PackageMappedSpecifier::from_jsr_specifier(&jsr_specifier))
+    }
+
+    assert_eq!(
+      parse("jsr:@std/path"),
+      Some(PackageMappedSpecifier {
+        name: "@jsr/std__path".to_string(),
+        version: Some("*".to_string()),
+        sub_path: None,
+        peer_dependency: false
+      })
+    );
+    assert_eq!(
+      parse("jsr:@std/path@^1"),
+      Some(PackageMappedSpecifier {
+        name: "@jsr/std__path".to_string(),
+        version: Some("^1".to_string()),
+        sub_path: None,
+        peer_dependency: false
+      })
+    );
+    assert_eq!(
+      parse("jsr:@std/path@^1/join"),
+      Some(PackageMappedSpecifier {
+        name: "@jsr/std__path".to_string(),
+        version: Some("^1".to_string()),
+        sub_path: Some("join".to_string()),
+        peer_dependency: false
+      })
+    );
+    assert_eq!(
+      parse("jsr:@scope/name@1.2.3/sub/path"),
+      Some(PackageMappedSpecifier {
+        name: "@jsr/scope__name".to_string(),
+        version: Some("1.2.3".to_string()),
+        sub_path: Some("sub/path".to_string()),
+        peer_dependency: false
+      })
+    );
+  }
 }
@@ -13,6 +13,7 @@ use crate::utils::url_to_file_path;
 use crate::LoadResponse;
 use crate::Loader;
 
+#[derive(Default)]
 pub struct DefaultLoader {}
 
 impl DefaultLoader {
@@ -9,6 +9,8 @@ use std::rc::Rc;
 use anyhow::Result;
 use deno_ast::ModuleSpecifier;
 use deno_graph::source::CacheSetting;
+use deno_graph::source::DirEntry;
+use deno_graph::source::FileSystem;
 use deno_graph::source::LoaderChecksum;
 use futures::future;
 use futures::Future;
@@ -34,12 +36,22 @@ pub struct LoadResponse {
 }
 
 pub trait Loader {
+  /// Loads the specifier. When a checksum is provided, implementations
+  /// should verify the content with `LoaderChecksum::check_source`.
   fn load(
     &self,
     url: ModuleSpecifier,
     cache_setting: CacheSetting,
     maybe_checksum: Option<LoaderChecksum>,
   ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>>;
+
+  /// Gets the entries of a local directory. This is used to find the modules
+  /// that a dynamic import with a template literal could import (ex.
+  /// `` import(`./locales/${lang}.ts`) ``). Defaults to reading the file
+  /// system, except on Wasm where nothing is found.
+  fn read_dir(&self, dir_url: &ModuleSpecifier) -> Vec<DirEntry> {
+    <&dyn FileSystem>::default().read_dir(dir_url)
+  }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -74,6 +86,12 @@ impl<'a> SourceLoader<'a> {
   }
 }
 
+impl<'a> FileSystem for SourceLoader<'a> {
+  fn read_dir(&self, dir_url: &ModuleSpecifier) -> Vec<DirEntry> {
+    self.loader.read_dir(dir_url)
+  }
+}
+
 impl<'a> deno_graph::source::Loader for SourceLoader<'a> {
   fn load(
     &self,
@@ -0,0 +1,367 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use anyhow::anyhow;
+use anyhow::bail;
+use anyhow::Context;
+use anyhow::Result;
+use deno_ast::ModuleSpecifier;
+use deno_graph::source::CacheSetting;
+use deno_graph::source::LoaderChecksum;
+use deno_graph::source::Locker;
+use deno_semver::package::PackageNv;
+use serde_json::Map;
+use serde_json::Value;
+
+use crate::Loader;
+use crate::LockfileOptions;
+
+/// A deno.lock file that remote modules are verified against.
+pub struct Lockfile {
+  specifier: ModuleSpecifier,
+  value: Map<String, Value>,
+  has_changes: bool,
+}
+
+impl Lockfile {
+  pub async fn load(
+    options: LockfileOptions,
+    loader: &dyn Loader,
+  ) -> Result<Self> {
+    let text = match options.text {
+      Some(text) => Some(text),
+      None => loader
+        .load(options.specifier.clone(), CacheSetting::Use, None)
+        .await
+        .with_context(|| format!("Error loading {}", options.specifier))?
+        .map(|response| String::from_utf8(response.content))
+        .transpose()?,
+    };
+    match text {
+      Some(text) => Self::from_text(options.specifier, &text),
+      // the lockfile will be created
+      None if options.write => Ok(Self::new(options.specifier)),
+      None => Err(anyhow!("Could not find lockfile {}", options.specifier)),
+    }
+  }
+
+  pub fn new(specifier: ModuleSpecifier) -> Self {
+    let mut value = Map::new();
+    value.insert("version".to_string(), "4".into());
+    Self {
+      specifier,
+      value,
+      has_changes: false,
+    }
+  }
+
+  pub fn from_text(specifier: ModuleSpecifier, text: &str) -> Result<Self> {
+    let value: Value = serde_json::from_str(text)
+      .with_context(|| format!("Error parsing lockfile {}", specifier))?;
+    let Value::Object(value) = value else {
+      bail!("Expected an object in lockfile {}", specifier);
+    };
+    match value.get("version").and_then(|v| v.as_str()) {
+      Some("2" | "3" | "4" | "5") => {}
+      Some(version) => bail!(
+        "Unsupported lockfile version '{}' in {}. Upgrade dnt or regenerate the lockfile.",
+        version,
+        specifier
+      ),
+      None => bail!(
+        "Unsupported lockfile version in {}. Regenerate the lockfile with a newer version of Deno.",
+        specifier
+      ),
+    }
+    Ok(Self {
+      specifier,
+      value,
+      has_changes: false,
+    })
+  }
+
+  pub fn specifier(&self) -> &ModuleSpecifier {
+    &self.specifier
+  }
+
+  /// Gets if any checksums were added to the lockfile.
+  pub fn has_changes(&self) -> bool {
+    self.has_changes
+  }
+
+  pub fn to_text(&self) -> String {
+    let mut text =
+      serde_json::to_string_pretty(&Value::Object(self.value.clone())).unwrap();
+    text.push('\n');
+    text
+  }
+
+  /// Gets if the jsr packages are at the top level, which is the case
+  /// starting with version 4. Version 5 only changed the npm packages.
+  fn has_top_level_jsr(&self) -> bool {
+    matches!(
+      self.value.get("version").and_then(|v| v.as_str()),
+      Some("4" | "5")
+    )
+  }
+
+  fn remote(&self) -> Option<&Map<String, Value>> {
+    self.value.get("remote")?.as_object()
+  }
+
+  fn jsr_packages(&self) -> Option<&Map<String, Value>> {
+    if self.has_top_level_jsr() {
+      self.value.get("jsr")?.as_object()
+    } else {
+      self.value.get("packages")?.get("jsr")?.as_object()
+    }
+  }
+
+  fn jsr_packages_mut(&mut self) -> &mut Map<String, Value> {
+    let parent = if self.has_top_level_jsr() {
+      &mut self.value
+    } else {
+      get_or_insert_object(&mut self.value, "packages")
+    };
+    get_or_insert_object(parent, "jsr")
+  }
+}
+
+impl Locker for Lockfile {
+  fn get_remote_checksum(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Option<LoaderChecksum> {
+    let checksum = self.remote()?.get(specifier.as_str())?.as_str()?;
+    Some(LoaderChecksum::new(checksum.to_string()))
+  }
+
+  fn has_remote_checksum(&self, specifier: &ModuleSpecifier) -> bool {
+    self
+      .remote()
+      .map(|r| r.contains_key(specifier.as_str()))
+      .unwrap_or(false)
+  }
+
+  fn set_remote_checksum(
+    &mut self,
+    specifier: &ModuleSpecifier,
+    checksum: LoaderChecksum,
+  ) {
+    let remote = get_or_insert_object(&mut self.value, "remote");
+    remote.insert(specifier.to_string(), checksum.into_string().into());
+    sort_keys(remote);
+    self.has_changes = true;
+  }
+
+  fn get_pkg_manifest_checksum(
+    &self,
+    package_nv: &PackageNv,
+  ) -> Option<LoaderChecksum> {
+    let checksum = self
+      .jsr_packages()?
+      .get(&package_nv.to_string())?
+      .get("integrity")?
+      .as_str()?;
+    Some(LoaderChecksum::new(checksum.to_string()))
+  }
+
+  fn set_pkg_manifest_checksum(
+    &mut self,
+    package_nv: &PackageNv,
+    checksum: LoaderChecksum,
+  ) {
+    let packages = self.jsr_packages_mut();
+    let package = match packages
+      .entry(package_nv.to_string())
+      .or_insert_with(|| Value::Object(Default::default()))
+    {
+      Value::Object(package) => package,
+      value => {
+        *value = Value::Object(Default::default());
+        value.as_object_mut().unwrap()
+      }
+    };
+    package.insert("integrity".to_string(), checksum.into_string().into());
+    sort_keys(packages);
+    self.has_changes = true;
+  }
+}
+
+fn get_or_insert_object<'a>(
+  value: &'a mut Map<String, Value>,
+  key: &str,
+) -> &'a mut Map<String, Value> {
+  let value = value
+    .entry(key)
+    .or_insert_with(|| Value::Object(Default::default()));
+  if !value.is_object() {
+    *value = Value::Object(Default::default());
+  }
+  value.as_object_mut().unwrap()
+}
+
+fn sort_keys(map: &mut Map<String, Value>) {
+  let mut entries = std::mem::take(map).into_iter().collect::<Vec<_>>();
+  entries.sort_by(|a, b| a.0.cmp(&b.0));
+  map.extend(entries);
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn adds_remote_checksums() {
+    let lockfile_specifier =
+      ModuleSpecifier::parse("file:///deno.lock").unwrap();
+    let mut lockfile = Lockfile::from_text(
+      lockfile_specifier,
+      r#"{
+  "version": "3",
+  "remote": {
+    "https://deno.land/x/mod/b.ts": "b"
+  }
+}
+"#,
+    )
+    .unwrap();
+    let a = ModuleSpecifier::parse("https://deno.land/x/mod/a.ts").unwrap();
+    let b = ModuleSpecifier::parse("https://deno.land/x/mod/b.ts").unwrap();
+    assert!(!lockfile.has_remote_checksum(&a));
+    assert_eq!(
+      lockfile.get_remote_checksum(&b),
+      Some(LoaderChecksum::new("b".to_string()))
+    );
+    assert!(!lockfile.has_changes());
+
+    lockfile.set_remote_checksum(&a, LoaderChecksum::new("a".to_string()));
+    assert!(lockfile.has_changes());
+    assert_eq!(
+      lockfile.to_text(),
+      r#"{
+  "version": "3",
+  "remote": {
+    "https://deno.land/x/mod/a.ts": "a",
+    "https://deno.land/x/mod/b.ts": "b"
+  }
+}
+"#
+    );
+  }
+
+  #[test]
+  fn package_manifest_checksums() {
+    let nv = PackageNv::from_str("@std/path@1.0.0").unwrap();
+    let mut lockfile =
+      Lockfile::new(ModuleSpecifier::parse("file:///deno.lock").unwrap());
+    assert_eq!(lockfile.get_pkg_manifest_checksum(&nv), None);
+    lockfile
+      .set_pkg_manifest_checksum(&nv, LoaderChecksum::new("a".to_string()));
+    assert_eq!(
+      lockfile.get_pkg_manifest_checksum(&nv),
+      Some(LoaderChecksum::new("a".to_string()))
+    );
+    assert_eq!(
+      lockfile.to_text(),
+      r#"{
+  "version": "4",
+  "jsr": {
+    "@std/path@1.0.0": {
+      "integrity": "a"
+    }
+  }
+}
+"#
+    );
+  }
+
+  #[test]
+  fn v5_lockfile() {
+    let nv = PackageNv::from_str("@std/path@1.0.0").unwrap();
+    let other_nv = PackageNv::from_str("@std/fs@1.0.0").unwrap();
+    let remote =
+      ModuleSpecifier::parse("https://deno.land/x/mod/mod.ts").unwrap();
+    let mut lockfile = Lockfile::from_text(
+      ModuleSpecifier::parse("file:///deno.lock").unwrap(),
+      r#"{
+  "version": "5",
+  "specifiers": {
+    "jsr:@std/path@1": "1.0.0",
+    "npm:chalk@5": "5.4.1"
+  },
+  "jsr": {
+    "@std/path@1.0.0": {
+      "integrity": "a"
+    }
+  },
+  "npm": {
+    "chalk@5.4.1": {
+      "integrity": "sha512-chalk",
+      "bin": true
+    }
+  },
+  "remote": {
+    "https://deno.land/x/mod/mod.ts": "b"
+  }
+}
+"#,
+    )
+    .unwrap();
+    assert_eq!(
+      lockfile.get_pkg_manifest_checksum(&nv),
+      Some(LoaderChecksum::new("a".to_string()))
+    );
+    assert_eq!(
+      lockfile.get_remote_checksum(&remote),
+      Some(LoaderChecksum::new("b".to_string()))
+    );
+
+    lockfile.set_pkg_manifest_checksum(
+      &other_nv,
+      LoaderChecksum::new("c".to_string()),
+    );
+    assert_eq!(
+      lockfile.to_text(),
+      r#"{
+  "version": "5",
+  "specifiers": {
+    "jsr:@std/path@1": "1.0.0",
+    "npm:chalk@5": "5.4.1"
+  },
+  "jsr": {
+    "@std/fs@1.0.0": {
+      "integrity": "c"
+    },
+    "@std/path@1.0.0": {
+      "integrity": "a"
+    }
+  },
+  "npm": {
+    "chalk@5.4.1": {
+      "integrity": "sha512-chalk",
+      "bin": true
+    }
+  },
+  "remote": {
+    "https://deno.land/x/mod/mod.ts": "b"
+  }
+}
+"#
+    );
+  }
+
+  #[test]
+  fn errors_unsupported_version() {
+    let specifier = ModuleSpecifier::parse("file:///deno.lock").unwrap();
+    let err = Lockfile::from_text(specifier.clone(), r#"{ "version": "6" }"#)
+      .err()
+      .unwrap();
+    assert_eq!(
+      err.to_string(),
+      "Unsupported lockfile version '6' in file:///deno.lock. Upgrade dnt or regenerate the lockfile."
+    );
+    assert!(Lockfile::from_text(specifier, "{}").is_err());
+  }
+}
@@ -12,15 +12,21 @@ use anyhow::bail;
 use anyhow::Result;
 use deno_ast::MediaType;
 use deno_ast::ModuleSpecifier;
+use deno_graph::source::LoaderChecksum;
 use deno_graph::Module;
 use once_cell::sync::Lazy;
 
+use crate::graph::format_specifiers_for_message;
 use crate::graph::ModuleGraph;
 use crate::specifiers::Specifiers;
+use crate::utils::declaration_ext_len;
 use crate::utils::get_unique_path;
 use crate::utils::partition_by_root_specifiers;
+use crate::utils::path_with_stem_suffix;
 use crate::utils::url_to_file_path;
 use crate::utils::with_extension;
+use crate::OutsideRootDirMode;
+use crate::RemoteLayout;
 
 pub struct SyntheticSpecifiers {
   pub polyfills: ModuleSpecifier,
@@ -38,36 +44,71 @@ pub static SYNTHETIC_TEST_SPECIFIERS: Lazy<SyntheticSpecifiers> =
     shims: ModuleSpecifier::parse("dnt://_dnt.test_shims.ts").unwrap(),
   });
 
+pub struct MappingsOptions<'a> {
+  pub module_graph: &'a ModuleGraph,
+  pub specifiers: &'a Specifiers,
+  pub root_dir: Option<&'a ModuleSpecifier>,
+  pub outside_root_dir_mode: OutsideRootDirMode,
+  pub dedupe_remote_modules: bool,
+  pub remote_layout: RemoteLayout,
+  pub remote_path_max_length: Option<usize>,
+  pub preserve_module_format_extensions: bool,
+}
+
 pub struct Mappings {
   inner: HashMap<ModuleSpecifier, PathBuf>,
+  /// Remote modules that use the output file of an identical module.
+  duplicates: HashSet<ModuleSpecifier>,
+  preserve_module_format_extensions: bool,
 }
 
 impl Mappings {
-  pub fn new(
-    module_graph: &ModuleGraph,
-    specifiers: &Specifiers,
-  ) -> Result<Self> {
+  pub fn new(options: MappingsOptions) -> Result<Self> {
+    let MappingsOptions {
+      module_graph,
+      specifiers,
+      root_dir,
+      outside_root_dir_mode,
+      dedupe_remote_modules,
+      remote_layout,
+      remote_path_max_length,
+      preserve_module_format_extensions,
+    } = options;
     let mut mappings = HashMap::new();
     let mut mapped_filepaths_no_ext = HashSet::new();
-    let base_dir = get_base_dir(&specifiers.local)?;
+    let base_dir = match root_dir {
+      Some(root_dir) => {
+        if root_dir.scheme() != "file" {
+          bail!("Expected the root directory to be a file url: {}", root_dir);
+        }
+        url_to_file_path(root_dir)?
+      }
+      None => get_base_dir(&specifiers.local)?,
+    };
     let mut root_local_dirs = HashSet::new();
+    let mut outside_root_dir = Vec::new();
 
     for specifier in specifiers.local.iter() {
       let file_path = url_to_file_path(specifier)?;
-      let relative_file_path =
-        file_path.strip_prefix(&base_dir).map_err(|_| {
-          anyhow::anyhow!(
-            "Error stripping prefix of {} with base {}",
-            file_path.display(),
-            base_dir.display()
-          )
-        })?;
+      let relative_file_path = match file_path.strip_prefix(&base_dir) {
+        Ok(relative_file_path) => relative_file_path,
+        Err(_) if root_dir.is_some() => {
+          outside_root_dir.push((specifier, file_path));
+          continue;
+        }
+        Err(_) => bail!(
+          "Error stripping prefix of {} with base {}",
+          file_path.display(),
+          base_dir.display()
+        ),
+      };
       mappings.insert(
         specifier.clone(),
         get_mapped_file_path(
           MediaType::from_path(relative_file_path),
           relative_file_path,
           &mut mapped_filepaths_no_ext,
+          preserve_module_format_extensions,
         ),
       );
       if let Some(Component::Normal(first_dir)) =
@@ -77,11 +118,49 @@ impl Mappings {
       }
     }
 
+    if !outside_root_dir.is_empty() {
+      if outside_root_dir_mode == OutsideRootDirMode::Error {
+        bail!(
+          "The following local modules are outside the root directory {}:\n{}",
+          base_dir.display(),
+          format_specifiers_for_message(
+            outside_root_dir.iter().map(|(s, _)| *s).collect()
+          ),
+        );
+      }
+      let external_path =
+        get_unique_path(PathBuf::from("_external"), &mut root_local_dirs);
+      for (specifier, file_path) in outside_root_dir {
+        let relative_file_path =
+          get_path_from_shared_dir(&base_dir, &file_path);
+        mappings.insert(
+          specifier.clone(),
+          get_mapped_file_path(
+            MediaType::from_path(&file_path),
+            external_path.join(relative_file_path),
+            &mut mapped_filepaths_no_ext,
+            preserve_module_format_extensions,
+          ),
+        );
+      }
+    }
+
+    let duplicates = if dedupe_remote_modules {
+      get_remote_duplicates(module_graph, specifiers)
+    } else {
+      HashMap::new()
+    };
     let deps_path =
       get_unique_path(PathBuf::from("deps"), &mut root_local_dirs);
-    for (specifier, suggested_path) in
-      remote_specifiers_to_paths(specifiers.remote.iter())
-    {
+    for (specifier, suggested_path) in remote_specifiers_to_paths(
+      module_graph,
+      specifiers
+        .remote
+        .iter()
+        .filter(|s| !duplicates.contains_key(*s)),
+      remote_layout,
+      remote_path_max_length,
+    ) {
       let media_type = match module_graph.get(&specifier) {
         Module::Js(esm) => esm.media_type,
         Module::Json(json) => json.media_type,
@@ -95,11 +174,21 @@ impl Mappings {
           media_type,
           deps_path.join(suggested_path),
           &mut mapped_filepaths_no_ext,
+          preserve_module_format_extensions,
         ),
       );
     }
 
+    for (duplicate, original) in &duplicates {
+      let file_path = mappings.get(original).unwrap().clone();
+      mappings.insert(duplicate.clone(), file_path);
+    }
+
     for (code_specifier, d) in specifiers.types.iter() {
+      if duplicates.contains_key(code_specifier) {
+        // the original has the same declaration file
+        continue;
+      }
       let to = &d.selected.specifier;
       let file_path = mappings.get(code_specifier).unwrap_or_else(|| {
         panic!(
@@ -108,7 +197,11 @@ impl Mappings {
           to,
         );
       });
-      let new_file_path = with_extension(file_path, "d.ts");
+      let new_file_path = if preserve_module_format_extensions {
+        get_declaration_file_path(file_path)
+      } else {
+        with_extension(file_path, "d.ts")
+      };
       if let Some(past_path) = mappings.insert(to.clone(), new_file_path) {
         panic!(
           "dnt bug - Already had path {} in map when adding declaration file for {}. Adding: {}",
@@ -143,6 +236,7 @@ impl Mappings {
           MediaType::TypeScript,
           &specifier.to_string()["dnt://".len()..],
           mapped_filepaths_no_ext,
+          false,
         ),
       );
     }
@@ -168,28 +262,387 @@ impl Mappings {
       &SYNTHETIC_TEST_SPECIFIERS.shims,
     );
 
-    Ok(Mappings { inner: mappings })
+    Ok(Mappings {
+      inner: mappings,
+      duplicates: duplicates.into_keys().collect(),
+      preserve_module_format_extensions,
+    })
+  }
+
+  /// Gets if output files keep the `.mts`/`.cts` module format extensions,
+  /// which the specifiers importing them need to match.
+  pub fn preserve_module_format_extensions(&self) -> bool {
+    self.preserve_module_format_extensions
   }
 
   pub fn get_file_path(&self, specifier: &ModuleSpecifier) -> &PathBuf {
-    self.inner.get(specifier).unwrap_or_else(|| {
+    self.maybe_file_path(specifier).unwrap_or_else(|| {
       panic!("Could not find file path for specifier: {}", specifier,);
     })
   }
+
+  pub fn maybe_file_path(
+    &self,
+    specifier: &ModuleSpecifier,
+  ) -> Option<&PathBuf> {
+    self.inner.get(specifier)
+  }
+
+  /// Gets if the remote module is identical to another one, so its
+  /// output file is the other module's output file.
+  pub fn is_duplicate(&self, specifier: &ModuleSpecifier) -> bool {
+    self.duplicates.contains(specifier)
+  }
+}
+
+/// Gets the remote modules that are byte-identical to another remote
+/// module, mapped to the module whose output file they should use.
+///
+/// Identical modules are only merged when their dependencies resolve to
+/// the same or merged modules, because otherwise their output differs.
+fn get_remote_duplicates(
+  module_graph: &ModuleGraph,
+  specifiers: &Specifiers,
+) -> HashMap<ModuleSpecifier, ModuleSpecifier> {
+  struct Candidate<'a> {
+    specifier: &'a ModuleSpecifier,
+    /// Specifier text with the resolved code and type dependencies.
+    dependencies: Vec<(&'a str, Vec<ModuleSpecifier>)>,
+  }
+
+  let mut candidates = Vec::new();
+  let mut keys = Vec::new();
+  for specifier in &specifiers.remote {
+    let (source, media_type, dependencies) = match module_graph.get(specifier) {
+      Module::Js(module) => (
+        &module.source,
+        module.media_type,
+        module
+          .dependencies
+          .iter()
+          .map(|(text, dep)| {
+            let resolved = [&dep.maybe_code, &dep.maybe_type]
+              .into_iter()
+              .filter_map(|r| r.maybe_specifier())
+              .map(|s| module_graph.resolve(s))
+              .collect();
+            (text.as_str(), resolved)
+          })
+          .collect(),
+      ),
+      Module::Json(module) => (&module.source, module.media_type, Vec::new()),
+      Module::Npm(_) | Module::Node(_) | Module::External(_) => continue,
+    };
+    keys.push(format!(
+      "{}\0{}\0{}\0{}",
+      LoaderChecksum::gen(source.as_bytes()),
+      media_type,
+      specifiers.test_modules.contains(specifier),
+      specifiers
+        .types
+        .get(specifier)
+        .map(|d| d.selected.specifier.as_str())
+        .unwrap_or(""),
+    ));
+    candidates.push(Candidate {
+      specifier,
+      dependencies,
+    });
+  }
+
+  // split the groups of identical modules until every module in a
+  // group has dependencies in the same groups
+  let mut groups = get_group_indexes(keys);
+  loop {
+    let group_by_specifier = candidates
+      .iter()
+      .zip(&groups)
+      .map(|(c, group)| (c.specifier, *group))
+      .collect::<HashMap<_, _>>();
+    let keys = candidates
+      .iter()
+      .zip(&groups)
+      .map(|(candidate, group)| {
+        let mut key = group.to_string();
+        for (text, resolved) in &candidate.dependencies {
+          key.push('\0');
+          key.push_str(text);
+          for specifier in resolved {
+            key.push('\0');
+            match group_by_specifier.get(specifier) {
+              Some(group) => key.push_str(&format!("#{}", group)),
+              None => key.push_str(specifier.as_str()),
+            }
+          }
+        }
+        key
+      })
+      .collect::<Vec<_>>();
+    let new_groups = get_group_indexes(keys);
+    let group_count = |groups: &[usize]| groups.iter().max().map(|g| g + 1);
+    let is_stable = group_count(&new_groups) == group_count(&groups);
+    groups = new_groups;
+    if is_stable {
+      break;
+    }
+  }
+
+  // use the first specifier alphabetically so the output is stable
+  let mut originals: HashMap<usize, &ModuleSpecifier> = HashMap::new();
+  for (candidate, group) in candidates.iter().zip(&groups) {
+    let original = originals.entry(*group).or_insert(candidate.specifier);
+    if candidate.specifier < *original {
+      *original = candidate.specifier;
+    }
+  }
+  candidates
+    .iter()
+    .zip(&groups)
+    .filter_map(|(candidate, group)| {
+      let original = originals[group];
+      (candidate.specifier != original)
+        .then(|| (candidate.specifier.clone(), original.clone()))
+    })
+    .collect()
+}
+
+/// Gets the index of the group of each key, where equal keys are in
+/// the same group.
+fn get_group_indexes(keys: Vec<String>) -> Vec<usize> {
+  let mut indexes = HashMap::new();
+  keys
+    .into_iter()
+    .map(|key| {
+      let next_index = indexes.len();
+      *indexes.entry(key).or_insert(next_index)
+    })
+    .collect()
 }
 
 /// Takes a group of remote specifiers for the provided base directory
 /// and gets their output paths.
 fn remote_specifiers_to_paths<'a>(
+  module_graph: &ModuleGraph,
   specifiers: impl Iterator<Item = &'a ModuleSpecifier>,
+  layout: RemoteLayout,
+  max_length: Option<usize>,
 ) -> Vec<(ModuleSpecifier, PathBuf)> {
   // Use a constant value, because we want the code to be portable
   // when it's moved to another system.
   let win_path_max_len = 260;
   let approx_path_prefix_len = 80;
-  let max_length = win_path_max_len - approx_path_prefix_len;
+  let max_length =
+    max_length.unwrap_or(win_path_max_len - approx_path_prefix_len);
 
-  remote_specifiers_to_paths_with_truncation(specifiers, max_length)
+  match layout {
+    RemoteLayout::Mirror => {
+      remote_specifiers_to_paths_with_truncation(specifiers, max_length)
+    }
+    RemoteLayout::ContentHash => get_stable_paths(
+      specifiers.map(|specifier| {
+        let source = match module_graph.get(specifier) {
+          Module::Js(module) => &*module.source,
+          Module::Json(module) => &*module.source,
+          Module::Npm(_) | Module::Node(_) | Module::External(_) => "",
+        };
+        let path = content_hash_path(specifier, source);
+        (specifier.clone(), path)
+      }),
+      // the directory is already unique to the url
+      |_| false,
+      max_length,
+    ),
+    RemoteLayout::Flat => get_stable_paths(
+      specifiers.map(|specifier| (specifier.clone(), flat_path(specifier))),
+      is_ambiguous_flat_path,
+      max_length,
+    ),
+  }
+}
+
+/// Gets the path in a directory named with a short hash of the url
+/// and source (ex. `3f2a9c1b0d/mod.ts`).
+fn content_hash_path(specifier: &ModuleSpecifier, source: &str) -> PathBuf {
+  let hash =
+    LoaderChecksum::gen(format!("{}\0{}", specifier, source).as_bytes());
+  PathBuf::from(&hash[..10]).join(url_file_name(specifier))
+}
+
+/// Gets the path in a directory for the url's package, or the url's
+/// host when there isn't one (ex. `oak@v12.6.0_3f2a9c1b/mod.ts`).
+///
+/// The package directory is suffixed with a hash of the package's url
+/// since the same package can be on several hosts.
+fn flat_path(specifier: &ModuleSpecifier) -> PathBuf {
+  let segments = specifier
+    .path_segments()
+    .map(|s| s.filter(|s| !s.is_empty()).collect::<Vec<_>>())
+    .unwrap_or_default();
+  let (dir_name, sub_path) = match get_package_dir_name(&segments) {
+    Some((dir_name, end_index)) => {
+      let mut package_url = specifier.clone();
+      package_url.set_path(&format!("/{}/", segments[..end_index].join("/")));
+      package_url.set_query(None);
+      package_url.set_fragment(None);
+      let dir_name = format!("{}_{}", dir_name, get_url_hash(&package_url));
+      (dir_name, &segments[end_index..])
+    }
+    None => {
+      let mut root = specifier.clone();
+      root.set_path("/");
+      let dir_name = dir_name_for_root(&root).to_string_lossy().to_string();
+      (dir_name, segments.as_slice())
+    }
+  };
+  let mut path = PathBuf::from(sanitize_segment(&dir_name));
+  if sub_path.is_empty() {
+    return path.join("mod");
+  }
+  for segment in sub_path {
+    path.push(sanitize_segment(segment));
+  }
+  path
+}
+
+/// Gets the directory name of the package in the url's path segments
+/// along with the index of the segment after it.
+///
+/// Ex. `x/oak@v12.6.0/mod.ts` is `oak@v12.6.0`, `@std/path/1.0.0/mod.ts`
+/// (JSR) is `std__path@1.0.0`, and `@preact/signals@1.2.0/index.js` is
+/// `preact__signals@1.2.0`.
+fn get_package_dir_name(segments: &[&str]) -> Option<(String, usize)> {
+  if let [scope, name, version, _, ..] = segments {
+    if scope.starts_with('@')
+      && version.starts_with(|c: char| c.is_ascii_digit())
+    {
+      return Some((format!("{}__{}@{}", &scope[1..], name, version), 3));
+    }
+  }
+  let index = segments
+    .iter()
+    .position(|s| !s.starts_with('@') && s.contains('@'))?;
+  let name = match index.checked_sub(1).map(|i| segments[i]) {
+    Some(scope) if scope.starts_with('@') => {
+      format!("{}__{}", &scope[1..], segments[index])
+    }
+    _ => segments[index].to_string(),
+  };
+  Some((name, index + 1))
+}
+
+/// Makes the paths of a layout unique and fit within the max length.
+///
+/// Paths that could be the same as another url's are suffixed with a
+/// hash of their own url, so adding a module doesn't change the paths
+/// of the existing ones. When paths only differ by their extension, the
+/// `.ts` path (or otherwise the `.js` path) keeps its name.
+fn get_stable_paths(
+  items: impl Iterator<Item = (ModuleSpecifier, PathBuf)>,
+  is_ambiguous: impl Fn(&Path) -> bool,
+  max_length: usize,
+) -> Vec<(ModuleSpecifier, PathBuf)> {
+  let mut items = items
+    .map(|(specifier, mut path)| {
+      let url_hash = get_url_hash(&specifier);
+      // the query and fragment aren't in the path
+      if specifier.query().is_some()
+        || specifier.fragment().is_some()
+        || is_ambiguous(&path)
+      {
+        path = path_with_stem_suffix(&path, &format!("_{}", url_hash));
+      }
+      let path = fit_path_to_length(path, &url_hash, max_length);
+      (specifier, path)
+    })
+    .collect::<Vec<_>>();
+
+  let mut indexes_by_path: HashMap<String, Vec<usize>> = HashMap::new();
+  for (index, (_, path)) in items.iter().enumerate() {
+    // the extension is changed later, so it isn't part of the comparison
+    let key = without_known_ext(path).to_string_lossy().to_lowercase();
+    indexes_by_path.entry(key).or_default().push(index);
+  }
+  for mut indexes in indexes_by_path.into_values() {
+    if indexes.len() < 2 {
+      continue;
+    }
+    let rank = |index: &usize| get_ext_rank(&items[*index].1);
+    let min_rank = indexes.iter().map(rank).min().unwrap();
+    if indexes.iter().filter(|i| rank(i) == min_rank).count() == 1 {
+      indexes.retain(|i| rank(i) != min_rank);
+    }
+    for index in indexes {
+      let (specifier, path) = &mut items[index];
+      let suffix = format!("_{}", get_url_hash(specifier));
+      *path = path_with_stem_suffix(path, &suffix);
+    }
+  }
+
+  items
+}
+
+fn get_ext_rank(path: &Path) -> usize {
+  match MediaType::from_path(path) {
+    MediaType::TypeScript => 0,
+    MediaType::JavaScript => 1,
+    _ => 2,
+  }
+}
+
+/// Gets if a flat path could be the same as another url's path in the
+/// package on a case insensitive file system.
+fn is_ambiguous_flat_path(path: &Path) -> bool {
+  path.to_string_lossy().chars().any(|c| c.is_uppercase())
+}
+
+/// Shortens a path that's too long to the first directory and the file
+/// name, prefixed with the url hash so it's still unique.
+fn fit_path_to_length(
+  path: PathBuf,
+  url_hash: &str,
+  max_length: usize,
+) -> PathBuf {
+  if path.to_string_lossy().len() <= max_length {
+    return path;
+  }
+  let first_dir = match path.components().next() {
+    Some(Component::Normal(name)) => PathBuf::from(name),
+    _ => PathBuf::new(),
+  };
+  let file_name = format!(
+    "{}_{}",
+    url_hash,
+    path.file_name().unwrap().to_string_lossy()
+  );
+  let dir_len = first_dir.to_string_lossy().len() + 1;
+  let max_file_name_len =
+    std::cmp::max(max_length.saturating_sub(dir_len), url_hash.len() + 4);
+  if file_name.len() <= max_file_name_len {
+    return first_dir.join(file_name);
+  }
+  let file_name = match split_stem_and_ext(&file_name) {
+    Some((stem, ext)) => {
+      let stem_len = std::cmp::max(
+        url_hash.len(),
+        max_file_name_len.saturating_sub(ext.len() + 1),
+      );
+      format!("{}.{}", &stem[..std::cmp::min(stem.len(), stem_len)], ext)
+    }
+    None => file_name[..max_file_name_len].to_string(),
+  };
+  first_dir.join(file_name)
+}
+
+fn url_file_name(specifier: &ModuleSpecifier) -> String {
+  specifier
+    .path_segments()
+    .and_then(|s| s.filter(|s| !s.is_empty()).last())
+    .map(sanitize_segment)
+    .unwrap_or_else(|| "mod".to_string())
+}
+
+fn get_url_hash(specifier: &ModuleSpecifier) -> String {
+  LoaderChecksum::gen(specifier.as_str().as_bytes())[..8].to_string()
 }
 
 fn remote_specifiers_to_paths_with_truncation<'a>(
@@ -394,12 +847,25 @@ fn remote_specifiers_to_paths_with_truncation<'a>(
   result
 }
 
+/// Gets the path of the declaration file for a code file's output path,
+/// which has the same module format (ex. `mod.d.mts` for `mod.mjs`).
+fn get_declaration_file_path(file_path: &Path) -> PathBuf {
+  let extension = file_path
+    .extension()
+    .map(|ext| ext.to_string_lossy().to_lowercase());
+  let declaration_ext = match extension.as_deref() {
+    Some("mjs" | "mts") => "d.mts",
+    Some("cjs" | "cts") => "d.cts",
+    _ => "d.ts",
+  };
+  with_extension(file_path, declaration_ext)
+}
+
 fn split_stem_and_ext(path: &str) -> Option<(&str, &str)> {
-  let d_ts_ext = ".d.ts";
-  if path.to_lowercase().ends_with(d_ts_ext) {
+  if let Some(ext_len) = declaration_ext_len(path) {
     Some((
-      &path[..path.len() - d_ts_ext.len()],
-      &path[path.len() - (d_ts_ext.len() - 1)..],
+      &path[..path.len() - ext_len],
+      &path[path.len() - (ext_len - 1)..],
     ))
   } else {
     path
@@ -408,28 +874,31 @@ fn split_stem_and_ext(path: &str) -> Option<(&str, &str)> {
   }
 }
 
+fn without_known_ext(path: &Path) -> PathBuf {
+  // remove the extension if it's known
+  // Ex. url could be `https://deno.land/test/1.2.5`
+  // and we don't want to use `1.2`
+  let media_type = MediaType::from_path(path);
+  if media_type == MediaType::Unknown {
+    path.into()
+  } else {
+    with_extension(path, "")
+  }
+}
+
 fn get_mapped_file_path(
   media_type: MediaType,
   path: impl AsRef<Path>,
   mapped_filepaths_no_ext: &mut HashSet<String>,
+  preserve_module_format_extensions: bool,
 ) -> PathBuf {
-  fn without_ext(path: &Path) -> PathBuf {
-    // remove the extension if it's known
-    // Ex. url could be `https://deno.land/test/1.2.5`
-    // and we don't want to use `1.2`
-    let media_type = MediaType::from_path(path);
-    if media_type == MediaType::Unknown {
-      path.into()
-    } else {
-      with_extension(path, "")
-    }
-  }
-
   let filepath_no_ext =
-    get_unique_path(without_ext(path.as_ref()), mapped_filepaths_no_ext);
+    get_unique_path(without_known_ext(path.as_ref()), mapped_filepaths_no_ext);
   let extension = match media_type {
     MediaType::Json => "js",
-    MediaType::Mjs | MediaType::Mts => "js",
+    MediaType::Mjs | MediaType::Mts if !preserve_module_format_extensions => {
+      "js"
+    }
     _ => &media_type.as_ts_extension()[1..],
   };
   with_extension(
@@ -486,6 +955,22 @@ fn is_banned_segment_char(c: char) -> bool {
   matches!(c, '/' | '\\') || is_banned_path_char(c)
 }
 
+/// Gets the path of a file from the closest directory it shares with
+/// the provided directory.
+fn get_path_from_shared_dir(dir: &Path, file_path: &Path) -> PathBuf {
+  let shared_count = dir
+    .components()
+    .zip(file_path.components())
+    .take_while(|(a, b)| a == b)
+    .count();
+  file_path
+    .components()
+    .skip(shared_count)
+    // skip the prefix and root when on a different drive on windows
+    .filter(|c| matches!(c, Component::Normal(_)))
+    .collect()
+}
+
 fn get_base_dir(specifiers: &[ModuleSpecifier]) -> Result<PathBuf> {
   if specifiers.is_empty() {
     bail!("Did not find any local files. Specifying only remote files is not currently supported.");
@@ -557,6 +1042,23 @@ mod test {
     }
   }
 
+  #[test]
+  fn should_get_path_from_shared_dir() {
+    run_test(
+      "/project/src",
+      "/project/fixtures/data.ts",
+      "fixtures/data.ts",
+    );
+    run_test("/project/src", "/other/mod.ts", "other/mod.ts");
+    run_test("/project/src/", "/project/src.ts", "src.ts");
+
+    fn run_test(dir: &str, file_path: &str, expected: &str) {
+      let result =
+        get_path_from_shared_dir(Path::new(dir), Path::new(file_path));
+      assert_eq!(result, PathBuf::from(expected));
+    }
+  }
+
   #[test]
   fn test_remote_specifiers_to_paths() {
     run_remote_specifiers_to_paths_test(
@@ -685,12 +1187,240 @@ mod test {
     assert_eq!(result_as_strs, expected);
   }
 
+  #[test]
+  fn test_flat_paths() {
+    fn dir(name: &str, package_url: &str) -> String {
+      format!("{}_{}", name, url_hash(package_url))
+    }
+
+    let oak = dir("oak@v12.6.0", "https://deno.land/x/oak@v12.6.0/");
+    let std = dir("std@0.200.0", "https://deno.land/std@0.200.0/");
+    let x_std = dir("std@0.200.0", "https://deno.land/x/std@0.200.0/");
+    let std_path = dir("std__path@1.0.0", "https://jsr.io/@std/path/1.0.0/");
+    let signals = dir(
+      "preact__signals@1.2.0",
+      "https://esm.sh/@preact/signals@1.2.0/",
+    );
+    let preact = dir("preact@10.0.0", "https://esm.sh/preact@10.0.0/");
+    let unpkg_preact = dir("preact@10.0.0", "https://unpkg.com/preact@10.0.0/");
+    run_stable_paths_test(
+      &[
+        "https://deno.land/x/oak@v12.6.0/mod.ts",
+        "https://deno.land/x/oak@v12.6.0/mod.js",
+        "https://deno.land/std@0.200.0/path/mod.ts",
+        "https://deno.land/x/std@0.200.0/path/mod.ts",
+        "https://jsr.io/@std/path/1.0.0/mod.ts",
+        "https://esm.sh/@preact/signals@1.2.0/index.js",
+        "https://esm.sh/preact@10.0.0",
+        "https://esm.sh/preact@10.0.0/hooks.js",
+        "https://unpkg.com/preact@10.0.0/hooks.js",
+        "http://localhost:8000/lib/mod.ts",
+        "http://localhost:8000/lib/mod.ts?query",
+        "http://localhost:8000/lib/Other.ts",
+      ],
+      flat_path,
+      &[
+        (
+          "http://localhost:8000/lib/Other.ts",
+          &format!(
+            "localhost_8000/lib/Other_{}.ts",
+            url_hash("http://localhost:8000/lib/Other.ts")
+          ),
+        ),
+        (
+          "http://localhost:8000/lib/mod.ts",
+          "localhost_8000/lib/mod.ts",
+        ),
+        (
+          "http://localhost:8000/lib/mod.ts?query",
+          &format!(
+            "localhost_8000/lib/mod_{}.ts",
+            url_hash("http://localhost:8000/lib/mod.ts?query")
+          ),
+        ),
+        (
+          "https://deno.land/std@0.200.0/path/mod.ts",
+          &format!("{}/path/mod.ts", std),
+        ),
+        (
+          "https://deno.land/x/oak@v12.6.0/mod.js",
+          &format!(
+            "{}/mod_{}.js",
+            oak,
+            url_hash("https://deno.land/x/oak@v12.6.0/mod.js")
+          ),
+        ),
+        (
+          "https://deno.land/x/oak@v12.6.0/mod.ts",
+          &format!("{}/mod.ts", oak),
+        ),
+        (
+          "https://deno.land/x/std@0.200.0/path/mod.ts",
+          &format!("{}/path/mod.ts", x_std),
+        ),
+        (
+          "https://esm.sh/@preact/signals@1.2.0/index.js",
+          &format!("{}/index.js", signals),
+        ),
+        ("https://esm.sh/preact@10.0.0", &format!("{}/mod", preact)),
+        (
+          "https://esm.sh/preact@10.0.0/hooks.js",
+          &format!("{}/hooks.js", preact),
+        ),
+        (
+          "https://jsr.io/@std/path/1.0.0/mod.ts",
+          &format!("{}/mod.ts", std_path),
+        ),
+        (
+          "https://unpkg.com/preact@10.0.0/hooks.js",
+          &format!("{}/hooks.js", unpkg_preact),
+        ),
+      ],
+      260,
+    );
+  }
+
+  #[test]
+  fn test_content_hash_paths() {
+    let specifier =
+      ModuleSpecifier::parse("https://deno.land/x/a/mod.ts").unwrap();
+    let path = content_hash_path(&specifier, "export {};");
+    assert_eq!(path.file_name().unwrap(), "mod.ts");
+    assert_eq!(path.parent().unwrap().to_string_lossy().len(), 10);
+    // changes with the source
+    assert_ne!(path, content_hash_path(&specifier, "export const a = 1;"));
+    let root = ModuleSpecifier::parse("https://deno.land/").unwrap();
+    assert_eq!(content_hash_path(&root, "").file_name().unwrap(), "mod");
+  }
+
+  #[test]
+  fn test_stable_paths_max_length() {
+    let long_url =
+      "https://deno.land/x/pkg@1.0.0/a/b/c/d/e/f/long_file_name.ts";
+    let hash = url_hash(long_url);
+    let dir =
+      format!("pkg@1.0.0_{}", url_hash("https://deno.land/x/pkg@1.0.0/"));
+    run_stable_paths_test(
+      &[long_url, "https://deno.land/x/pkg@1.0.0/short.ts"],
+      flat_path,
+      &[
+        (long_url, &format!("{}/{}_long_file_name.ts", dir, hash)),
+        (
+          "https://deno.land/x/pkg@1.0.0/short.ts",
+          &format!("{}/short.ts", dir),
+        ),
+      ],
+      45,
+    );
+    run_stable_paths_test(
+      &[long_url],
+      flat_path,
+      &[(long_url, &format!("{}/{}_long_f.ts", dir, hash))],
+      37,
+    );
+  }
+
+  #[test]
+  fn test_stable_paths_when_adding_modules() {
+    let existing = [
+      "https://deno.land/x/oak@v12.6.0/mod.ts",
+      "https://deno.land/x/std@0.200.0/path/mod.ts",
+      "https://esm.sh/preact@10.0.0/hooks.js",
+      "http://localhost/mod.ts?a",
+      "http://localhost/folder/file.json",
+    ];
+    let added = [
+      // sorts before the existing module with the same path
+      "https://deno.land/std@0.200.0/path/mod.ts",
+      "https://deno.land/x/oak@v12.6.0/mod.js",
+      "https://deno.land/x/oak@v12.6.0/Mod.ts",
+      "https://unpkg.com/preact@10.0.0/hooks.js",
+      "http://localhost/FOLDER/file.json",
+      // sorts after the existing module with the same path
+      "https://deno.land/x/oak@v12.6.0/mod.tsx",
+      "http://localhost/mod.ts",
+      "http://localhost/mod.ts?b",
+      "https://deno.land/x/oak@v12.6.0/a/b/c/d/e/f/g/h/i/j/k/l/m/mod.ts",
+    ];
+    let get_paths = |urls: &[&str]| {
+      let specifiers = urls
+        .iter()
+        .map(|s| ModuleSpecifier::parse(s).unwrap())
+        .collect::<Vec<_>>();
+      get_stable_paths(
+        specifiers.iter().map(|s| (s.clone(), flat_path(s))),
+        is_ambiguous_flat_path,
+        60,
+      )
+      .into_iter()
+      .collect::<HashMap<_, _>>()
+    };
+    let before = get_paths(&existing);
+    let after =
+      get_paths(&existing.iter().chain(&added).copied().collect::<Vec<_>>());
+    for (specifier, path) in before {
+      assert_eq!(after[&specifier], path);
+    }
+    assert_eq!(after.values().collect::<HashSet<_>>().len(), after.len());
+  }
+
+  fn url_hash(url: &str) -> String {
+    get_url_hash(&ModuleSpecifier::parse(url).unwrap())
+  }
+
+  fn run_stable_paths_test(
+    specifiers: &[&str],
+    get_path: impl Fn(&ModuleSpecifier) -> PathBuf,
+    expected: &[(&str, &str)],
+    max_length: usize,
+  ) {
+    let specifiers = specifiers
+      .iter()
+      .map(|s| ModuleSpecifier::parse(s).unwrap())
+      .collect::<Vec<_>>();
+    let result = get_stable_paths(
+      specifiers.iter().map(|s| (s.clone(), get_path(s))),
+      is_ambiguous_flat_path,
+      max_length,
+    );
+    let mut result = result
+      .into_iter()
+      .map(|(url, path)| {
+        (url.to_string(), path.to_string_lossy().replace('\\', "/"))
+      })
+      .collect::<Vec<_>>();
+    result.sort();
+    let expected = expected
+      .iter()
+      .map(|(url, path)| (url.to_string(), path.to_string()))
+      .collect::<Vec<_>>();
+    assert_eq!(result, expected);
+  }
+
+  #[test]
+  fn test_get_declaration_file_path() {
+    run_test("deps/mod.js", "deps/mod.d.ts");
+    run_test("deps/mod.ts", "deps/mod.d.ts");
+    run_test("deps/mod.mjs", "deps/mod.d.mts");
+    run_test("deps/mod.MTS", "deps/mod.d.mts");
+    run_test("deps/mod.cjs", "deps/mod.d.cts");
+    run_test("deps/mod.cts", "deps/mod.d.cts");
+
+    fn run_test(file_path: &str, expected: &str) {
+      assert_eq!(
+        get_declaration_file_path(Path::new(file_path)),
+        PathBuf::from(expected)
+      );
+    }
+  }
+
   #[test]
   fn test_split_stem_and_ext() {
     assert_eq!(split_stem_and_ext("test.ts"), Some(("test", "ts")));
     assert_eq!(split_stem_and_ext("test.TS"), Some(("test", "TS")));
     assert_eq!(split_stem_and_ext("test.D.TS"), Some(("test", "D.TS")));
     assert_eq!(split_stem_and_ext("test.d.ts"), Some(("test", "d.ts")));
+    assert_eq!(split_stem_and_ext("test.d.mts"), Some(("test", "d.mts")));
     assert_eq!(
       split_stem_and_ext("test.other.json"),
       Some(("test.other", "json"))
@@ -0,0 +1,64 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::Callee;
+use deno_ast::view::Expr;
+use deno_ast::view::Node;
+use deno_ast::SourceRanged;
+
+use super::Polyfill;
+use super::PolyfillVisitContext;
+use crate::ScriptTarget;
+
+pub struct ArrayChangeByCopyPolyfill;
+
+impl Polyfill for ArrayChangeByCopyPolyfill {
+  fn name(&self) -> &'static str {
+    "array-change-by-copy"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2023 as u32)
+  }
+
+  fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
+    if let Node::CallExpr(expr) = node {
+      if let Callee::Expr(Expr::Member(callee)) = expr.callee {
+        let arg_count = expr.args.len();
+        return match callee.prop.text_fast(context.program) {
+          "toReversed" => arg_count == 0,
+          "toSorted" => arg_count <= 1,
+          "toSpliced" => true,
+          "with" => arg_count == 2,
+          _ => false,
+        };
+      }
+    }
+    false
+  }
+
+  fn get_file_text(&self) -> &'static str {
+    include_str!("./scripts/es2023.array-changeByCopy.ts")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::polyfills::PolyfillTester;
+
+  #[test]
+  pub fn finds_when_matches() {
+    let tester =
+      PolyfillTester::new(Box::new(|| Box::new(ArrayChangeByCopyPolyfill)));
+    assert_eq!(tester.matches("[].toReversed()"), true);
+    assert_eq!(tester.matches("[].toReversed(1)"), false);
+    assert_eq!(tester.matches("[].toSorted()"), true);
+    assert_eq!(tester.matches("[].toSorted((a, b) => a - b)"), true);
+    assert_eq!(tester.matches("[].toSorted(a, b)"), false);
+    assert_eq!(tester.matches("[].toSpliced(0, 1, 'a')"), true);
+    assert_eq!(tester.matches("new Uint8Array(1).with(0, 1)"), true);
+    assert_eq!(tester.matches("[].with(0)"), false);
+    assert_eq!(tester.matches("[].toReversed"), false);
+    assert_eq!(tester.matches("[].reverse()"), false);
+  }
+}
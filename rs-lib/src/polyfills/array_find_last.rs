@@ -12,8 +12,12 @@ use crate::ScriptTarget;
 pub struct ArrayFindLastPolyfill;
 
 impl Polyfill for ArrayFindLastPolyfill {
-  fn use_for_target(&self, _target: ScriptTarget) -> bool {
-    true
+  fn name(&self) -> &'static str {
+    "array-find-last"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2023 as u32)
   }
 
   fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
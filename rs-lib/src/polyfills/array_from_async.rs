@@ -9,8 +9,12 @@ use crate::ScriptTarget;
 pub struct ArrayFromAsyncPolyfill;
 
 impl Polyfill for ArrayFromAsyncPolyfill {
-  fn use_for_target(&self, _target: ScriptTarget) -> bool {
-    true
+  fn name(&self) -> &'static str {
+    "array-from-async"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2024 as u32)
   }
 
   fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
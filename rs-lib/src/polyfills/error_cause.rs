@@ -10,8 +10,12 @@ use crate::ScriptTarget;
 pub struct ErrorCausePolyfill;
 
 impl Polyfill for ErrorCausePolyfill {
-  fn use_for_target(&self, _target: ScriptTarget) -> bool {
-    true
+  fn name(&self) -> &'static str {
+    "error-cause"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2022 as u32)
   }
 
   fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
@@ -0,0 +1,77 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::Node;
+use deno_ast::SourceRanged;
+
+use super::Polyfill;
+use super::PolyfillVisitContext;
+use crate::analyze::is_in_type;
+use crate::ScriptTarget;
+
+/// Name of the polyfill. The `using` declarations in a module are
+/// downleveled when the module uses it.
+pub const EXPLICIT_RESOURCE_MANAGEMENT_POLYFILL_NAME: &str =
+  "explicit-resource-management";
+
+pub struct ExplicitResourceManagementPolyfill;
+
+impl Polyfill for ExplicitResourceManagementPolyfill {
+  fn name(&self) -> &'static str {
+    EXPLICIT_RESOURCE_MANAGEMENT_POLYFILL_NAME
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    // not in an edition yet, so only supported by the latest target
+    target != ScriptTarget::Latest
+  }
+
+  fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
+    match node {
+      Node::UsingDecl(_) => true,
+      Node::Ident(ident) => {
+        let name = ident.text_fast(context.program);
+        matches!(
+          name,
+          "DisposableStack" | "AsyncDisposableStack" | "SuppressedError"
+        ) && ident.ctxt() == context.unresolved_context
+          && !context.top_level_decls.contains(name)
+          && !is_in_type(node)
+      }
+      _ => {
+        context.has_global_property_access(node, "Symbol", "dispose")
+          || context.has_global_property_access(node, "Symbol", "asyncDispose")
+      }
+    }
+  }
+
+  fn get_file_text(&self) -> &'static str {
+    include_str!("./scripts/esnext.disposable.ts")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::polyfills::PolyfillTester;
+
+  #[test]
+  pub fn finds_when_matches() {
+    let tester = PolyfillTester::new(Box::new(|| {
+      Box::new(ExplicitResourceManagementPolyfill)
+    }));
+    assert_eq!(tester.matches("{ using file = open(); }"), true);
+    assert_eq!(tester.matches("await using conn = connect();"), true);
+    assert_eq!(tester.matches("class A { [Symbol.dispose]() {} }"), true);
+    assert_eq!(tester.matches("obj[Symbol.asyncDispose]();"), true);
+    assert_eq!(tester.matches("new DisposableStack();"), true);
+    assert_eq!(tester.matches("new AsyncDisposableStack();"), true);
+    assert_eq!(tester.matches("new SuppressedError(a, b);"), true);
+    assert_eq!(
+      tester.matches("class DisposableStack {} new DisposableStack();"),
+      false
+    );
+    assert_eq!(tester.matches("let stack: DisposableStack;"), false);
+    assert_eq!(tester.matches("Symbol.iterator;"), false);
+    assert_eq!(tester.matches("const using = 1;"), false);
+  }
+}
@@ -0,0 +1,45 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::Node;
+
+use super::Polyfill;
+use super::PolyfillVisitContext;
+use crate::ScriptTarget;
+
+pub struct GroupByPolyfill;
+
+impl Polyfill for GroupByPolyfill {
+  fn name(&self) -> &'static str {
+    "group-by"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2024 as u32)
+  }
+
+  fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
+    context.has_global_property_access(node, "Object", "groupBy")
+      || context.has_global_property_access(node, "Map", "groupBy")
+  }
+
+  fn get_file_text(&self) -> &'static str {
+    include_str!("./scripts/es2024.groupBy.ts")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::polyfills::PolyfillTester;
+
+  #[test]
+  pub fn finds_when_matches() {
+    let tester = PolyfillTester::new(Box::new(|| Box::new(GroupByPolyfill)));
+    assert_eq!(tester.matches("Object.groupBy(items, (i) => i.kind)"), true);
+    assert_eq!(tester.matches("Map.groupBy(items, (i) => i.kind)"), true);
+    assert_eq!(tester.matches("const { groupBy } = Object;"), true);
+    assert_eq!(tester.matches("class Map {} Map.groupBy(items, f)"), false);
+    assert_eq!(tester.matches("Other.groupBy(items, f)"), false);
+    assert_eq!(tester.matches("Object.groupBy2(items, f)"), false);
+  }
+}
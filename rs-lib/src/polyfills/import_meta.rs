@@ -1,6 +1,7 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
 use deno_ast::view::Expr;
+use deno_ast::view::MemberExpr;
 use deno_ast::view::Node;
 use deno_ast::SourceRanged;
 
@@ -8,9 +9,17 @@ use super::Polyfill;
 use super::PolyfillVisitContext;
 use crate::ScriptTarget;
 
-pub struct ImportMetaPolyfill;
+pub struct ImportMetaPolyfill {
+  /// Whether `import.meta.main` and `import.meta.resolve(specifier)` are
+  /// rewritten, so only the other uses need the declarations.
+  pub is_rewritten: bool,
+}
 
 impl Polyfill for ImportMetaPolyfill {
+  fn name(&self) -> &'static str {
+    "import-meta"
+  }
+
   fn use_for_target(&self, _target: ScriptTarget) -> bool {
     true
   }
@@ -18,9 +27,10 @@ impl Polyfill for ImportMetaPolyfill {
   fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
     if let Node::MemberExpr(expr) = node {
       if let Expr::MetaProp(_meta) = expr.obj {
-        let text = expr.prop.text_fast(context.program);
-        if text == "main" || text == "resolve" {
-          return true;
+        match expr.prop.text_fast(context.program) {
+          "main" => return !self.is_rewritten,
+          "resolve" => return !self.is_rewritten || !is_resolve_call(expr),
+          _ => {}
         }
       }
     }
@@ -31,3 +41,14 @@ impl Polyfill for ImportMetaPolyfill {
     include_str!("./scripts/deno.import-meta.ts")
   }
 }
+
+/// Gets if the member expression is the callee of a call with a single
+/// argument (ex. `import.meta.resolve(specifier)`).
+fn is_resolve_call(expr: &MemberExpr) -> bool {
+  match expr.parent() {
+    Node::CallExpr(call_expr) => {
+      call_expr.args.len() == 1 && call_expr.callee.range() == expr.range()
+    }
+    _ => false,
+  }
+}
@@ -10,19 +10,35 @@ use deno_ast::view::Pat;
 use deno_ast::view::Program;
 use deno_ast::view::PropName;
 use deno_ast::SourceRanged;
+use deno_semver::VersionReq;
 
 use crate::Dependency;
+use crate::ModuleKind;
 use crate::ScriptTarget;
 
+mod array_change_by_copy;
 mod array_find_last;
 mod array_from_async;
 mod error_cause;
+mod explicit_resource_management;
+mod group_by;
 mod import_meta;
+mod node_compat;
 mod object_has_own;
+mod promise_try;
 mod promise_with_resolvers;
+mod regexp_escape;
+mod set_methods;
 mod string_replace_all;
+mod uint8array_base64;
+
+pub use explicit_resource_management::EXPLICIT_RESOURCE_MANAGEMENT_POLYFILL_NAME;
+pub use node_compat::unsupported_built_ins_for_range;
+pub use node_compat::NodeBuiltIn;
 
 pub trait Polyfill {
+  /// Unique name of the polyfill.
+  fn name(&self) -> &'static str;
   fn use_for_target(&self, target: ScriptTarget) -> bool;
   fn visit_node(
     &self,
@@ -95,22 +111,42 @@ impl<'a, 'b> PolyfillVisitContext<'a, 'b> {
   }
 }
 
-pub fn polyfills_for_target(target: ScriptTarget) -> Vec<Box<dyn Polyfill>> {
-  all_polyfills()
+/// Gets the polyfills to search for. When a Node.js version range is
+/// provided, it's used instead of the target.
+pub fn polyfills_for_target(
+  target: ScriptTarget,
+  node_version_range: Option<&VersionReq>,
+  module_kind: Option<ModuleKind>,
+) -> Vec<Box<dyn Polyfill>> {
+  all_polyfills(module_kind)
     .into_iter()
-    .filter(|p| p.use_for_target(target))
+    .filter(|p| match node_version_range {
+      Some(range) => node_compat::is_polyfill_needed_for_range(p.name(), range),
+      None => p.use_for_target(target),
+    })
     .collect()
 }
 
-fn all_polyfills() -> Vec<Box<dyn Polyfill>> {
+fn all_polyfills(module_kind: Option<ModuleKind>) -> Vec<Box<dyn Polyfill>> {
   vec![
     Box::new(object_has_own::ObjectHasOwnPolyfill),
     Box::new(error_cause::ErrorCausePolyfill),
     Box::new(string_replace_all::StringReplaceAllPolyfill),
     Box::new(array_find_last::ArrayFindLastPolyfill),
     Box::new(array_from_async::ArrayFromAsyncPolyfill),
-    Box::new(import_meta::ImportMetaPolyfill),
+    Box::new(import_meta::ImportMetaPolyfill {
+      // `import.meta.main` and `import.meta.resolve(specifier)` are
+      // rewritten when there's a module kind
+      is_rewritten: module_kind.is_some(),
+    }),
     Box::new(promise_with_resolvers::PromiseWithResolversPolyfill),
+    Box::new(array_change_by_copy::ArrayChangeByCopyPolyfill),
+    Box::new(set_methods::SetMethodsPolyfill),
+    Box::new(group_by::GroupByPolyfill),
+    Box::new(uint8array_base64::Uint8ArrayBase64Polyfill),
+    Box::new(promise_try::PromiseTryPolyfill),
+    Box::new(regexp_escape::RegExpEscapePolyfill),
+    Box::new(explicit_resource_management::ExplicitResourceManagementPolyfill),
   ]
 }
 
@@ -119,13 +155,22 @@ pub fn build_polyfill_file(polyfills: &[Box<dyn Polyfill>]) -> Option<String> {
     return None;
   }
 
+  // triple-slash directives are only used at the top of the file
+  let mut directives_text = String::new();
   let mut file_text = String::new();
 
   for polyfill in polyfills {
-    file_text.push_str(polyfill.get_file_text());
+    let mut text = polyfill.get_file_text();
+    while text.starts_with("/// <reference") {
+      let end = text.find('\n').map(|i| i + 1).unwrap_or(text.len());
+      directives_text.push_str(&text[..end]);
+      text = &text[end..];
+    }
+    file_text.push_str(text);
   }
 
-  Some(file_text)
+  directives_text.push_str(&file_text);
+  Some(directives_text)
 }
 
 #[cfg(test)]
@@ -175,3 +220,58 @@ impl PolyfillTester {
     })
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn get_names(target: ScriptTarget) -> Vec<&'static str> {
+    polyfills_for_target(target, None, None)
+      .iter()
+      .map(|p| p.name())
+      .collect()
+  }
+
+  #[test]
+  fn polyfills_for_edition_targets() {
+    let es2023 = get_names(ScriptTarget::ES2023);
+    assert!(es2023.contains(&"promise-with-resolvers"));
+    assert!(es2023.contains(&"array-from-async"));
+    assert!(es2023.contains(&"group-by"));
+    assert!(!es2023.contains(&"array-find-last"));
+    assert!(!es2023.contains(&"array-change-by-copy"));
+
+    let es2024 = get_names(ScriptTarget::ES2024);
+    assert!(!es2024.contains(&"promise-with-resolvers"));
+    assert!(!es2024.contains(&"array-from-async"));
+    assert!(!es2024.contains(&"group-by"));
+    assert!(es2024.contains(&"set-methods"));
+    assert!(es2024.contains(&"promise-try"));
+    assert!(es2024.contains(&"regexp-escape"));
+
+    let es2025 = get_names(ScriptTarget::ES2025);
+    assert!(!es2025.contains(&"set-methods"));
+    assert!(!es2025.contains(&"promise-try"));
+    assert!(!es2025.contains(&"regexp-escape"));
+    assert!(es2025.contains(&"uint8array-base64"));
+    assert!(es2025.contains(&"explicit-resource-management"));
+
+    let latest = get_names(ScriptTarget::Latest);
+    assert!(!latest.contains(&"explicit-resource-management"));
+  }
+
+  #[test]
+  fn build_polyfill_file_moves_directives_to_top() {
+    let file_text = build_polyfill_file(&[
+      Box::new(promise_try::PromiseTryPolyfill),
+      Box::new(
+        explicit_resource_management::ExplicitResourceManagementPolyfill,
+      ),
+    ])
+    .unwrap();
+    assert!(
+      file_text.starts_with("/// <reference lib=\"esnext.disposable\" />\n")
+    );
+    assert_eq!(file_text.matches("/// <reference").count(), 1);
+  }
+}
@@ -0,0 +1,250 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::Callee;
+use deno_ast::view::Expr;
+use deno_ast::view::Lit;
+use deno_ast::view::Node;
+use deno_ast::SourceRanged;
+use deno_semver::VersionReq;
+
+use super::PolyfillVisitContext;
+use crate::analyze::is_in_type;
+
+/// First Node.js version with all the built-ins that a polyfill provides.
+/// Polyfills that aren't in the table are always used.
+const POLYFILL_NODE_VERSIONS: &[(&str, &str)] = &[
+  ("object-has-own", "16.9.0"),
+  ("error-cause", "16.9.0"),
+  ("string-replace-all", "15.0.0"),
+  ("array-find-last", "18.0.0"),
+  ("array-from-async", "22.0.0"),
+  ("promise-with-resolvers", "22.0.0"),
+  ("array-change-by-copy", "20.0.0"),
+  ("set-methods", "22.0.0"),
+  ("group-by", "21.0.0"),
+  ("uint8array-base64", "25.0.0"),
+  ("promise-try", "23.0.0"),
+  ("regexp-escape", "24.0.0"),
+  ("explicit-resource-management", "24.0.0"),
+];
+
+enum BuiltInAccess {
+  /// Property of a global (ex. `Promise.any`).
+  Static(&'static str, &'static str),
+  /// Method called on an array literal (ex. `[].flat()`).
+  ArrayMethod(&'static str),
+  /// Method called on a string literal (ex. `"".isWellFormed()`).
+  StringMethod(&'static str),
+  /// Global (ex. `WeakRef`).
+  Global(&'static str),
+}
+
+/// Built-in that there isn't a polyfill for.
+pub struct NodeBuiltIn {
+  /// Name to display (ex. `Array.prototype.at`).
+  pub name: &'static str,
+  /// First Node.js version that has it.
+  pub node_version: &'static str,
+  access: BuiltInAccess,
+}
+
+const UNPOLYFILLED_BUILT_INS: &[NodeBuiltIn] = &[
+  NodeBuiltIn {
+    name: "Array.prototype.flat",
+    node_version: "11.0.0",
+    access: BuiltInAccess::ArrayMethod("flat"),
+  },
+  NodeBuiltIn {
+    name: "Array.prototype.flatMap",
+    node_version: "11.0.0",
+    access: BuiltInAccess::ArrayMethod("flatMap"),
+  },
+  NodeBuiltIn {
+    name: "Object.fromEntries",
+    node_version: "12.0.0",
+    access: BuiltInAccess::Static("Object", "fromEntries"),
+  },
+  NodeBuiltIn {
+    name: "String.prototype.matchAll",
+    node_version: "12.0.0",
+    access: BuiltInAccess::StringMethod("matchAll"),
+  },
+  NodeBuiltIn {
+    name: "Promise.allSettled",
+    node_version: "12.9.0",
+    access: BuiltInAccess::Static("Promise", "allSettled"),
+  },
+  NodeBuiltIn {
+    name: "WeakRef",
+    node_version: "14.6.0",
+    access: BuiltInAccess::Global("WeakRef"),
+  },
+  NodeBuiltIn {
+    name: "FinalizationRegistry",
+    node_version: "14.6.0",
+    access: BuiltInAccess::Global("FinalizationRegistry"),
+  },
+  NodeBuiltIn {
+    name: "Promise.any",
+    node_version: "15.0.0",
+    access: BuiltInAccess::Static("Promise", "any"),
+  },
+  NodeBuiltIn {
+    name: "AggregateError",
+    node_version: "15.0.0",
+    access: BuiltInAccess::Global("AggregateError"),
+  },
+  NodeBuiltIn {
+    name: "Atomics.waitAsync",
+    node_version: "16.0.0",
+    access: BuiltInAccess::Static("Atomics", "waitAsync"),
+  },
+  NodeBuiltIn {
+    name: "Intl.Segmenter",
+    node_version: "16.0.0",
+    access: BuiltInAccess::Static("Intl", "Segmenter"),
+  },
+  NodeBuiltIn {
+    name: "Array.prototype.at",
+    node_version: "16.6.0",
+    access: BuiltInAccess::ArrayMethod("at"),
+  },
+  NodeBuiltIn {
+    name: "String.prototype.at",
+    node_version: "16.6.0",
+    access: BuiltInAccess::StringMethod("at"),
+  },
+  NodeBuiltIn {
+    name: "String.prototype.isWellFormed",
+    node_version: "20.0.0",
+    access: BuiltInAccess::StringMethod("isWellFormed"),
+  },
+  NodeBuiltIn {
+    name: "String.prototype.toWellFormed",
+    node_version: "20.0.0",
+    access: BuiltInAccess::StringMethod("toWellFormed"),
+  },
+  NodeBuiltIn {
+    name: "Iterator.from",
+    node_version: "22.0.0",
+    access: BuiltInAccess::Static("Iterator", "from"),
+  },
+];
+
+impl NodeBuiltIn {
+  pub fn is_used(&self, node: Node, context: &PolyfillVisitContext) -> bool {
+    match self.access {
+      BuiltInAccess::Static(global_name, property_name) => {
+        context.has_global_property_access(node, global_name, property_name)
+      }
+      BuiltInAccess::ArrayMethod(name) => {
+        is_method_call(node, name, context, |obj| matches!(obj, Expr::Array(_)))
+      }
+      BuiltInAccess::StringMethod(name) => {
+        is_method_call(node, name, context, |obj| {
+          matches!(obj, Expr::Lit(Lit::Str(_)) | Expr::Tpl(_))
+        })
+      }
+      BuiltInAccess::Global(name) => match node {
+        Node::Ident(ident) => {
+          ident.ctxt() == context.unresolved_context
+            && !context.top_level_decls.contains(name)
+            && ident.text_fast(context.program) == name
+            && !is_in_type(node)
+        }
+        _ => false,
+      },
+    }
+  }
+}
+
+/// Gets if the node calls the method on a receiver the predicate matches.
+/// Only literal receivers are matched because the type of other values
+/// isn't known (ex. `obj.at(i)` may be a user defined method).
+fn is_method_call(
+  node: Node,
+  name: &str,
+  context: &PolyfillVisitContext,
+  is_receiver: impl Fn(&Expr) -> bool,
+) -> bool {
+  let Node::CallExpr(expr) = node else {
+    return false;
+  };
+  let Callee::Expr(Expr::Member(callee)) = expr.callee else {
+    return false;
+  };
+  let mut obj = callee.obj;
+  while let Expr::Paren(paren) = obj {
+    obj = paren.expr;
+  }
+  callee.prop.text_fast(context.program) == name && is_receiver(&obj)
+}
+
+/// Gets if the polyfill's built-ins are missing in a version in the range.
+pub fn is_polyfill_needed_for_range(name: &str, range: &VersionReq) -> bool {
+  match POLYFILL_NODE_VERSIONS.iter().find(|(n, _)| *n == name) {
+    Some((_, node_version)) => !is_available_in_range(node_version, range),
+    None => true,
+  }
+}
+
+/// Gets the built-ins without a polyfill that are missing in
+/// a version in the range.
+pub fn unsupported_built_ins_for_range(
+  range: &VersionReq,
+) -> Vec<&'static NodeBuiltIn> {
+  UNPOLYFILLED_BUILT_INS
+    .iter()
+    .filter(|b| !is_available_in_range(b.node_version, range))
+    .collect()
+}
+
+/// Gets if every version in the range is the provided version or newer.
+fn is_available_in_range(node_version: &str, range: &VersionReq) -> bool {
+  let older_versions =
+    VersionReq::parse_from_npm(&format!("<{}", node_version)).unwrap();
+  !range.intersects(&older_versions)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  fn range(text: &str) -> VersionReq {
+    VersionReq::parse_from_npm(text).unwrap()
+  }
+
+  #[test]
+  fn polyfill_needed_for_range() {
+    let is_needed =
+      |name: &str, text: &str| is_polyfill_needed_for_range(name, &range(text));
+    assert!(is_needed("array-find-last", ">=16"));
+    assert!(!is_needed("array-find-last", ">=18"));
+    assert!(is_needed("set-methods", "^20 || ^22"));
+    assert!(!is_needed("set-methods", "^22 || ^24"));
+    assert!(is_needed("object-has-own", "16.x"));
+    assert!(!is_needed("object-has-own", "16.9.x"));
+    // not in the table
+    assert!(is_needed("import-meta", ">=22"));
+  }
+
+  #[test]
+  fn unsupported_for_range() {
+    let get_names = |text: &str| {
+      unsupported_built_ins_for_range(&range(text))
+        .iter()
+        .map(|b| b.name)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(get_names(">=22"), Vec::<&str>::new());
+    assert_eq!(get_names(">=20"), vec!["Iterator.from"]);
+    assert_eq!(
+      get_names(">=18"),
+      vec![
+        "String.prototype.isWellFormed",
+        "String.prototype.toWellFormed",
+        "Iterator.from"
+      ]
+    );
+  }
+}
@@ -9,8 +9,12 @@ use crate::ScriptTarget;
 pub struct ObjectHasOwnPolyfill;
 
 impl Polyfill for ObjectHasOwnPolyfill {
-  fn use_for_target(&self, _target: ScriptTarget) -> bool {
-    true
+  fn name(&self) -> &'static str {
+    "object-has-own"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2022 as u32)
   }
 
   fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
@@ -0,0 +1,46 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::Node;
+
+use super::Polyfill;
+use super::PolyfillVisitContext;
+use crate::ScriptTarget;
+
+pub struct PromiseTryPolyfill;
+
+impl Polyfill for PromiseTryPolyfill {
+  fn name(&self) -> &'static str {
+    "promise-try"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2025 as u32)
+  }
+
+  fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
+    context.has_global_property_access(node, "Promise", "try")
+  }
+
+  fn get_file_text(&self) -> &'static str {
+    include_str!("./scripts/es2025.promise-try.ts")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::polyfills::PolyfillTester;
+
+  #[test]
+  pub fn finds_when_matches() {
+    let tester = PolyfillTester::new(Box::new(|| Box::new(PromiseTryPolyfill)));
+    assert_eq!(tester.matches("Promise.try(() => 1)"), true);
+    assert_eq!(
+      tester.matches("class Promise {} Promise.try(() => 1)"),
+      false
+    );
+    assert_eq!(tester.matches("Other.try(() => 1)"), false);
+    assert_eq!(tester.matches("Promise.tryAgain(() => 1)"), false);
+    assert_eq!(tester.matches("const { try: tryFn } = Promise;"), true);
+  }
+}
@@ -9,9 +9,12 @@ use crate::ScriptTarget;
 pub struct PromiseWithResolversPolyfill;
 
 impl Polyfill for PromiseWithResolversPolyfill {
-  fn use_for_target(&self, _target: ScriptTarget) -> bool {
-    // (target as u32) < (ScriptTarget::ES2021 as u32)
-    true // just always use it for the time being
+  fn name(&self) -> &'static str {
+    "promise-with-resolvers"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2024 as u32)
   }
 
   fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
@@ -0,0 +1,47 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::Node;
+
+use super::Polyfill;
+use super::PolyfillVisitContext;
+use crate::ScriptTarget;
+
+pub struct RegExpEscapePolyfill;
+
+impl Polyfill for RegExpEscapePolyfill {
+  fn name(&self) -> &'static str {
+    "regexp-escape"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2025 as u32)
+  }
+
+  fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
+    context.has_global_property_access(node, "RegExp", "escape")
+  }
+
+  fn get_file_text(&self) -> &'static str {
+    include_str!("./scripts/es2025.regexp-escape.ts")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::polyfills::PolyfillTester;
+
+  #[test]
+  pub fn finds_when_matches() {
+    let tester =
+      PolyfillTester::new(Box::new(|| Box::new(RegExpEscapePolyfill)));
+    assert_eq!(tester.matches("RegExp.escape('a.b')"), true);
+    assert_eq!(
+      tester.matches("class RegExp {} RegExp.escape('a.b')"),
+      false
+    );
+    assert_eq!(tester.matches("Other.escape('a.b')"), false);
+    assert_eq!(tester.matches("const { escape } = RegExp;"), true);
+    assert_eq!(tester.matches("escape('a.b')"), false);
+  }
+}
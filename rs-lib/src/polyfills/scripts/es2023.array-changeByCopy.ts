@@ -0,0 +1,222 @@
+// https://github.com/microsoft/TypeScript/blob/v5.4.5/src/lib/es2023.array.d.ts
+declare global {
+  interface Array<T> {
+    /** Returns a copy of an array with its elements reversed. */
+    toReversed(): T[];
+    /**
+     * Returns a copy of an array with its elements sorted.
+     * @param compareFn Function used to determine the order of the elements.
+     * It is expected to return a negative value if the first argument is less
+     * than the second argument, zero if they're equal, and a positive value
+     * otherwise. If omitted, the elements are sorted in ascending, ASCII
+     * character order.
+     */
+    toSorted(compareFn?: (a: T, b: T) => number): T[];
+    /**
+     * Copies an array and removes elements and, if necessary, inserts new
+     * elements in their place. Returns the copied array.
+     * @param start The zero-based location in the array from which to start
+     * removing elements.
+     * @param deleteCount The number of elements to remove.
+     * @param items Elements to insert into the copied array in place of the
+     * deleted elements.
+     */
+    toSpliced(start: number, deleteCount: number, ...items: T[]): T[];
+    toSpliced(start: number, deleteCount?: number): T[];
+    /**
+     * Copies an array, then overwrites the value at the provided index with
+     * the given value. If the index is negative, then it replaces from the end
+     * of the array.
+     * @param index The index of the value to overwrite.
+     * @param value The value to write into the copied array.
+     */
+    with(index: number, value: T): T[];
+  }
+  interface ReadonlyArray<T> {
+    toReversed(): T[];
+    toSorted(compareFn?: (a: T, b: T) => number): T[];
+    toSpliced(start: number, deleteCount: number, ...items: T[]): T[];
+    toSpliced(start: number, deleteCount?: number): T[];
+    with(index: number, value: T): T[];
+  }
+  interface Int8Array {
+    toReversed(): Int8Array;
+    toSorted(compareFn?: (a: number, b: number) => number): Int8Array;
+    with(index: number, value: number): Int8Array;
+  }
+  interface Uint8Array {
+    toReversed(): Uint8Array;
+    toSorted(compareFn?: (a: number, b: number) => number): Uint8Array;
+    with(index: number, value: number): Uint8Array;
+  }
+  interface Uint8ClampedArray {
+    toReversed(): Uint8ClampedArray;
+    toSorted(compareFn?: (a: number, b: number) => number): Uint8ClampedArray;
+    with(index: number, value: number): Uint8ClampedArray;
+  }
+  interface Int16Array {
+    toReversed(): Int16Array;
+    toSorted(compareFn?: (a: number, b: number) => number): Int16Array;
+    with(index: number, value: number): Int16Array;
+  }
+  interface Uint16Array {
+    toReversed(): Uint16Array;
+    toSorted(compareFn?: (a: number, b: number) => number): Uint16Array;
+    with(index: number, value: number): Uint16Array;
+  }
+  interface Int32Array {
+    toReversed(): Int32Array;
+    toSorted(compareFn?: (a: number, b: number) => number): Int32Array;
+    with(index: number, value: number): Int32Array;
+  }
+  interface Uint32Array {
+    toReversed(): Uint32Array;
+    toSorted(compareFn?: (a: number, b: number) => number): Uint32Array;
+    with(index: number, value: number): Uint32Array;
+  }
+  interface Float32Array {
+    toReversed(): Float32Array;
+    toSorted(compareFn?: (a: number, b: number) => number): Float32Array;
+    with(index: number, value: number): Float32Array;
+  }
+  interface Float64Array {
+    toReversed(): Float64Array;
+    toSorted(compareFn?: (a: number, b: number) => number): Float64Array;
+    with(index: number, value: number): Float64Array;
+  }
+  interface BigInt64Array {
+    toReversed(): BigInt64Array;
+    toSorted(compareFn?: (a: bigint, b: bigint) => number): BigInt64Array;
+    with(index: number, value: bigint): BigInt64Array;
+  }
+  interface BigUint64Array {
+    toReversed(): BigUint64Array;
+    toSorted(compareFn?: (a: bigint, b: bigint) => number): BigUint64Array;
+    with(index: number, value: bigint): BigUint64Array;
+  }
+}
+
+function toIntegerOrInfinity(value: any): number {
+  const number = Number(value);
+  return Number.isNaN(number) ? 0 : Math.trunc(number);
+}
+
+function toRelativeIndex(value: any, length: number): number {
+  const index = toIntegerOrInfinity(value);
+  return index < 0 ? Math.max(length + index, 0) : Math.min(index, length);
+}
+
+function copyArray(self: any): any[] {
+  const length = self.length;
+  const result = new Array(length);
+  for (let i = 0; i < length; i++) {
+    result[i] = self[i];
+  }
+  return result;
+}
+
+function copyTypedArray(self: any): any {
+  return new self.constructor(self);
+}
+
+function validateCompareFn(compareFn: any) {
+  if (compareFn !== undefined && typeof compareFn !== "function") {
+    throw new TypeError(
+      "The comparison function must be either a function or undefined",
+    );
+  }
+}
+
+function withValue(self: any, copy: any, index: any, value: any) {
+  const length = self.length;
+  const relativeIndex = toIntegerOrInfinity(index);
+  const actualIndex = relativeIndex < 0 ? length + relativeIndex : relativeIndex;
+  if (actualIndex < 0 || actualIndex >= length) {
+    throw new RangeError("Invalid index: " + index);
+  }
+  copy[actualIndex] = value;
+  return copy;
+}
+
+function defineArrayMethod(prototype: any, name: string, value: any) {
+  if (!prototype[name]) {
+    Object.defineProperty(prototype, name, {
+      value,
+      writable: true,
+      enumerable: false,
+      configurable: true,
+    });
+  }
+}
+
+defineArrayMethod(Array.prototype, "toReversed", function (this: any) {
+  return copyArray(this).reverse();
+});
+
+defineArrayMethod(
+  Array.prototype,
+  "toSorted",
+  function (this: any, compareFn?: any) {
+    validateCompareFn(compareFn);
+    return copyArray(this).sort(compareFn);
+  },
+);
+
+defineArrayMethod(
+  Array.prototype,
+  "toSpliced",
+  function (this: any, ...args: any[]) {
+    const length = this.length;
+    const start = toRelativeIndex(args[0], length);
+    const skipCount = args.length === 0
+      ? 0
+      : args.length === 1
+      ? length - start
+      : Math.min(Math.max(toIntegerOrInfinity(args[1]), 0), length - start);
+    const result = [];
+    for (let i = 0; i < start; i++) {
+      result.push(this[i]);
+    }
+    for (let i = 2; i < args.length; i++) {
+      result.push(args[i]);
+    }
+    for (let i = start + skipCount; i < length; i++) {
+      result.push(this[i]);
+    }
+    return result;
+  },
+);
+
+defineArrayMethod(
+  Array.prototype,
+  "with",
+  function (this: any, index: any, value: any) {
+    return withValue(this, copyArray(this), index, value);
+  },
+);
+
+// the prototype shared by all the typed arrays
+const typedArrayPrototype = Object.getPrototypeOf(Uint8Array.prototype);
+
+defineArrayMethod(typedArrayPrototype, "toReversed", function (this: any) {
+  return copyTypedArray(this).reverse();
+});
+
+defineArrayMethod(
+  typedArrayPrototype,
+  "toSorted",
+  function (this: any, compareFn?: any) {
+    validateCompareFn(compareFn);
+    return copyTypedArray(this).sort(compareFn);
+  },
+);
+
+defineArrayMethod(
+  typedArrayPrototype,
+  "with",
+  function (this: any, index: any, value: any) {
+    return withValue(this, copyTypedArray(this), index, value);
+  },
+);
+
+export {};
@@ -0,0 +1,67 @@
+// https://github.com/microsoft/TypeScript/blob/v5.4.5/src/lib/esnext.object.d.ts
+// https://github.com/microsoft/TypeScript/blob/v5.4.5/src/lib/esnext.collection.d.ts
+declare global {
+  interface ObjectConstructor {
+    /**
+     * Groups members of an iterable according to the return value of the passed callback.
+     * @param items An iterable.
+     * @param keySelector A callback which will be invoked for each item in items.
+     */
+    groupBy<K extends PropertyKey, T>(
+      items: Iterable<T>,
+      keySelector: (item: T, index: number) => K,
+    ): Partial<Record<K, T[]>>;
+  }
+
+  interface MapConstructor {
+    /**
+     * Groups members of an iterable according to the return value of the passed callback.
+     * @param items An iterable.
+     * @param keySelector A callback which will be invoked for each item in items.
+     */
+    groupBy<K, T>(
+      items: Iterable<T>,
+      keySelector: (item: T, index: number) => K,
+    ): Map<K, T[]>;
+  }
+}
+
+if (Object.groupBy === undefined) {
+  Object.groupBy = function (items: any, keySelector: any) {
+    const result = Object.create(null);
+    let index = 0;
+    for (const item of items) {
+      const key = keySelector(item, index++);
+      const propertyKey = typeof key === "symbol" ? key : String(key);
+      if (propertyKey in result) {
+        result[propertyKey].push(item);
+      } else {
+        result[propertyKey] = [item];
+      }
+    }
+    return result;
+  } as any;
+}
+
+if (Map.groupBy === undefined) {
+  Map.groupBy = function (items: any, keySelector: any) {
+    const result = new Map();
+    let index = 0;
+    for (const item of items) {
+      let key = keySelector(item, index++);
+      // normalize -0 to +0 like the Map constructor
+      if (key === 0) {
+        key = 0;
+      }
+      const group = result.get(key);
+      if (group === undefined) {
+        result.set(key, [item]);
+      } else {
+        group.push(item);
+      }
+    }
+    return result;
+  } as any;
+}
+
+export {};
@@ -0,0 +1,36 @@
+declare global {
+  // https://github.com/microsoft/TypeScript/blob/v5.7.2/src/lib/esnext.promise.d.ts
+  interface PromiseConstructor {
+    /**
+     * Takes a callback of any kind (returns or throws, synchronously or asynchronously) and wraps its result
+     * in a Promise.
+     *
+     * @param callbackFn A function that is called synchronously. It can do anything: either return
+     * a value, throw an error, or return a promise.
+     * @param args Additional arguments, that will be passed to the callback.
+     *
+     * @returns A Promise that is:
+     * - Already fulfilled, if the callback synchronously returns a value.
+     * - Already rejected, if the callback synchronously throws an error.
+     * - Asynchronously fulfilled or rejected, if the callback returns a promise.
+     */
+    try<T, U extends unknown[]>(
+      callbackFn: (...args: U) => T | PromiseLike<T>,
+      ...args: U
+    ): Promise<Awaited<T>>;
+  }
+}
+
+if (Promise.try === undefined) {
+  Promise.try = function (this: any, callbackFn: any, ...args: any[]) {
+    return new this((resolve: any, reject: any) => {
+      try {
+        resolve(callbackFn(...args));
+      } catch (err) {
+        reject(err);
+      }
+    });
+  } as any;
+}
+
+export {};
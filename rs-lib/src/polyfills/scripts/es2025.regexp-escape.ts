@@ -0,0 +1,58 @@
+declare global {
+  interface RegExpConstructor {
+    /**
+     * Escapes any characters in the string that have a special meaning in a
+     * regular expression, so it can be used to match the literal text.
+     * @param string The string to escape.
+     */
+    escape(string: string): string;
+  }
+}
+
+// https://tc39.es/proposal-regex-escaping/#sec-encodeforregexpescape
+function encodeForRegExpEscape(char: string, isFirst: boolean): string {
+  const code = char.codePointAt(0)!;
+  const toHexEscape = () => {
+    const hex = "000" + code.toString(16);
+    return code <= 0xff ? "\\x" + hex.slice(-2) : "\\u" + hex.slice(-4);
+  };
+  if (isFirst && /^[0-9A-Za-z]$/.test(char)) {
+    return toHexEscape();
+  }
+  if ("^$\\.*+?()[]{}|/".includes(char)) {
+    return "\\" + char;
+  }
+  switch (char) {
+    case "\t":
+      return "\\t";
+    case "\n":
+      return "\\n";
+    case "\v":
+      return "\\v";
+    case "\f":
+      return "\\f";
+    case "\r":
+      return "\\r";
+  }
+  const isLoneSurrogate = char.length === 1 && code >= 0xd800 &&
+    code <= 0xdfff;
+  if (",-=<>#&!%:;@~'`\"".includes(char) || /^\s$/.test(char) || isLoneSurrogate) {
+    return toHexEscape();
+  }
+  return char;
+}
+
+if (RegExp.escape === undefined) {
+  RegExp.escape = function (string: string) {
+    if (typeof string !== "string") {
+      throw new TypeError("Expected a string");
+    }
+    let result = "";
+    for (const char of string) {
+      result += encodeForRegExpEscape(char, result.length === 0);
+    }
+    return result;
+  };
+}
+
+export {};
@@ -0,0 +1,225 @@
+// https://github.com/microsoft/TypeScript/blob/v5.5.2/src/lib/esnext.collection.d.ts
+declare global {
+  interface ReadonlySetLike<T> {
+    /**
+     * Despite its name, returns an iterator of the values in the set-like.
+     */
+    keys(): Iterator<T>;
+    /**
+     * @returns a boolean indicating whether an element with the specified value exists in the set-like or not.
+     */
+    has(value: T): boolean;
+    /**
+     * @returns the number of (unique) elements in the set-like.
+     */
+    readonly size: number;
+  }
+
+  interface Set<T> {
+    /**
+     * @returns a new Set containing all the elements in this Set and also all the elements in the argument.
+     */
+    union<U>(other: ReadonlySetLike<U>): Set<T | U>;
+    /**
+     * @returns a new Set containing all the elements which are both in this Set and in the argument.
+     */
+    intersection<U>(other: ReadonlySetLike<U>): Set<T & U>;
+    /**
+     * @returns a new Set containing all the elements in this Set which are not also in the argument.
+     */
+    difference<U>(other: ReadonlySetLike<U>): Set<T>;
+    /**
+     * @returns a new Set containing all the elements which are in either this Set or in the argument, but not in both.
+     */
+    symmetricDifference<U>(other: ReadonlySetLike<U>): Set<T | U>;
+    /**
+     * @returns a boolean indicating whether all the elements in this Set are also in the argument.
+     */
+    isSubsetOf(other: ReadonlySetLike<unknown>): boolean;
+    /**
+     * @returns a boolean indicating whether all the elements in the argument are also in this Set.
+     */
+    isSupersetOf(other: ReadonlySetLike<unknown>): boolean;
+    /**
+     * @returns a boolean indicating whether this Set has no elements in common with the argument.
+     */
+    isDisjointFrom(other: ReadonlySetLike<unknown>): boolean;
+  }
+
+  interface ReadonlySet<T> {
+    union<U>(other: ReadonlySetLike<U>): Set<T | U>;
+    intersection<U>(other: ReadonlySetLike<U>): Set<T & U>;
+    difference<U>(other: ReadonlySetLike<U>): Set<T>;
+    symmetricDifference<U>(other: ReadonlySetLike<U>): Set<T | U>;
+    isSubsetOf(other: ReadonlySetLike<unknown>): boolean;
+    isSupersetOf(other: ReadonlySetLike<unknown>): boolean;
+    isDisjointFrom(other: ReadonlySetLike<unknown>): boolean;
+  }
+}
+
+interface SetRecord {
+  size: number;
+  has(value: any): boolean;
+  keys(): Iterator<any>;
+}
+
+function getSetRecord(other: any): SetRecord {
+  if (other == null || (typeof other !== "object" && typeof other !== "function")) {
+    throw new TypeError("The argument must be a set-like object");
+  }
+  const rawSize = Number(other.size);
+  if (Number.isNaN(rawSize)) {
+    throw new TypeError("The 'size' property must be a number");
+  }
+  const size = Math.trunc(rawSize);
+  if (size < 0) {
+    throw new RangeError("The 'size' property must not be negative");
+  }
+  const has = other.has;
+  if (typeof has !== "function") {
+    throw new TypeError("The 'has' property must be a function");
+  }
+  const keys = other.keys;
+  if (typeof keys !== "function") {
+    throw new TypeError("The 'keys' property must be a function");
+  }
+  return {
+    size,
+    has: (value) => Boolean(has.call(other, value)),
+    keys: () => keys.call(other),
+  };
+}
+
+/** Calls the callback for each key of the set-like, stopping
+ * early when the callback returns `false`. */
+function forEachSetRecordKey(
+  record: SetRecord,
+  callback: (key: any) => boolean | void,
+) {
+  const iterator = record.keys();
+  while (true) {
+    const next = iterator.next();
+    if (next.done) {
+      return;
+    }
+    if (callback(next.value) === false) {
+      iterator.return?.();
+      return;
+    }
+  }
+}
+
+function defineSetMethod(name: string, value: any) {
+  if (!(Set.prototype as any)[name]) {
+    Object.defineProperty(Set.prototype, name, {
+      value,
+      writable: true,
+      enumerable: false,
+      configurable: true,
+    });
+  }
+}
+
+defineSetMethod("union", function (this: Set<any>, other: any) {
+  const record = getSetRecord(other);
+  const result = new Set(this);
+  forEachSetRecordKey(record, (key) => {
+    result.add(key);
+  });
+  return result;
+});
+
+defineSetMethod("intersection", function (this: Set<any>, other: any) {
+  const record = getSetRecord(other);
+  const result = new Set();
+  if (this.size <= record.size) {
+    for (const value of Array.from(this)) {
+      if (record.has(value)) {
+        result.add(value);
+      }
+    }
+  } else {
+    forEachSetRecordKey(record, (key) => {
+      if (this.has(key)) {
+        result.add(key);
+      }
+    });
+  }
+  return result;
+});
+
+defineSetMethod("difference", function (this: Set<any>, other: any) {
+  const record = getSetRecord(other);
+  const result = new Set(this);
+  if (this.size <= record.size) {
+    for (const value of Array.from(this)) {
+      if (record.has(value)) {
+        result.delete(value);
+      }
+    }
+  } else {
+    forEachSetRecordKey(record, (key) => {
+      result.delete(key);
+    });
+  }
+  return result;
+});
+
+defineSetMethod("symmetricDifference", function (this: Set<any>, other: any) {
+  const record = getSetRecord(other);
+  const result = new Set(this);
+  forEachSetRecordKey(record, (key) => {
+    if (this.has(key)) {
+      result.delete(key);
+    } else {
+      result.add(key);
+    }
+  });
+  return result;
+});
+
+defineSetMethod("isSubsetOf", function (this: Set<any>, other: any) {
+  const record = getSetRecord(other);
+  if (this.size > record.size) {
+    return false;
+  }
+  for (const value of Array.from(this)) {
+    if (!record.has(value)) {
+      return false;
+    }
+  }
+  return true;
+});
+
+defineSetMethod("isSupersetOf", function (this: Set<any>, other: any) {
+  const record = getSetRecord(other);
+  if (this.size < record.size) {
+    return false;
+  }
+  let isSuperset = true;
+  forEachSetRecordKey(record, (key) => {
+    isSuperset = this.has(key);
+    return isSuperset;
+  });
+  return isSuperset;
+});
+
+defineSetMethod("isDisjointFrom", function (this: Set<any>, other: any) {
+  const record = getSetRecord(other);
+  let isDisjoint = true;
+  if (this.size <= record.size) {
+    for (const value of Array.from(this)) {
+      if (record.has(value)) {
+        return false;
+      }
+    }
+  } else {
+    forEachSetRecordKey(record, (key) => {
+      isDisjoint = !this.has(key);
+      return isDisjoint;
+    });
+  }
+  return isDisjoint;
+});
+
+export {};
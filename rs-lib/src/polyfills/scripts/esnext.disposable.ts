@@ -0,0 +1,226 @@
+/// <reference lib="esnext.disposable" />
+// https://github.com/tc39/proposal-explicit-resource-management
+
+function defineDisposableGlobal(target: any, name: string, value: any) {
+  if (target[name] === undefined) {
+    Object.defineProperty(target, name, {
+      value,
+      writable: true,
+      enumerable: false,
+      configurable: true,
+    });
+  }
+}
+
+defineDisposableGlobal(Symbol, "dispose", Symbol("Symbol.dispose"));
+defineDisposableGlobal(
+  Symbol,
+  "asyncDispose",
+  Symbol("Symbol.asyncDispose"),
+);
+
+class SuppressedErrorPolyfill extends Error {
+  error: any;
+  suppressed: any;
+
+  constructor(error: any, suppressed: any, message?: string) {
+    super(message);
+    this.name = "SuppressedError";
+    this.error = error;
+    this.suppressed = suppressed;
+  }
+}
+
+defineDisposableGlobal(globalThis, "SuppressedError", SuppressedErrorPolyfill);
+
+function getDisposeMethod(value: any, isAsync: boolean): () => any {
+  if (
+    value === null ||
+    (typeof value !== "object" && typeof value !== "function")
+  ) {
+    throw new TypeError("Expected the value to be disposable");
+  }
+  let method = isAsync ? value[Symbol.asyncDispose] : undefined;
+  if (method === undefined) {
+    const disposeMethod = value[Symbol.dispose];
+    if (typeof disposeMethod === "function" && isAsync) {
+      // the result of a sync dispose method isn't awaited
+      method = function () {
+        disposeMethod.call(value);
+      };
+    } else {
+      method = disposeMethod;
+    }
+  }
+  if (typeof method !== "function") {
+    throw new TypeError("Expected the value to be disposable");
+  }
+  return () => method.call(value);
+}
+
+function assertDisposeCallback(onDispose: any) {
+  if (typeof onDispose !== "function") {
+    throw new TypeError("Expected the callback to be a function");
+  }
+}
+
+class DisposableStackPolyfill {
+  #disposed = false;
+  #callbacks: (() => void)[] = [];
+
+  get disposed(): boolean {
+    return this.#disposed;
+  }
+
+  use<T>(value: T): T {
+    this.#assertNotDisposed();
+    if (value !== null && value !== undefined) {
+      this.#callbacks.push(getDisposeMethod(value, false));
+    }
+    return value;
+  }
+
+  adopt<T>(value: T, onDispose: (value: T) => void): T {
+    this.#assertNotDisposed();
+    assertDisposeCallback(onDispose);
+    this.#callbacks.push(() => onDispose(value));
+    return value;
+  }
+
+  defer(onDispose: () => void): void {
+    this.#assertNotDisposed();
+    assertDisposeCallback(onDispose);
+    this.#callbacks.push(() => onDispose());
+  }
+
+  move(): DisposableStackPolyfill {
+    this.#assertNotDisposed();
+    const stack = new DisposableStackPolyfill();
+    stack.#callbacks = this.#callbacks;
+    this.#callbacks = [];
+    this.#disposed = true;
+    return stack;
+  }
+
+  dispose(): void {
+    if (this.#disposed) {
+      return;
+    }
+    this.#disposed = true;
+    const callbacks = this.#callbacks;
+    this.#callbacks = [];
+    let hasError = false;
+    let error: any;
+    for (let i = callbacks.length - 1; i >= 0; i--) {
+      try {
+        callbacks[i]();
+      } catch (err) {
+        error = hasError ? new SuppressedError(err, error) : err;
+        hasError = true;
+      }
+    }
+    if (hasError) {
+      throw error;
+    }
+  }
+
+  [Symbol.dispose](): void {
+    this.dispose();
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "DisposableStack";
+  }
+
+  #assertNotDisposed() {
+    if (this.#disposed) {
+      throw new ReferenceError("The DisposableStack was already disposed");
+    }
+  }
+}
+
+class AsyncDisposableStackPolyfill {
+  #disposed = false;
+  #callbacks: (() => any)[] = [];
+
+  get disposed(): boolean {
+    return this.#disposed;
+  }
+
+  use<T>(value: T): T {
+    this.#assertNotDisposed();
+    if (value !== null && value !== undefined) {
+      this.#callbacks.push(getDisposeMethod(value, true));
+    }
+    return value;
+  }
+
+  adopt<T>(value: T, onDisposeAsync: (value: T) => any): T {
+    this.#assertNotDisposed();
+    assertDisposeCallback(onDisposeAsync);
+    this.#callbacks.push(() => onDisposeAsync(value));
+    return value;
+  }
+
+  defer(onDisposeAsync: () => any): void {
+    this.#assertNotDisposed();
+    assertDisposeCallback(onDisposeAsync);
+    this.#callbacks.push(() => onDisposeAsync());
+  }
+
+  move(): AsyncDisposableStackPolyfill {
+    this.#assertNotDisposed();
+    const stack = new AsyncDisposableStackPolyfill();
+    stack.#callbacks = this.#callbacks;
+    this.#callbacks = [];
+    this.#disposed = true;
+    return stack;
+  }
+
+  async disposeAsync(): Promise<void> {
+    if (this.#disposed) {
+      return;
+    }
+    this.#disposed = true;
+    const callbacks = this.#callbacks;
+    this.#callbacks = [];
+    let hasError = false;
+    let error: any;
+    for (let i = callbacks.length - 1; i >= 0; i--) {
+      try {
+        await callbacks[i]();
+      } catch (err) {
+        error = hasError ? new SuppressedError(err, error) : err;
+        hasError = true;
+      }
+    }
+    if (hasError) {
+      throw error;
+    }
+  }
+
+  [Symbol.asyncDispose](): Promise<void> {
+    return this.disposeAsync();
+  }
+
+  get [Symbol.toStringTag](): string {
+    return "AsyncDisposableStack";
+  }
+
+  #assertNotDisposed() {
+    if (this.#disposed) {
+      throw new ReferenceError(
+        "The AsyncDisposableStack was already disposed",
+      );
+    }
+  }
+}
+
+defineDisposableGlobal(globalThis, "DisposableStack", DisposableStackPolyfill);
+defineDisposableGlobal(
+  globalThis,
+  "AsyncDisposableStack",
+  AsyncDisposableStackPolyfill,
+);
+
+export {};
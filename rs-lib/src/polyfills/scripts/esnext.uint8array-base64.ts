@@ -0,0 +1,227 @@
+// https://github.com/tc39/proposal-arraybuffer-base64
+declare global {
+  interface Uint8ArrayConstructor {
+    /**
+     * Creates a new Uint8Array from a base64-encoded string.
+     * @param string The base64-encoded string.
+     * @param options Alphabet of the string and how to handle a final chunk
+     * that's missing padding (defaults to `"loose"`).
+     */
+    fromBase64(
+      string: string,
+      options?: {
+        alphabet?: "base64" | "base64url";
+        lastChunkHandling?: "loose" | "strict" | "stop-before-partial";
+      },
+    ): Uint8Array;
+    /**
+     * Creates a new Uint8Array from a hex-encoded string.
+     * @param string The hex-encoded string.
+     */
+    fromHex(string: string): Uint8Array;
+  }
+  interface Uint8Array {
+    /**
+     * Converts the array to a base64-encoded string.
+     * @param options Alphabet to use and whether to omit the padding.
+     */
+    toBase64(
+      options?: { alphabet?: "base64" | "base64url"; omitPadding?: boolean },
+    ): string;
+    /** Converts the array to a hex-encoded string. */
+    toHex(): string;
+  }
+}
+
+const base64Chars =
+  "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const base64UrlChars =
+  "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+function getBase64Chars(options: any): string {
+  const alphabet = options?.alphabet ?? "base64";
+  if (alphabet !== "base64" && alphabet !== "base64url") {
+    throw new TypeError("Expected the alphabet to be base64 or base64url");
+  }
+  return alphabet === "base64" ? base64Chars : base64UrlChars;
+}
+
+function assertUint8Array(value: any) {
+  if (!(value instanceof Uint8Array)) {
+    throw new TypeError("Expected a Uint8Array");
+  }
+}
+
+function assertString(value: any) {
+  if (typeof value !== "string") {
+    throw new TypeError("Expected a string");
+  }
+}
+
+function decodeBase64(string: string, options: any): Uint8Array {
+  assertString(string);
+  const chars = getBase64Chars(options);
+  const lastChunkHandling = options?.lastChunkHandling ?? "loose";
+  if (
+    lastChunkHandling !== "loose" &&
+    lastChunkHandling !== "strict" &&
+    lastChunkHandling !== "stop-before-partial"
+  ) {
+    throw new TypeError(
+      "Expected lastChunkHandling to be loose, strict, or stop-before-partial",
+    );
+  }
+  const input = string.replace(/[\t\n\f\r ]/g, "");
+  const bytes: number[] = [];
+  let chunk = 0;
+  let chunkLength = 0;
+  let index = 0;
+  for (; index < input.length; index++) {
+    const char = input[index];
+    if (char === "=") {
+      break;
+    }
+    const value = chars.indexOf(char);
+    if (value === -1) {
+      throw new SyntaxError("Invalid base64 character: " + char);
+    }
+    chunk = (chunk << 6) | value;
+    chunkLength++;
+    if (chunkLength === 4) {
+      bytes.push((chunk >> 16) & 255, (chunk >> 8) & 255, chunk & 255);
+      chunk = 0;
+      chunkLength = 0;
+    }
+  }
+
+  const padding = input.slice(index);
+  if (chunkLength === 0) {
+    if (padding.length > 0) {
+      throw new SyntaxError("Unexpected base64 padding");
+    }
+    return new Uint8Array(bytes);
+  }
+  if (chunkLength === 1) {
+    if (lastChunkHandling === "stop-before-partial" && padding.length === 0) {
+      return new Uint8Array(bytes);
+    }
+    throw new SyntaxError("Invalid base64 chunk");
+  }
+  const expectedPadding = chunkLength === 2 ? "==" : "=";
+  if (padding !== expectedPadding) {
+    const isPartial = padding.length < expectedPadding.length &&
+      expectedPadding.startsWith(padding);
+    if (lastChunkHandling === "stop-before-partial" && isPartial) {
+      return new Uint8Array(bytes);
+    }
+    if (padding.length > 0 || lastChunkHandling === "strict") {
+      throw new SyntaxError("Invalid base64 padding");
+    }
+  }
+  const extraBits = chunkLength === 2 ? chunk & 15 : chunk & 3;
+  if (lastChunkHandling === "strict" && extraBits !== 0) {
+    throw new SyntaxError("Invalid base64 chunk");
+  }
+  if (chunkLength === 2) {
+    bytes.push((chunk >> 4) & 255);
+  } else {
+    bytes.push((chunk >> 10) & 255, (chunk >> 2) & 255);
+  }
+  return new Uint8Array(bytes);
+}
+
+function encodeBase64(bytes: Uint8Array, options: any): string {
+  assertUint8Array(bytes);
+  const chars = getBase64Chars(options);
+  const omitPadding = Boolean(options?.omitPadding);
+  let result = "";
+  let index = 0;
+  for (; index + 2 < bytes.length; index += 3) {
+    const chunk = (bytes[index] << 16) | (bytes[index + 1] << 8) |
+      bytes[index + 2];
+    result += chars[(chunk >> 18) & 63] + chars[(chunk >> 12) & 63] +
+      chars[(chunk >> 6) & 63] + chars[chunk & 63];
+  }
+  const remaining = bytes.length - index;
+  if (remaining === 1) {
+    const chunk = bytes[index] << 16;
+    result += chars[(chunk >> 18) & 63] + chars[(chunk >> 12) & 63];
+    if (!omitPadding) {
+      result += "==";
+    }
+  } else if (remaining === 2) {
+    const chunk = (bytes[index] << 16) | (bytes[index + 1] << 8);
+    result += chars[(chunk >> 18) & 63] + chars[(chunk >> 12) & 63] +
+      chars[(chunk >> 6) & 63];
+    if (!omitPadding) {
+      result += "=";
+    }
+  }
+  return result;
+}
+
+function decodeHex(string: string): Uint8Array {
+  assertString(string);
+  if (string.length % 2 !== 0) {
+    throw new SyntaxError("Expected the hex string to have an even length");
+  }
+  const result = new Uint8Array(string.length / 2);
+  for (let i = 0; i < result.length; i++) {
+    const byte = string.slice(i * 2, i * 2 + 2);
+    if (!/^[0-9a-fA-F]{2}$/.test(byte)) {
+      throw new SyntaxError("Invalid hex characters: " + byte);
+    }
+    result[i] = parseInt(byte, 16);
+  }
+  return result;
+}
+
+function encodeHex(bytes: Uint8Array): string {
+  assertUint8Array(bytes);
+  let result = "";
+  for (let i = 0; i < bytes.length; i++) {
+    result += (bytes[i] < 16 ? "0" : "") + bytes[i].toString(16);
+  }
+  return result;
+}
+
+function defineUint8ArrayMethod(target: any, name: string, value: any) {
+  if (!target[name]) {
+    Object.defineProperty(target, name, {
+      value,
+      writable: true,
+      enumerable: false,
+      configurable: true,
+    });
+  }
+}
+
+defineUint8ArrayMethod(
+  Uint8Array,
+  "fromBase64",
+  function (string: string, options?: any) {
+    return decodeBase64(string, options);
+  },
+);
+
+defineUint8ArrayMethod(Uint8Array, "fromHex", function (string: string) {
+  return decodeHex(string);
+});
+
+defineUint8ArrayMethod(
+  Uint8Array.prototype,
+  "toBase64",
+  function (this: Uint8Array, options?: any) {
+    return encodeBase64(this, options);
+  },
+);
+
+defineUint8ArrayMethod(
+  Uint8Array.prototype,
+  "toHex",
+  function (this: Uint8Array) {
+    return encodeHex(this);
+  },
+);
+
+export {};
@@ -0,0 +1,70 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::Callee;
+use deno_ast::view::Expr;
+use deno_ast::view::Node;
+use deno_ast::SourceRanged;
+
+use super::Polyfill;
+use super::PolyfillVisitContext;
+use crate::ScriptTarget;
+
+pub struct SetMethodsPolyfill;
+
+impl Polyfill for SetMethodsPolyfill {
+  fn name(&self) -> &'static str {
+    "set-methods"
+  }
+
+  fn use_for_target(&self, target: ScriptTarget) -> bool {
+    (target as u32) < (ScriptTarget::ES2025 as u32)
+  }
+
+  fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
+    if let Node::CallExpr(expr) = node {
+      if let Callee::Expr(Expr::Member(callee)) = expr.callee {
+        if expr.args.len() == 1
+          && matches!(
+            callee.prop.text_fast(context.program),
+            "union"
+              | "intersection"
+              | "difference"
+              | "symmetricDifference"
+              | "isSubsetOf"
+              | "isSupersetOf"
+              | "isDisjointFrom"
+          )
+        {
+          return true;
+        }
+      }
+    }
+    false
+  }
+
+  fn get_file_text(&self) -> &'static str {
+    include_str!("./scripts/es2025.set-methods.ts")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::polyfills::PolyfillTester;
+
+  #[test]
+  pub fn finds_when_matches() {
+    let tester = PolyfillTester::new(Box::new(|| Box::new(SetMethodsPolyfill)));
+    assert_eq!(tester.matches("new Set().union(other)"), true);
+    assert_eq!(tester.matches("a.intersection(b)"), true);
+    assert_eq!(tester.matches("a.difference(b)"), true);
+    assert_eq!(tester.matches("a.symmetricDifference(b)"), true);
+    assert_eq!(tester.matches("a.isSubsetOf(b)"), true);
+    assert_eq!(tester.matches("a.isSupersetOf(b)"), true);
+    assert_eq!(tester.matches("a.isDisjointFrom(b)"), true);
+    assert_eq!(tester.matches("a.union()"), false);
+    assert_eq!(tester.matches("a.union(b, c)"), false);
+    assert_eq!(tester.matches("a.union"), false);
+    assert_eq!(tester.matches("union(b)"), false);
+  }
+}
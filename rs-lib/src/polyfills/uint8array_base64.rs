@@ -0,0 +1,73 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::Callee;
+use deno_ast::view::Expr;
+use deno_ast::view::Node;
+use deno_ast::SourceRanged;
+
+use super::Polyfill;
+use super::PolyfillVisitContext;
+use crate::ScriptTarget;
+
+pub struct Uint8ArrayBase64Polyfill;
+
+impl Polyfill for Uint8ArrayBase64Polyfill {
+  fn name(&self) -> &'static str {
+    "uint8array-base64"
+  }
+
+  fn use_for_target(&self, _target: ScriptTarget) -> bool {
+    // added after ES2025
+    true
+  }
+
+  fn visit_node(&self, node: Node, context: &PolyfillVisitContext) -> bool {
+    if context.has_global_property_access(node, "Uint8Array", "fromBase64")
+      || context.has_global_property_access(node, "Uint8Array", "fromHex")
+    {
+      return true;
+    }
+    if let Node::CallExpr(expr) = node {
+      if let Callee::Expr(Expr::Member(callee)) = expr.callee {
+        return match callee.prop.text_fast(context.program) {
+          "toBase64" => expr.args.len() <= 1,
+          "toHex" => expr.args.is_empty(),
+          _ => false,
+        };
+      }
+    }
+    false
+  }
+
+  fn get_file_text(&self) -> &'static str {
+    include_str!("./scripts/esnext.uint8array-base64.ts")
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::polyfills::PolyfillTester;
+
+  #[test]
+  pub fn finds_when_matches() {
+    let tester =
+      PolyfillTester::new(Box::new(|| Box::new(Uint8ArrayBase64Polyfill)));
+    assert_eq!(tester.matches("Uint8Array.fromBase64('AA==')"), true);
+    assert_eq!(tester.matches("Uint8Array.fromHex('ff')"), true);
+    assert_eq!(tester.matches("const { fromHex } = Uint8Array;"), true);
+    assert_eq!(
+      tester.matches("class Uint8Array {} Uint8Array.fromHex('ff')"),
+      false
+    );
+    assert_eq!(tester.matches("Other.fromBase64('AA==')"), false);
+    assert_eq!(tester.matches("bytes.toBase64()"), true);
+    assert_eq!(
+      tester.matches("bytes.toBase64({ omitPadding: true })"),
+      true
+    );
+    assert_eq!(tester.matches("bytes.toHex()"), true);
+    assert_eq!(tester.matches("bytes.toHex(1)"), false);
+    assert_eq!(tester.matches("bytes.toBase64"), false);
+  }
+}
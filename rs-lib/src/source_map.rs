@@ -0,0 +1,271 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use anyhow::Result;
+use deno_ast::ModuleSpecifier;
+use deno_ast::TextChange;
+use sourcemap::SourceMap;
+use sourcemap::SourceMapBuilder;
+
+/// Line and column (in UTF-16 code units) within a text.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct LineCol {
+  line: u32,
+  col: u32,
+}
+
+impl LineCol {
+  pub fn advance(&mut self, text: &str) {
+    for c in text.chars() {
+      self.advance_char(c);
+    }
+  }
+
+  pub fn advance_char(&mut self, c: char) {
+    if c == '\n' {
+      self.line += 1;
+      self.col = 0;
+    } else {
+      self.col += c.len_utf16() as u32;
+    }
+  }
+}
+
+/// Creates a source map from the text of the original module and the
+/// text changes that were applied to it to create the output text.
+pub fn create_source_map(
+  specifier: &ModuleSpecifier,
+  original_text: &str,
+  text_changes: &[TextChange],
+) -> Result<String> {
+  let mut text_changes = text_changes.iter().collect::<Vec<_>>();
+  text_changes.sort_by_key(|c| (c.range.start, c.range.end));
+
+  let mut builder = SourceMapBuilder::new(None);
+  let source_id = builder.add_source(specifier.as_str());
+  let mut generated = LineCol::default();
+  let mut original = LineCol::default();
+  let mut last_index = 0;
+
+  for change in text_changes {
+    add_unchanged_text(
+      &mut builder,
+      source_id,
+      &original_text[last_index..change.range.start],
+      &mut generated,
+      &mut original,
+    );
+    if !change.new_text.is_empty() {
+      add_mapping(&mut builder, source_id, generated, original);
+    }
+    generated.advance(&change.new_text);
+    original.advance(&original_text[change.range.clone()]);
+    last_index = change.range.end;
+  }
+  add_unchanged_text(
+    &mut builder,
+    source_id,
+    &original_text[last_index..],
+    &mut generated,
+    &mut original,
+  );
+
+  source_map_to_string(builder.into_sourcemap())
+}
+
+/// Updates the source map of an output file to account for text that
+/// was inserted into the output file after the source map was created.
+pub fn shift_source_map_for_insertion(
+  source_map: &str,
+  text_before_insertion: &str,
+  insertion: &TextChange,
+) -> Result<String> {
+  debug_assert!(insertion.range.is_empty());
+  let mut insert_pos = LineCol::default();
+  insert_pos.advance(&text_before_insertion[..insertion.range.start]);
+  let mut inserted_end = insert_pos;
+  inserted_end.advance(&insertion.new_text);
+
+  let source_map = SourceMap::from_slice(source_map.as_bytes())?;
+  let mut builder = SourceMapBuilder::new(None);
+  for (index, source) in source_map.sources().enumerate() {
+    let source_id = builder.add_source(source);
+    debug_assert_eq!(source_id, index as u32);
+  }
+  for token in source_map.tokens() {
+    let mut dst = LineCol {
+      line: token.get_dst_line(),
+      col: token.get_dst_col(),
+    };
+    if dst >= insert_pos {
+      if dst.line == insert_pos.line {
+        dst.col = inserted_end.col + (dst.col - insert_pos.col);
+      }
+      dst.line += inserted_end.line - insert_pos.line;
+    }
+    builder.add_raw(
+      dst.line,
+      dst.col,
+      token.get_src_line(),
+      token.get_src_col(),
+      token.has_source().then(|| token.get_src_id()),
+      None,
+      false,
+    );
+  }
+
+  source_map_to_string(builder.into_sourcemap())
+}
+
+/// Maps the unchanged text at the start of each line and each word so
+/// that positions within a line resolve to the right column.
+fn add_unchanged_text(
+  builder: &mut SourceMapBuilder,
+  source_id: u32,
+  text: &str,
+  generated: &mut LineCol,
+  original: &mut LineCol,
+) {
+  let mut previous_char: Option<char> = None;
+  for c in text.chars() {
+    let is_boundary = match previous_char {
+      None => true,
+      Some('\n') => true,
+      Some(previous_char) => is_word_char(c) && !is_word_char(previous_char),
+    };
+    if is_boundary && c != '\n' {
+      add_mapping(builder, source_id, *generated, *original);
+    }
+    generated.advance_char(c);
+    original.advance_char(c);
+    previous_char = Some(c);
+  }
+}
+
+fn add_mapping(
+  builder: &mut SourceMapBuilder,
+  source_id: u32,
+  generated: LineCol,
+  original: LineCol,
+) {
+  builder.add_raw(
+    generated.line,
+    generated.col,
+    original.line,
+    original.col,
+    Some(source_id),
+    None,
+    false,
+  );
+}
+
+fn is_word_char(c: char) -> bool {
+  c.is_alphanumeric() || c == '_' || c == '$'
+}
+
+fn source_map_to_string(source_map: SourceMap) -> Result<String> {
+  let mut bytes = Vec::new();
+  source_map.to_writer(&mut bytes)?;
+  Ok(String::from_utf8(bytes)?)
+}
+
+#[cfg(test)]
+mod test {
+  use deno_ast::apply_text_changes;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn maps_output_positions_to_original() {
+    let original_text = concat!(
+      "import { a } from \"./a.ts\";\n",
+      "// @deno-types=\"./b.d.ts\"\n",
+      "import { b } from \"./b.js\";\n",
+      "console.log(a, b);\n",
+    );
+    let text_changes = vec![
+      TextChange {
+        range: 19..25,
+        new_text: "./a.js".to_string(),
+      },
+      TextChange {
+        range: 28..53,
+        new_text: String::new(),
+      },
+      TextChange {
+        range: 0..0,
+        new_text: "import * as dntShim from \"./_dnt.shims.js\";\n".to_string(),
+      },
+    ];
+    let specifier = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+    let source_map =
+      create_source_map(&specifier, original_text, &text_changes).unwrap();
+    let output_text = apply_text_changes(original_text, text_changes);
+    assert_eq!(
+      output_text,
+      concat!(
+        "import * as dntShim from \"./_dnt.shims.js\";\n",
+        "import { a } from \"./a.js\";\n",
+        "\n",
+        "import { b } from \"./b.js\";\n",
+        "console.log(a, b);\n",
+      )
+    );
+
+    let source_map = SourceMap::from_slice(source_map.as_bytes()).unwrap();
+    assert_eq!(source_map.get_source(0), Some("file:///mod.ts"));
+    assert_lookup(&source_map, (1, 9), (0, 9));
+    assert_lookup(&source_map, (1, 19), (0, 19));
+    assert_lookup(&source_map, (3, 9), (2, 9));
+    assert_lookup(&source_map, (4, 12), (3, 12));
+    assert_lookup(&source_map, (4, 15), (3, 15));
+
+    // now insert a statement at the top of the output
+    let source_map = shift_source_map_for_insertion(
+      &source_map_to_string(source_map).unwrap(),
+      &output_text,
+      &TextChange {
+        range: 0..0,
+        new_text: "import \"./_dnt.polyfills.js\";\n".to_string(),
+      },
+    )
+    .unwrap();
+    let source_map = SourceMap::from_slice(source_map.as_bytes()).unwrap();
+    assert_lookup(&source_map, (2, 9), (0, 9));
+    assert_lookup(&source_map, (5, 15), (3, 15));
+  }
+
+  #[test]
+  fn shifts_columns_on_insertion_line() {
+    let specifier = ModuleSpecifier::parse("file:///mod.ts").unwrap();
+    let source_map =
+      create_source_map(&specifier, "/* a */ b; c;", &[]).unwrap();
+    let source_map = shift_source_map_for_insertion(
+      &source_map,
+      "/* a */ b; c;",
+      &TextChange {
+        range: 7..7,
+        new_text: "\nimport \"./x.js\";\n".to_string(),
+      },
+    )
+    .unwrap();
+    let source_map = SourceMap::from_slice(source_map.as_bytes()).unwrap();
+    assert_lookup(&source_map, (0, 3), (0, 3));
+    assert_lookup(&source_map, (2, 1), (0, 8));
+    assert_lookup(&source_map, (2, 4), (0, 11));
+  }
+
+  fn assert_lookup(
+    source_map: &SourceMap,
+    generated: (u32, u32),
+    original: (u32, u32),
+  ) {
+    let token = source_map.lookup_token(generated.0, generated.1).unwrap();
+    assert_eq!(
+      (token.get_src_line(), token.get_src_col()),
+      original,
+      "generated position {:?}",
+      generated,
+    );
+  }
+}
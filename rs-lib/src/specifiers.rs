@@ -8,6 +8,7 @@ use anyhow::Result;
 use deno_ast::ModuleSpecifier;
 use deno_graph::Module;
 use deno_graph::Resolution;
+use deno_semver::jsr::JsrPackageReqReference;
 use deno_semver::npm::NpmPackageReqReference;
 
 use crate::declaration_file_resolution::resolve_declaration_file_mappings;
@@ -68,15 +69,11 @@ pub fn get_specifiers<'a>(
         {
           found_mapped_specifiers
             .insert(module.specifier().clone(), mapped_entry);
-        } else if let Ok(npm_specifier) =
-          deno_semver::npm::NpmPackageReqReference::from_specifier(
-            module.specifier(),
-          )
+        } else if let Some(mapped_entry) =
+          get_package_mapped_specifier(module.specifier())
         {
-          found_mapped_specifiers.insert(
-            module.specifier().clone(),
-            PackageMappedSpecifier::from_npm_specifier(&npm_specifier),
-          );
+          found_mapped_specifiers
+            .insert(module.specifier().clone(), mapped_entry);
         } else {
           found_module_specifiers.push(module.specifier().clone());
 
@@ -133,14 +130,13 @@ pub fn get_specifiers<'a>(
       }
       Module::External(module) => {
         let specifier = &module.specifier;
-        if let Ok(npm_specifier) =
-          NpmPackageReqReference::from_specifier(specifier)
+        if !found_mapped_specifiers.contains_key(specifier)
+          && !specifiers.mapped_packages.contains_key(specifier)
         {
-          if !found_mapped_specifiers.contains_key(specifier) {
-            specifiers.mapped_packages.insert(
-              specifier.clone(),
-              PackageMappedSpecifier::from_npm_specifier(&npm_specifier),
-            );
+          if let Some(mapped_entry) = get_package_mapped_specifier(specifier) {
+            specifiers
+              .mapped_packages
+              .insert(specifier.clone(), mapped_entry);
           }
         }
       }
@@ -188,6 +184,21 @@ pub fn get_specifiers<'a>(
   })
 }
 
+/// Gets the package to use for an `npm:` or `jsr:` specifier.
+fn get_package_mapped_specifier(
+  specifier: &ModuleSpecifier,
+) -> Option<PackageMappedSpecifier> {
+  if let Ok(npm_specifier) = NpmPackageReqReference::from_specifier(specifier) {
+    Some(PackageMappedSpecifier::from_npm_specifier(&npm_specifier))
+  } else if let Ok(jsr_specifier) =
+    JsrPackageReqReference::from_specifier(specifier)
+  {
+    Some(PackageMappedSpecifier::from_jsr_specifier(&jsr_specifier))
+  } else {
+    None
+  }
+}
+
 fn ensure_package_mapped_specifiers_valid(
   mapped_specifiers: &BTreeMap<ModuleSpecifier, PackageMappedSpecifier>,
   test_mapped_specifiers: &BTreeMap<ModuleSpecifier, PackageMappedSpecifier>,
@@ -0,0 +1,315 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use deno_ast::ModuleSpecifier;
+use deno_ast::TextChange;
+use deno_graph::source::LoaderChecksum;
+use serde_json::json;
+use serde_json::Value;
+
+use crate::Diagnostic;
+use crate::DiagnosticPosition;
+use crate::DiagnosticRange;
+use crate::DiagnosticSeverity;
+use crate::ShimUsage;
+
+/// Storage for the result of analyzing a module, which allows modules
+/// that haven't changed since a previous run to skip being analyzed.
+///
+/// Keys are a hash of the module's source, the transform options, and
+/// the output paths of the module's dependencies. The data is opaque.
+pub trait TransformCache {
+  fn get(&self, key: &str) -> Option<Vec<u8>>;
+  /// Stores the data for the key. The cache is only an optimization,
+  /// so implementations should ignore any errors.
+  fn set(&self, key: &str, data: Vec<u8>);
+}
+
+/// Transform cache that only lives as long as the value.
+#[derive(Default)]
+pub struct InMemoryTransformCache {
+  entries: RefCell<HashMap<String, Vec<u8>>>,
+}
+
+impl InMemoryTransformCache {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn len(&self) -> usize {
+    self.entries.borrow().len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.entries.borrow().is_empty()
+  }
+}
+
+impl TransformCache for InMemoryTransformCache {
+  fn get(&self, key: &str) -> Option<Vec<u8>> {
+    self.entries.borrow().get(key).cloned()
+  }
+
+  fn set(&self, key: &str, data: Vec<u8>) {
+    self.entries.borrow_mut().insert(key.to_string(), data);
+  }
+}
+
+/// Transform cache that stores each entry as a file in a directory
+/// so that it persists between runs.
+pub struct FileTransformCache {
+  dir: PathBuf,
+}
+
+impl FileTransformCache {
+  pub fn new(dir: impl Into<PathBuf>) -> Self {
+    Self { dir: dir.into() }
+  }
+}
+
+impl TransformCache for FileTransformCache {
+  fn get(&self, key: &str) -> Option<Vec<u8>> {
+    std::fs::read(self.dir.join(key)).ok()
+  }
+
+  fn set(&self, key: &str, data: Vec<u8>) {
+    // write to a temporary file first so another process never
+    // reads a partially written entry
+    let file_path = self.dir.join(key);
+    let temp_file_path = file_path.with_extension("tmp");
+    let _ = std::fs::create_dir_all(&self.dir)
+      .and_then(|_| std::fs::write(&temp_file_path, data))
+      .and_then(|_| std::fs::rename(&temp_file_path, &file_path));
+  }
+}
+
+/// Gets the key of a cache entry from all the inputs that
+/// affect how a module is transformed.
+pub(crate) fn get_cache_key(inputs: &[&str]) -> String {
+  let mut data = String::new();
+  data.push_str(env!("CARGO_PKG_VERSION"));
+  for input in inputs {
+    data.push('\0');
+    data.push_str(input);
+  }
+  LoaderChecksum::gen(data.as_bytes())
+}
+
+/// The result of analyzing a module, which is what gets cached.
+pub(crate) struct ModuleTransform {
+  pub text_changes: Vec<TextChange>,
+  /// Names of the polyfills the module uses.
+  pub polyfills: Vec<String>,
+  pub diagnostics: Vec<Diagnostic>,
+  /// Shim global names the module uses along with where.
+  pub shim_usages: Vec<ShimUsage>,
+}
+
+impl ModuleTransform {
+  pub fn to_bytes(&self) -> Vec<u8> {
+    let text_changes = self
+      .text_changes
+      .iter()
+      .map(|c| json!([c.range.start, c.range.end, c.new_text]))
+      .collect::<Vec<_>>();
+    let value = json!({
+      "textChanges": text_changes,
+      "polyfills": self.polyfills,
+      "diagnostics": self
+        .diagnostics
+        .iter()
+        .map(diagnostic_to_json)
+        .collect::<Vec<_>>(),
+      "shimUsages": self
+        .shim_usages
+        .iter()
+        .map(|u| {
+          let ranges = u.ranges.iter().map(range_to_json).collect::<Vec<_>>();
+          json!({ "name": u.name, "ranges": ranges })
+        })
+        .collect::<Vec<_>>(),
+    });
+    serde_json::to_vec(&value).unwrap()
+  }
+
+  /// Deserializes a cache entry, returning `None` when it's not valid
+  /// for the module's source text.
+  pub fn from_bytes(data: &[u8], source_text: &str) -> Option<Self> {
+    fn as_strings(value: &Value) -> Option<Vec<String>> {
+      value
+        .as_array()?
+        .iter()
+        .map(|v| v.as_str().map(|s| s.to_string()))
+        .collect()
+    }
+
+    let value: Value = serde_json::from_slice(data).ok()?;
+    let text_changes = value
+      .get("textChanges")?
+      .as_array()?
+      .iter()
+      .map(|change| {
+        let start = change.get(0)?.as_u64()? as usize;
+        let end = change.get(1)?.as_u64()? as usize;
+        let new_text = change.get(2)?.as_str()?.to_string();
+        let is_valid_range = start <= end
+          && source_text.is_char_boundary(start)
+          && source_text.is_char_boundary(end);
+        is_valid_range.then_some(TextChange {
+          range: start..end,
+          new_text,
+        })
+      })
+      .collect::<Option<Vec<_>>>()?;
+    Some(Self {
+      text_changes,
+      polyfills: as_strings(value.get("polyfills")?)?,
+      diagnostics: value
+        .get("diagnostics")?
+        .as_array()?
+        .iter()
+        .map(diagnostic_from_json)
+        .collect::<Option<Vec<_>>>()?,
+      shim_usages: value
+        .get("shimUsages")?
+        .as_array()?
+        .iter()
+        .map(|usage| {
+          Some(ShimUsage {
+            name: usage.get("name")?.as_str()?.to_string(),
+            ranges: usage
+              .get("ranges")?
+              .as_array()?
+              .iter()
+              .map(range_from_json)
+              .collect::<Option<Vec<_>>>()?,
+          })
+        })
+        .collect::<Option<Vec<_>>>()?,
+    })
+  }
+}
+
+fn range_to_json(range: &DiagnosticRange) -> Value {
+  json!([
+    range.start.line,
+    range.start.character,
+    range.end.line,
+    range.end.character
+  ])
+}
+
+fn range_from_json(value: &Value) -> Option<DiagnosticRange> {
+  let get = |index: usize| Some(value.get(index)?.as_u64()? as usize);
+  Some(DiagnosticRange {
+    start: DiagnosticPosition {
+      line: get(0)?,
+      character: get(1)?,
+    },
+    end: DiagnosticPosition {
+      line: get(2)?,
+      character: get(3)?,
+    },
+  })
+}
+
+fn diagnostic_to_json(diagnostic: &Diagnostic) -> Value {
+  json!({
+    "code": diagnostic.code.as_str(),
+    "error": diagnostic.severity == DiagnosticSeverity::Error,
+    "specifier": diagnostic.specifier.as_ref().map(|s| s.as_str()),
+    "range": diagnostic.range.as_ref().map(range_to_json),
+    "message": diagnostic.message,
+  })
+}
+
+fn diagnostic_from_json(value: &Value) -> Option<Diagnostic> {
+  let range = match value.get("range")? {
+    Value::Null => None,
+    range => Some(range_from_json(range)?),
+  };
+  let specifier = match value.get("specifier")? {
+    Value::Null => None,
+    specifier => Some(ModuleSpecifier::parse(specifier.as_str()?).ok()?),
+  };
+  Some(Diagnostic {
+    code: value.get("code")?.as_str()?.parse().ok()?,
+    severity: if value.get("error")?.as_bool()? {
+      DiagnosticSeverity::Error
+    } else {
+      DiagnosticSeverity::Warning
+    },
+    specifier,
+    range,
+    message: value.get("message")?.as_str()?.to_string(),
+  })
+}
+
+#[cfg(test)]
+mod test {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::DiagnosticCode;
+
+  #[test]
+  fn module_transform_round_trips() {
+    let transform = ModuleTransform {
+      text_changes: vec![TextChange {
+        range: 1..3,
+        new_text: "./a.js".to_string(),
+      }],
+      polyfills: vec!["object-has-own".to_string()],
+      diagnostics: vec![Diagnostic::warning(
+        DiagnosticCode::DenoShimIgnoreRenamed,
+        Some(ModuleSpecifier::parse("file:///mod.ts").unwrap()),
+        "warning".to_string(),
+      )
+      .with_range(DiagnosticRange {
+        start: DiagnosticPosition {
+          line: 0,
+          character: 1,
+        },
+        end: DiagnosticPosition {
+          line: 2,
+          character: 3,
+        },
+      })],
+      shim_usages: vec![ShimUsage {
+        name: "Deno.readTextFile".to_string(),
+        ranges: vec![DiagnosticRange {
+          start: DiagnosticPosition {
+            line: 1,
+            character: 0,
+          },
+          end: DiagnosticPosition {
+            line: 1,
+            character: 17,
+          },
+        }],
+      }],
+    };
+    let result =
+      ModuleTransform::from_bytes(&transform.to_bytes(), "'a.ts'").unwrap();
+    assert_eq!(result.text_changes.len(), 1);
+    assert_eq!(result.text_changes[0].range, 1..3);
+    assert_eq!(result.text_changes[0].new_text, "./a.js");
+    assert_eq!(result.polyfills, transform.polyfills);
+    assert_eq!(result.diagnostics, transform.diagnostics);
+    assert_eq!(result.shim_usages, transform.shim_usages);
+
+    // invalid for this source text
+    assert!(ModuleTransform::from_bytes(&transform.to_bytes(), "'").is_none());
+    assert!(ModuleTransform::from_bytes(b"{", "'a.ts'").is_none());
+  }
+
+  #[test]
+  fn cache_key_changes_with_inputs() {
+    assert_eq!(get_cache_key(&["a", "b"]), get_cache_key(&["a", "b"]));
+    assert_ne!(get_cache_key(&["a", "b"]), get_cache_key(&["ab"]));
+    assert_ne!(get_cache_key(&["a", "b"]), get_cache_key(&["a", "c"]));
+  }
+}
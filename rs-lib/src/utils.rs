@@ -23,20 +23,17 @@ pub const BOM_CHAR: char = '\u{FEFF}';
 pub fn get_relative_specifier(
   from: impl AsRef<Path>,
   to: impl AsRef<Path>,
+  preserve_module_format_extensions: bool,
 ) -> String {
-  let to = with_extension(
-    to.as_ref(),
-    if to
-      .as_ref()
-      .to_string_lossy()
-      .to_lowercase()
-      .ends_with(".d.ts")
-    {
-      ""
-    } else {
-      "js"
-    },
-  );
+  let to = to.as_ref();
+  let ext = if preserve_module_format_extensions {
+    get_specifier_ext(to)
+  } else if to.to_string_lossy().to_lowercase().ends_with(".d.ts") {
+    ""
+  } else {
+    "js"
+  };
+  let to = with_extension(to, ext);
   let relative_path = get_relative_path(from, to);
   let relative_path_str = relative_path
     .to_string_lossy()
@@ -51,6 +48,21 @@ pub fn get_relative_specifier(
   }
 }
 
+/// Gets the extension a file's output should be imported with, which
+/// keeps its module format (ex. `mjs` for `mod.mts` or `mod.d.mts`).
+fn get_specifier_ext(path: &Path) -> &'static str {
+  let lower = path.to_string_lossy().to_lowercase();
+  if lower.ends_with(".d.ts") {
+    ""
+  } else if lower.ends_with(".mts") || lower.ends_with(".mjs") {
+    "mjs"
+  } else if lower.ends_with(".cts") || lower.ends_with(".cjs") {
+    "cjs"
+  } else {
+    "js"
+  }
+}
+
 pub fn get_relative_path(
   from: impl AsRef<Path>,
   to: impl AsRef<Path>,
@@ -139,6 +151,13 @@ pub fn path_with_stem_suffix(path: &Path, suffix: &str) -> PathBuf {
   }
 }
 
+/// Gets the name of a shim's binding in the shim file, which for a
+/// member of a global is the names joined with underscores (ex.
+/// `Deno.readTextFile` to `Deno_readTextFile`).
+pub fn get_shim_binding_name(global_name: &str) -> String {
+  global_name.replace('.', "_")
+}
+
 /// Strips the byte order mark from the provided text if it exists.
 pub fn strip_bom(text: &str) -> &str {
   if text.starts_with(BOM_CHAR) {
@@ -165,11 +184,13 @@ pub fn partition_by_root_specifiers<'a>(
   root_specifiers
 }
 
+/// Prepends the statement to the file text, returning the text change
+/// that was applied.
 pub fn prepend_statement_to_text(
   file_path: &Path,
   file_text: &mut String,
   statement_text: &str,
-) {
+) -> TextChange {
   // It's not great to have to reparse the file for this. Perhaps there is a utility
   // function in swc or maybe add one to deno_ast for parsing out the leading comments
   let text = std::mem::take(file_text);
@@ -191,11 +212,17 @@ pub fn prepend_statement_to_text(
     Ok(parsed_module) => parsed_module.with_view(|program| {
       let text_change =
         text_change_for_prepend_statement_to_text(program, statement_text);
-      *file_text = apply_text_changes(text.as_ref(), vec![text_change]);
+      *file_text = apply_text_changes(text.as_ref(), vec![text_change.clone()]);
+      text_change
     }),
     Err(_) => {
       // should never happen... fallback...
-      *file_text = format!("{}\n{}", statement_text, text);
+      let text_change = TextChange {
+        range: 0..0,
+        new_text: format!("{}\n", statement_text),
+      };
+      *file_text = apply_text_changes(text.as_ref(), vec![text_change.clone()]);
+      text_change
     }
   }
 }
@@ -229,12 +256,21 @@ fn top_file_insert_pos(program: Program) -> usize {
   pos
 }
 
-/// `with_extension` that handles `.d.ts` files
+/// Gets the length of the path's declaration file extension
+/// (ex. `.d.mts`) when it has one.
+pub fn declaration_ext_len(path: &str) -> Option<usize> {
+  let lower = path.to_lowercase();
+  [".d.ts", ".d.mts", ".d.cts"]
+    .iter()
+    .find(|ext| lower.ends_with(*ext))
+    .map(|ext| ext.len())
+}
+
+/// `with_extension` that handles declaration files (ex. `.d.ts`)
 pub fn with_extension(path: &Path, ext: &str) -> PathBuf {
-  let lower = path.to_string_lossy().to_lowercase();
-  if lower.ends_with(".d.ts") {
-    let path_str = path.to_string_lossy();
-    let prefix = &path_str[..path_str.len() - 5];
+  let path_str = path.to_string_lossy();
+  if let Some(ext_len) = declaration_ext_len(&path_str) {
+    let prefix = &path_str[..path_str.len() - ext_len];
     PathBuf::from(if ext.is_empty() {
       prefix.to_string()
     } else {
@@ -431,5 +467,13 @@ mod test {
       with_extension(&PathBuf::from("/test/test.d.ts"), ""),
       PathBuf::from("/test/test")
     );
+    assert_eq!(
+      with_extension(&PathBuf::from("/test/test.d.mts"), "mjs"),
+      PathBuf::from("/test/test.mjs")
+    );
+    assert_eq!(
+      with_extension(&PathBuf::from("/test/test.D.CTS"), ""),
+      PathBuf::from("/test/test")
+    );
   }
 }
@@ -0,0 +1,496 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use std::collections::HashSet;
+use std::ops::Range;
+
+use deno_ast::swc::common::SyntaxContext;
+use deno_ast::view::*;
+use deno_ast::ModuleSpecifier;
+use deno_ast::SourcePos;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+use deno_ast::SourceTextInfoProvider;
+use deno_ast::TextChange;
+
+use super::get_all_ident_names;
+use super::get_unique_name;
+use crate::utils::text_change_for_prepend_statement_to_text;
+use crate::Diagnostic;
+use crate::DiagnosticCode;
+use crate::DiagnosticRange;
+
+pub struct GetDenoTestTextChangesParams<'a, 'b> {
+  pub specifier: &'a ModuleSpecifier,
+  pub program: Program<'b>,
+  pub unresolved_context: SyntaxContext,
+  pub top_level_decls: &'a HashSet<String>,
+}
+
+pub struct GetDenoTestTextChangesResult {
+  pub text_changes: Vec<TextChange>,
+  /// Ranges of the original text that were replaced. Other text
+  /// changes shouldn't be made within these.
+  pub replaced_ranges: Vec<Range<usize>>,
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+struct Context<'a, 'b> {
+  specifier: &'a ModuleSpecifier,
+  program: Program<'b>,
+  unresolved_context: SyntaxContext,
+  top_level_decls: &'a HashSet<String>,
+  /// Local name of the `test` import from `node:test`.
+  test_name: String,
+  /// Names of the test context parameters in scope (ex. `t`).
+  test_context_names: Vec<&'b str>,
+  converted: bool,
+  text_changes: Vec<TextChange>,
+  replaced_ranges: Vec<Range<usize>>,
+  diagnostics: Vec<Diagnostic>,
+}
+
+/// A piece of the original text to keep along with the text to
+/// replace the text before it with.
+struct Segment {
+  prefix: String,
+  range: SourceRange,
+}
+
+/// Gets the text changes that convert `Deno.test(...)` and
+/// `t.step(...)` registrations to `node:test`'s `test(...)` and
+/// `t.test(...)`.
+pub fn get_deno_test_text_changes(
+  params: &GetDenoTestTextChangesParams,
+) -> GetDenoTestTextChangesResult {
+  let mut context = Context {
+    specifier: params.specifier,
+    program: params.program,
+    unresolved_context: params.unresolved_context,
+    top_level_decls: params.top_level_decls,
+    // a local binding could shadow the import, so avoid all the names
+    test_name: get_unique_name("test", &get_all_ident_names(params.program)),
+    test_context_names: Vec::new(),
+    converted: false,
+    text_changes: Vec::new(),
+    replaced_ranges: Vec::new(),
+    diagnostics: Vec::new(),
+  };
+
+  visit_children(params.program.into(), &mut context);
+
+  if context.converted {
+    let import_text = if context.test_name == "test" {
+      "import { test } from \"node:test\";".to_string()
+    } else {
+      format!(
+        "import {{ test as {} }} from \"node:test\";",
+        context.test_name
+      )
+    };
+    context
+      .text_changes
+      .push(text_change_for_prepend_statement_to_text(
+        params.program,
+        &import_text,
+      ));
+  }
+
+  GetDenoTestTextChangesResult {
+    text_changes: context.text_changes,
+    replaced_ranges: context.replaced_ranges,
+    diagnostics: context.diagnostics,
+  }
+}
+
+fn visit_children<'b>(node: Node<'b>, context: &mut Context<'_, 'b>) {
+  for child in node.children() {
+    visit_node(child, context);
+  }
+}
+
+fn visit_node<'b>(node: Node<'b>, context: &mut Context<'_, 'b>) {
+  if let Node::CallExpr(call_expr) = node {
+    if let Some(new_callee_text) = get_new_callee_text(call_expr, context) {
+      if !convert_test_call(call_expr, new_callee_text, context) {
+        add_diagnostic(call_expr, context);
+        visit_children(node, context);
+      }
+      return;
+    }
+  }
+
+  visit_children(node, context);
+}
+
+/// Gets the text to replace the callee with when the call is
+/// `Deno.test`, `Deno.test.ignore`, `Deno.test.only` or `t.step`.
+fn get_new_callee_text(
+  call_expr: &CallExpr,
+  context: &Context,
+) -> Option<String> {
+  let Callee::Expr(Expr::Member(member_expr)) = call_expr.callee else {
+    return None;
+  };
+  let MemberProp::Ident(prop) = member_expr.prop else {
+    return None;
+  };
+  match (member_expr.obj, prop.sym().as_ref()) {
+    (obj, "test") if is_deno_global(obj, context) => {
+      Some(context.test_name.clone())
+    }
+    (Expr::Member(obj), "ignore" | "only") => {
+      let MemberProp::Ident(obj_prop) = obj.prop else {
+        return None;
+      };
+      if obj_prop.sym() == "test" && is_deno_global(obj.obj, context) {
+        Some(format!(
+          "{}.{}",
+          context.test_name,
+          if prop.sym() == "ignore" {
+            "skip"
+          } else {
+            "only"
+          }
+        ))
+      } else {
+        None
+      }
+    }
+    (Expr::Ident(ident), "step") => {
+      let name = ident.text_fast(context.program);
+      if context.test_context_names.contains(&name) {
+        Some(format!("{}.test", name))
+      } else {
+        None
+      }
+    }
+    _ => None,
+  }
+}
+
+fn is_deno_global(expr: Expr, context: &Context) -> bool {
+  match expr {
+    Expr::Ident(ident) => {
+      ident.sym() == "Deno"
+        && ident.inner.to_id().1 == context.unresolved_context
+        && !context.top_level_decls.contains("Deno")
+    }
+    _ => false,
+  }
+}
+
+/// Converts the call, returning `false` when its arguments
+/// couldn't be analyzed.
+fn convert_test_call<'b>(
+  call_expr: &'b CallExpr<'b>,
+  new_callee_text: String,
+  context: &mut Context<'_, 'b>,
+) -> bool {
+  if call_expr.args.iter().any(|arg| arg.spread().is_some()) {
+    return false;
+  }
+  let args = call_expr.args.iter().map(|a| a.expr).collect::<Vec<_>>();
+  let (maybe_name, maybe_options, test_fn) = match args.as_slice() {
+    // Deno.test({ name, fn, ...options })
+    [Expr::Object(definition)] => {
+      return convert_test_definition(
+        call_expr,
+        definition,
+        new_callee_text,
+        context,
+      );
+    }
+    // Deno.test(fn)
+    [test_fn] => (None, None, *test_fn),
+    // Deno.test(options, fn)
+    [Expr::Object(options), test_fn] => (None, Some(*options), *test_fn),
+    // Deno.test(name, fn)
+    [name, test_fn] => (Some(*name), None, *test_fn),
+    // Deno.test(name, options, fn)
+    [name, Expr::Object(options), test_fn] => {
+      (Some(*name), Some(*options), *test_fn)
+    }
+    _ => return false,
+  };
+  let maybe_option_segments = match maybe_options {
+    Some(options) => match get_option_segments(options.props, context) {
+      Some(segments) => Some((options, segments)),
+      None => return false,
+    },
+    None => None,
+  };
+
+  push_text_change(call_expr.callee.range(), new_callee_text, context);
+  if let Some((options, mut segments)) = maybe_option_segments {
+    if let Some(first) = segments.first_mut() {
+      first.prefix.insert_str(0, "{ ");
+    }
+    let end_text = if segments.is_empty() { "{}" } else { " }" };
+    push_segment_text_changes(
+      options.range(),
+      segments,
+      end_text.to_string(),
+      context,
+    );
+  }
+  if let Some(name) = maybe_name {
+    visit_node(name.into(), context);
+  }
+  visit_test_fn(test_fn.into(), get_test_fn_param_name(test_fn), context);
+  true
+}
+
+/// Converts `Deno.test({ name, ...options, fn })` to
+/// `test(name, { ...options }, fn)`.
+fn convert_test_definition<'b>(
+  call_expr: &'b CallExpr<'b>,
+  definition: &'b ObjectLit<'b>,
+  new_callee_text: String,
+  context: &mut Context<'_, 'b>,
+) -> bool {
+  let Some((PropOrSpread::Prop(fn_prop), other_props)) =
+    definition.props.split_last()
+  else {
+    return false;
+  };
+  let (maybe_name, option_props) = match other_props.split_first() {
+    Some((PropOrSpread::Prop(prop), option_props))
+      if get_prop_key(prop, context) == Some("name") =>
+    {
+      let name: Node = match prop {
+        Prop::KeyValue(prop) => prop.value.into(),
+        Prop::Shorthand(ident) => (*ident).into(),
+        _ => return false,
+      };
+      (Some(name), option_props)
+    }
+    _ => (None, other_props),
+  };
+  if get_prop_key(fn_prop, context) != Some("fn") {
+    return false;
+  }
+  let Some(mut option_segments) = get_option_segments(option_props, context)
+  else {
+    return false;
+  };
+
+  let mut segments = Vec::with_capacity(option_segments.len() + 2);
+  if let Some(name) = maybe_name {
+    segments.push(Segment {
+      prefix: String::new(),
+      range: name.range(),
+    });
+  }
+  let has_options = !option_segments.is_empty();
+  if let Some(first) = option_segments.first_mut() {
+    first.prefix = format!(
+      "{}{{ {}",
+      if segments.is_empty() { "" } else { ", " },
+      first.prefix
+    );
+  }
+  segments.extend(option_segments);
+
+  let mut fn_prefix = format!(
+    "{}{}",
+    if has_options { " }" } else { "" },
+    if segments.is_empty() { "" } else { ", " },
+  );
+  let (fn_range, fn_node, maybe_param_name) = match fn_prop {
+    Prop::KeyValue(prop) => (
+      prop.value.range(),
+      prop.value.into(),
+      get_test_fn_param_name(prop.value),
+    ),
+    Prop::Shorthand(ident) => (ident.range(), (*ident).into(), None),
+    Prop::Method(method) => {
+      let function = method.function;
+      if function.is_async() {
+        fn_prefix.push_str("async ");
+      }
+      fn_prefix.push_str("function");
+      if function.is_generator() {
+        fn_prefix.push('*');
+      }
+      (
+        SourceRange::new(method.key.end(), method.end()),
+        function.into(),
+        get_first_param_name(function.params.iter().map(|p| &p.pat)),
+      )
+    }
+    _ => return false,
+  };
+  segments.push(Segment {
+    prefix: fn_prefix,
+    range: fn_range,
+  });
+  if let Some(first) = segments.first_mut() {
+    first.prefix.insert_str(0, &format!("{}(", new_callee_text));
+  }
+
+  push_segment_text_changes(
+    SourceRange::new(call_expr.callee.start(), call_expr.end()),
+    segments,
+    ")".to_string(),
+    context,
+  );
+  if let Some(name) = maybe_name {
+    visit_node(name, context);
+  }
+  visit_test_fn(fn_node, maybe_param_name, context);
+  true
+}
+
+/// Gets the segments of the option properties to keep, mapping
+/// `ignore` to `skip`. Options `node:test` doesn't have (ex.
+/// `permissions`) are removed.
+fn get_option_segments<'b>(
+  props: &[PropOrSpread<'b>],
+  context: &Context<'_, 'b>,
+) -> Option<Vec<Segment>> {
+  let mut segments = Vec::new();
+  for prop in props {
+    let PropOrSpread::Prop(prop) = prop else {
+      return None;
+    };
+    let key = get_prop_key(prop, context)?;
+    let new_key = match key {
+      "ignore" => "skip",
+      "only" => "only",
+      _ => continue,
+    };
+    let range = match prop {
+      Prop::KeyValue(prop) => prop.value.range(),
+      Prop::Shorthand(ident) => ident.range(),
+      _ => return None,
+    };
+    segments.push(Segment {
+      prefix: format!(
+        "{}{}: ",
+        if segments.is_empty() { "" } else { ", " },
+        new_key
+      ),
+      range,
+    });
+  }
+  Some(segments)
+}
+
+fn get_prop_key<'b>(
+  prop: &Prop<'b>,
+  context: &Context<'_, 'b>,
+) -> Option<&'b str> {
+  let key = match prop {
+    Prop::KeyValue(prop) => &prop.key,
+    Prop::Method(prop) => &prop.key,
+    Prop::Shorthand(ident) => return Some(ident.text_fast(context.program)),
+    _ => return None,
+  };
+  match key {
+    PropName::Ident(ident) => Some(ident.text_fast(context.program)),
+    PropName::Str(str) => {
+      let text = str.text_fast(context.program);
+      Some(&text[1..text.len() - 1])
+    }
+    _ => None,
+  }
+}
+
+fn get_test_fn_param_name(test_fn: Expr) -> Option<&str> {
+  match test_fn {
+    Expr::Arrow(arrow) => get_first_param_name(arrow.params.iter()),
+    Expr::Fn(fn_expr) => {
+      get_first_param_name(fn_expr.function.params.iter().map(|p| &p.pat))
+    }
+    _ => None,
+  }
+}
+
+fn get_first_param_name<'b>(
+  mut params: impl Iterator<Item = &'b Pat<'b>>,
+) -> Option<&'b str> {
+  match params.next()? {
+    Pat::Ident(ident) => Some(ident.id.sym().as_ref()),
+    _ => None,
+  }
+}
+
+fn visit_test_fn<'b>(
+  node: Node<'b>,
+  maybe_param_name: Option<&'b str>,
+  context: &mut Context<'_, 'b>,
+) {
+  match maybe_param_name {
+    Some(param_name) => {
+      context.test_context_names.push(param_name);
+      visit_node(node, context);
+      context.test_context_names.pop();
+    }
+    None => visit_node(node, context),
+  }
+}
+
+/// Replaces the text around the segments in the range with each
+/// segment's prefix and then the end text.
+fn push_segment_text_changes(
+  range: SourceRange,
+  segments: Vec<Segment>,
+  end_text: String,
+  context: &mut Context,
+) {
+  let mut pos = range.start;
+  for segment in segments {
+    push_text_change(
+      SourceRange::new(pos, segment.range.start),
+      segment.prefix,
+      context,
+    );
+    pos = segment.range.end;
+  }
+  push_text_change(SourceRange::new(pos, range.end), end_text, context);
+}
+
+fn push_text_change(
+  range: SourceRange,
+  new_text: String,
+  context: &mut Context,
+) {
+  let range = create_range(range.start, range.end, context);
+  if range.is_empty() && new_text.is_empty() {
+    return;
+  }
+  context.converted = true;
+  context.replaced_ranges.push(range.clone());
+  context.text_changes.push(TextChange { range, new_text });
+}
+
+fn add_diagnostic(call_expr: &CallExpr, context: &mut Context) {
+  let text_info = context.program.text_info();
+  let display = text_info.line_and_column_display(call_expr.start());
+  context.diagnostics.push(
+    Diagnostic::warning(
+      DiagnosticCode::DenoTest,
+      Some(context.specifier.clone()),
+      format!(
+        "Could not convert the {} call at {}:{}:{} to node:test.",
+        call_expr.callee.text_fast(context.program),
+        context.specifier,
+        display.line_number,
+        display.column_number,
+      ),
+    )
+    .with_range(DiagnosticRange::from_source_range(
+      call_expr.range(),
+      text_info,
+    )),
+  );
+}
+
+fn create_range(
+  start: SourcePos,
+  end: SourcePos,
+  context: &Context,
+) -> std::ops::Range<usize> {
+  SourceRange::new(start, end)
+    .as_byte_range(context.program.text_info().range().start)
+}
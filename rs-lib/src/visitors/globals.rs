@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
+use std::collections::BTreeMap;
 use std::collections::HashSet;
 
 use deno_ast::swc::common::SyntaxContext;
@@ -11,7 +12,10 @@ use deno_ast::SourceTextInfoProvider;
 use deno_ast::TextChange;
 
 use crate::analyze::is_in_type;
+use crate::utils::get_shim_binding_name;
 use crate::utils::text_change_for_prepend_statement_to_text;
+use crate::DiagnosticRange;
+use crate::ShimUsage;
 
 pub struct GetGlobalTextChangesParams<'a, 'b> {
   pub program: Program<'b>,
@@ -20,11 +24,14 @@ pub struct GetGlobalTextChangesParams<'a, 'b> {
   pub shim_global_names: &'a HashSet<&'a str>,
   pub ignore_line_indexes: &'a HashSet<usize>,
   pub top_level_decls: &'a HashSet<String>,
+  /// Ranges of text already replaced by other text changes.
+  pub ignore_ranges: &'a [std::ops::Range<usize>],
 }
 
 pub struct GetGlobalTextChangesResult {
   pub text_changes: Vec<TextChange>,
-  pub imported_shim: bool,
+  /// Shim global names the module uses along with where, sorted by name.
+  pub shim_usages: Vec<ShimUsage>,
 }
 
 struct Context<'a, 'b> {
@@ -33,8 +40,10 @@ struct Context<'a, 'b> {
   top_level_decls: &'a HashSet<String>,
   shim_global_names: &'a HashSet<&'a str>,
   import_shim: bool,
+  shim_usages: BTreeMap<String, Vec<DiagnosticRange>>,
   text_changes: Vec<TextChange>,
   ignore_line_indexes: &'a HashSet<usize>,
+  ignore_ranges: &'a [std::ops::Range<usize>],
 }
 
 pub fn get_global_text_changes(
@@ -46,8 +55,10 @@ pub fn get_global_text_changes(
     top_level_decls: params.top_level_decls,
     shim_global_names: params.shim_global_names,
     import_shim: false,
+    shim_usages: BTreeMap::new(),
     text_changes: Vec::new(),
     ignore_line_indexes: params.ignore_line_indexes,
+    ignore_ranges: params.ignore_ranges,
   };
   let program = params.program;
 
@@ -72,7 +83,11 @@ pub fn get_global_text_changes(
 
   GetGlobalTextChangesResult {
     text_changes: context.text_changes,
-    imported_shim: context.import_shim,
+    shim_usages: context
+      .shim_usages
+      .into_iter()
+      .map(|(name, ranges)| ShimUsage { name, ranges })
+      .collect(),
   }
 }
 
@@ -82,6 +97,9 @@ fn visit_children(node: Node, import_name: &str, context: &mut Context) {
   }
 
   if let Node::Ident(ident) = node {
+    if is_in_ignore_range(ident, context) {
+      return;
+    }
     let id = ident.inner.to_id();
     let is_unresolved_context = id.1 == context.unresolved_context;
     let ident_text = ident.text_fast(context.program);
@@ -91,31 +109,40 @@ fn visit_children(node: Node, import_name: &str, context: &mut Context) {
       if ident_text == "window" {
         if !context.top_level_decls.contains("window")
           && !has_ignore_comment(ident.into(), context)
+          && !visit_global_this(ident, import_name, context)
         {
-          if let Some(text_change) =
-            get_global_this_text_change(ident, import_name, context)
-          {
-            context.text_changes.push(text_change);
-            context.import_shim = true;
-          } else {
-            context.text_changes.push(TextChange {
-              range: create_range(ident.start(), ident.end(), context),
-              new_text: "globalThis".to_string(),
-            });
-          }
+          context.text_changes.push(TextChange {
+            range: create_range(ident.start(), ident.end(), context),
+            new_text: "globalThis".to_string(),
+          });
         }
         return;
       }
 
       // check to replace globalThis
       if ident_text == "globalThis" {
-        if let Some(text_change) =
-          get_global_this_text_change(ident, import_name, context)
+        visit_global_this(ident, import_name, context);
+        return;
+      }
+
+      // check if a member of the global should be imported
+      // (ex. `Deno.readTextFile`)
+      if let Some((range, member_name)) = get_shim_member(ident, context) {
+        if !context.top_level_decls.contains(ident_text)
+          && !should_ignore(ident.into(), context)
         {
-          context.text_changes.push(text_change);
+          context.text_changes.push(TextChange {
+            range: create_range(range.start, range.end, context),
+            new_text: format!(
+              "{}.{}",
+              import_name,
+              get_shim_binding_name(&member_name)
+            ),
+          });
+          add_shim_usage(member_name, range, context);
           context.import_shim = true;
+          return;
         }
-        return;
       }
 
       // check if global should be imported
@@ -128,6 +155,7 @@ fn visit_children(node: Node, import_name: &str, context: &mut Context) {
             range: create_range(ident.start(), ident.end(), context),
             new_text: format!("{}.{}", import_name, ident_text),
           });
+          add_shim_usage(name.to_string(), ident.range(), context);
           context.import_shim = true;
           return;
         }
@@ -136,44 +164,88 @@ fn visit_children(node: Node, import_name: &str, context: &mut Context) {
   }
 }
 
-fn get_global_this_text_change(
+/// Replaces `globalThis` with the shim's `dntGlobalThis`, returning
+/// `false` when it should stay as is.
+fn visit_global_this(
   ident: &Ident,
   import_name: &str,
-  context: &Context,
-) -> Option<TextChange> {
+  context: &mut Context,
+) -> bool {
   if should_ignore_global_this(ident, context) {
-    return None;
+    return false;
   }
+  let global_this_text_change = TextChange {
+    range: create_range(ident.start(), ident.end(), context),
+    new_text: format!("{}.dntGlobalThis", import_name),
+  };
   if is_in_type(ident.into()) {
     match ident.parent() {
       Node::TsQualifiedName(parent) => {
         let right_name = parent.right.text_fast(context.program);
-        if context.shim_global_names.contains(&right_name) {
-          Some(TextChange {
-            range: create_range(parent.start(), parent.end(), context),
-            new_text: format!(
-              "{}.{}",
-              import_name,
-              // doesn't seem exactly right... will wait for a bug to open
-              parent.right.text_fast(context.program),
-            ),
-          })
-        } else {
-          None
+        if !context.shim_global_names.contains(&right_name) {
+          return false;
         }
+        context.text_changes.push(TextChange {
+          range: create_range(parent.start(), parent.end(), context),
+          new_text: format!(
+            "{}.{}",
+            import_name,
+            // doesn't seem exactly right... will wait for a bug to open
+            right_name,
+          ),
+        });
+        add_shim_usage(right_name.to_string(), parent.range(), context);
+      }
+      Node::TsTypeQuery(_) => {
+        context.text_changes.push(global_this_text_change);
+        add_shim_usage("globalThis".to_string(), ident.range(), context);
       }
-      Node::TsTypeQuery(_) => Some(TextChange {
-        range: create_range(ident.start(), ident.end(), context),
-        new_text: format!("{}.dntGlobalThis", import_name),
-      }),
-      _ => None,
+      _ => return false,
     }
   } else {
-    Some(TextChange {
-      range: create_range(ident.start(), ident.end(), context),
-      new_text: format!("{}.dntGlobalThis", import_name),
-    })
+    context.text_changes.push(global_this_text_change);
+    add_shim_usage("globalThis".to_string(), ident.range(), context);
   }
+  context.import_shim = true;
+  true
+}
+
+fn add_shim_usage(name: String, range: SourceRange, context: &mut Context) {
+  let range =
+    DiagnosticRange::from_source_range(range, context.program.text_info());
+  context.shim_usages.entry(name).or_default().push(range);
+}
+
+/// Gets the range and name of the longest member of the identifier
+/// that's a shim global name (ex. `Deno.readTextFile` for `Deno`).
+fn get_shim_member(
+  ident: &Ident,
+  context: &Context,
+) -> Option<(SourceRange, String)> {
+  let mut node: Node = ident.into();
+  let mut name = ident.text_fast(context.program).to_string();
+  let mut result = None;
+  while let Some(parent) = node.parent() {
+    let (parent_range, prop_name) = match parent {
+      Node::MemberExpr(parent) if parent.obj.range() == node.range() => {
+        match parent.prop {
+          MemberProp::Ident(prop) => (parent.range(), prop.sym().as_ref()),
+          _ => break,
+        }
+      }
+      Node::TsQualifiedName(parent) if parent.left.range() == node.range() => {
+        (parent.range(), parent.right.sym().as_ref())
+      }
+      _ => break,
+    };
+    name.push('.');
+    name.push_str(prop_name);
+    if context.shim_global_names.contains(name.as_str()) {
+      result = Some((parent_range, name.clone()));
+    }
+    node = parent;
+  }
+  result
 }
 
 fn should_ignore_global_this(ident: &Ident, context: &Context) -> bool {
@@ -211,6 +283,14 @@ fn should_ignore_global_this(ident: &Ident, context: &Context) -> bool {
   false
 }
 
+fn is_in_ignore_range(ident: &Ident, context: &Context) -> bool {
+  let range = create_range(ident.start(), ident.end(), context);
+  context
+    .ignore_ranges
+    .iter()
+    .any(|r| r.start <= range.start && range.end <= r.end)
+}
+
 fn should_ignore(node: Node, context: &Context) -> bool {
   has_ignore_comment(node, context) || is_declaration_ident(node)
 }
@@ -262,7 +342,7 @@ fn is_declaration_ident(node: Node) -> bool {
   }
 }
 
-fn get_all_ident_names(program: Program) -> HashSet<String> {
+pub(super) fn get_all_ident_names(program: Program) -> HashSet<String> {
   let mut result = HashSet::new();
   visit_children(program.into(), &mut result);
   return result;
@@ -278,7 +358,10 @@ fn get_all_ident_names(program: Program) -> HashSet<String> {
   }
 }
 
-fn get_unique_name(name: &str, all_idents: &HashSet<String>) -> String {
+pub(super) fn get_unique_name(
+  name: &str,
+  all_idents: &HashSet<String>,
+) -> String {
   let mut count = 0;
   let mut new_name = name.to_string();
   while all_idents.contains(&new_name) {
@@ -0,0 +1,125 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::swc::ast::MetaPropKind;
+use deno_ast::view::*;
+use deno_ast::SourcePos;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+use deno_ast::SourceTextInfoProvider;
+use deno_ast::TextChange;
+
+use crate::ModuleKind;
+
+const SCRIPT_IMPORT_META_URL: &str =
+  "require(\"url\").pathToFileURL(__filename).href";
+const SCRIPT_IMPORT_META_MAIN: &str = "(require.main === module)";
+// 1. `process.argv[1]` is the full path
+// 2. Windows paths (ex. `E:\path\to\main.mjs`) are changed to use forward slashes
+const ESM_IMPORT_META_MAIN: &str = "(import.meta.url === (\"file:///\" + process.argv[1].replace(/\\\\/g, \"/\")).replace(/\\/{3,}/, \"///\"))";
+
+struct Context<'a> {
+  program: Program<'a>,
+  module_kind: ModuleKind,
+  text_changes: Vec<TextChange>,
+}
+
+/// Gets the text changes that replace `import.meta.url`, `import.meta.main`
+/// and `import.meta.resolve(specifier)` with code that works in Node for
+/// the provided module kind.
+pub fn get_import_meta_text_changes(
+  program: Program,
+  module_kind: ModuleKind,
+) -> Vec<TextChange> {
+  let mut context = Context {
+    program,
+    module_kind,
+    text_changes: Vec::new(),
+  };
+  visit_children(program.into(), &mut context);
+  context.text_changes
+}
+
+fn visit_children(node: Node, context: &mut Context) {
+  for child in node.children() {
+    visit_node(child, context);
+  }
+}
+
+fn visit_node(node: Node, context: &mut Context) {
+  match node {
+    // `import.meta.resolve(specifier)` -> `new URL(specifier, <url>).href`
+    Node::CallExpr(call_expr) if call_expr.args.len() == 1 => {
+      if let Callee::Expr(Expr::Member(member_expr)) = call_expr.callee {
+        if get_import_meta_prop_name(member_expr, context) == Some("resolve") {
+          let arg = call_expr.args[0];
+          context.text_changes.push(TextChange {
+            range: create_range(call_expr.start(), arg.start(), context),
+            new_text: "new URL(".to_string(),
+          });
+          context.text_changes.push(TextChange {
+            range: create_range(arg.end(), call_expr.end(), context),
+            new_text: format!(", {}).href", get_import_meta_url(context)),
+          });
+          visit_node(arg.into(), context);
+          return;
+        }
+      }
+    }
+    Node::MemberExpr(member_expr) => {
+      let new_text = match get_import_meta_prop_name(member_expr, context) {
+        Some("url") => match context.module_kind {
+          ModuleKind::Esm => None,
+          ModuleKind::CommonJs => Some(SCRIPT_IMPORT_META_URL),
+        },
+        Some("main") => match context.module_kind {
+          ModuleKind::Esm => Some(ESM_IMPORT_META_MAIN),
+          ModuleKind::CommonJs => Some(SCRIPT_IMPORT_META_MAIN),
+        },
+        _ => None,
+      };
+      if let Some(new_text) = new_text {
+        context.text_changes.push(TextChange {
+          range: create_range(member_expr.start(), member_expr.end(), context),
+          new_text: new_text.to_string(),
+        });
+        return;
+      }
+    }
+    _ => {}
+  }
+
+  visit_children(node, context);
+}
+
+/// Gets the property name when the expression is `import.meta.<name>`.
+fn get_import_meta_prop_name<'a>(
+  member_expr: &MemberExpr,
+  context: &Context<'a>,
+) -> Option<&'a str> {
+  let Expr::MetaProp(meta_prop) = member_expr.obj else {
+    return None;
+  };
+  if meta_prop.prop_kind() != MetaPropKind::ImportMeta {
+    return None;
+  }
+  match member_expr.prop {
+    MemberProp::Ident(ident) => Some(ident.text_fast(context.program)),
+    _ => None,
+  }
+}
+
+fn get_import_meta_url(context: &Context) -> &'static str {
+  match context.module_kind {
+    ModuleKind::Esm => "import.meta.url",
+    ModuleKind::CommonJs => SCRIPT_IMPORT_META_URL,
+  }
+}
+
+fn create_range(
+  start: SourcePos,
+  end: SourcePos,
+  context: &Context,
+) -> std::ops::Range<usize> {
+  SourceRange::new(start, end)
+    .as_byte_range(context.program.text_info().range().start)
+}
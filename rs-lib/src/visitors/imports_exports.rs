@@ -12,10 +12,14 @@ use deno_ast::SourceRanged;
 use deno_ast::SourceRangedForSpanned;
 use deno_ast::SourceTextInfoProvider;
 use deno_ast::TextChange;
+use deno_graph::Module;
 
 use crate::graph::ModuleGraph;
 use crate::mappings::Mappings;
 use crate::utils::get_relative_specifier;
+use crate::Diagnostic;
+use crate::DiagnosticCode;
+use crate::DiagnosticRange;
 
 pub struct GetImportExportsTextChangesParams<'a> {
   pub specifier: &'a ModuleSpecifier,
@@ -25,6 +29,11 @@ pub struct GetImportExportsTextChangesParams<'a> {
   pub package_specifier_mappings: &'a HashMap<ModuleSpecifier, String>,
 }
 
+pub struct GetImportExportsTextChangesResult {
+  pub text_changes: Vec<TextChange>,
+  pub diagnostics: Vec<Diagnostic>,
+}
+
 struct Context<'a> {
   program: Program<'a>,
   specifier: &'a ModuleSpecifier,
@@ -32,12 +41,13 @@ struct Context<'a> {
   mappings: &'a Mappings,
   output_file_path: &'a PathBuf,
   text_changes: Vec<TextChange>,
+  diagnostics: Vec<Diagnostic>,
   package_specifier_mappings: &'a HashMap<ModuleSpecifier, String>,
 }
 
 pub fn get_import_exports_text_changes(
   params: &GetImportExportsTextChangesParams<'_>,
-) -> Result<Vec<TextChange>> {
+) -> Result<GetImportExportsTextChangesResult> {
   let mut context = Context {
     program: params.program,
     specifier: params.specifier,
@@ -45,12 +55,16 @@ pub fn get_import_exports_text_changes(
     mappings: params.mappings,
     output_file_path: params.mappings.get_file_path(params.specifier),
     text_changes: Vec::new(),
+    diagnostics: Vec::new(),
     package_specifier_mappings: params.package_specifier_mappings,
   };
 
   visit_children(params.program.as_node(), &mut context)?;
 
-  Ok(context.text_changes)
+  Ok(GetImportExportsTextChangesResult {
+    text_changes: context.text_changes,
+    diagnostics: context.diagnostics,
+  })
 }
 
 fn visit_children(node: Node, context: &mut Context) -> Result<()> {
@@ -86,23 +100,31 @@ fn visit_children(node: Node, context: &mut Context) -> Result<()> {
       }
       Node::CallExpr(call_expr) => {
         if matches!(call_expr.callee, Callee::Import(_)) {
-          if let Some(Node::Str(src)) =
-            call_expr.args.first().map(|a| a.expr.as_node())
-          {
-            visit_module_specifier(src, context);
-            if call_expr.args.len() > 1 {
-              let assert_arg = call_expr.args[1];
-              let comma_token =
-                assert_arg.previous_token_fast(context.program).unwrap();
-              context.text_changes.push(TextChange {
-                range: create_range(
-                  comma_token.start(),
-                  assert_arg.end(),
-                  context,
-                ),
-                new_text: String::new(),
-              });
+          let Some(arg) = call_expr.args.first() else {
+            continue;
+          };
+          let is_analyzable = match arg.expr.as_node() {
+            Node::Str(src) => {
+              visit_module_specifier(src, context);
+              true
             }
+            Node::Tpl(tpl) => visit_dynamic_import_template(tpl, context),
+            _ => false,
+          };
+          if !is_analyzable {
+            add_dynamic_import_diagnostic(arg.expr.range(), context);
+          } else if call_expr.args.len() > 1 {
+            let assert_arg = call_expr.args[1];
+            let comma_token =
+              assert_arg.previous_token_fast(context.program).unwrap();
+            context.text_changes.push(TextChange {
+              range: create_range(
+                comma_token.start(),
+                assert_arg.end(),
+                context,
+              ),
+              new_text: String::new(),
+            });
           }
         } else {
           visit_children(child, context)?;
@@ -119,29 +141,157 @@ fn visit_children(node: Node, context: &mut Context) -> Result<()> {
 
 fn visit_module_specifier(str: &Str, context: &mut Context) {
   let value = str.value().to_string();
-  let specifier = context
-    .module_graph
-    .resolve_dependency(&value, context.specifier);
-  let specifier = match specifier {
-    Some(s) => s,
+  let new_text = match get_new_specifier_text(&value, context) {
+    Some(new_text) => new_text,
     None => return,
   };
 
-  let new_text = if let Some(bare_specifier) =
-    context.package_specifier_mappings.get(&specifier)
-  {
-    bare_specifier.to_string()
-  } else {
-    let specifier_file_path = context.mappings.get_file_path(&specifier);
-    get_relative_specifier(context.output_file_path, specifier_file_path)
-  };
-
   context.text_changes.push(TextChange {
     range: create_range(str.start() + 1, str.end() - 1, context),
     new_text,
   });
 }
 
+fn get_new_specifier_text(value: &str, context: &Context) -> Option<String> {
+  let specifier = context
+    .module_graph
+    .resolve_dependency(value, context.specifier)?;
+
+  Some(
+    if let Some(bare_specifier) =
+      context.package_specifier_mappings.get(&specifier)
+    {
+      bare_specifier.to_string()
+    } else {
+      let specifier_file_path = context.mappings.get_file_path(&specifier);
+      get_relative_specifier(
+        context.output_file_path,
+        specifier_file_path,
+        context.mappings.preserve_module_format_extensions(),
+      )
+    },
+  )
+}
+
+/// Rewrites the static start and end of a template literal in a dynamic
+/// import (ex. `` import(`./locales/${lang}.ts`) ``) based on the modules
+/// the graph found that it could import. Returns false when it couldn't.
+fn visit_dynamic_import_template(tpl: &Tpl, context: &mut Context) -> bool {
+  let first_quasi = tpl.quasis[0].inner.raw.as_ref();
+  let tpl_text_start = tpl.start() + 1;
+  let tpl_text_end = tpl.end() - 1;
+  if tpl.exprs.is_empty() {
+    // same as a string
+    if let Some(new_text) = get_new_specifier_text(first_quasi, context) {
+      context.text_changes.push(TextChange {
+        range: create_range(tpl_text_start, tpl_text_end, context),
+        new_text,
+      });
+    }
+    return true;
+  }
+
+  let last_quasi = tpl.quasis[tpl.quasis.len() - 1].inner.raw.as_ref();
+  let import_specifiers = get_dynamic_import_specifiers(tpl, context);
+  let mut new_start_and_end = None;
+  for specifier in &import_specifiers {
+    let Some(new_text) = get_new_specifier_text(specifier, context) else {
+      return false;
+    };
+    let Some(middle_text) = specifier
+      .strip_prefix(first_quasi)
+      .and_then(|s| s.strip_suffix(last_quasi))
+    else {
+      return false;
+    };
+    // the text at each position the expressions' text could be in the new text
+    let mut splits = new_text
+      .match_indices(middle_text)
+      .map(|(index, _)| {
+        (
+          new_text[..index].to_string(),
+          new_text[index + middle_text.len()..].to_string(),
+        )
+      })
+      .collect::<Vec<_>>();
+    if let Some(previous) = &new_start_and_end {
+      splits.retain(|split| split == previous);
+    } else {
+      // prefer keeping the start the same
+      splits.sort_by_key(|(start, _)| start != first_quasi);
+    }
+    match splits.into_iter().next() {
+      Some(split) => new_start_and_end = Some(split),
+      None => return false,
+    }
+  }
+
+  let Some((new_start, new_end)) = new_start_and_end else {
+    return false;
+  };
+  if new_start != first_quasi {
+    context.text_changes.push(TextChange {
+      range: create_range(
+        tpl_text_start,
+        tpl_text_start + first_quasi.len(),
+        context,
+      ),
+      new_text: new_start,
+    });
+  }
+  if new_end != last_quasi {
+    context.text_changes.push(TextChange {
+      range: create_range(
+        tpl_text_end - last_quasi.len(),
+        tpl_text_end,
+        context,
+      ),
+      new_text: new_end,
+    });
+  }
+  true
+}
+
+/// Gets the specifiers of the modules the graph found for the dynamic
+/// import whose argument is the provided template literal.
+fn get_dynamic_import_specifiers(tpl: &Tpl, context: &Context) -> Vec<String> {
+  let Module::Js(module) = context.module_graph.get(context.specifier) else {
+    return Vec::new();
+  };
+  let start = context
+    .program
+    .text_info()
+    .line_and_column_index(tpl.start());
+  module
+    .dependencies
+    .iter()
+    .filter(|(_, dep)| {
+      dep.imports.iter().any(|import| {
+        import.is_dynamic
+          && import.range.start.line == start.line_index
+          && import.range.start.character == start.column_index
+      })
+    })
+    .map(|(specifier, _)| specifier.clone())
+    .collect()
+}
+
+fn add_dynamic_import_diagnostic(range: SourceRange, context: &mut Context) {
+  let text_info = context.program.text_info();
+  let display = text_info.line_and_column_display(range.start);
+  context.diagnostics.push(
+    Diagnostic::warning(
+      DiagnosticCode::DynamicImport,
+      Some(context.specifier.clone()),
+      format!(
+        "Could not analyze the dynamic import at {}:{}:{}. The module it imports might not be in the output or its specifier might not be correct.",
+        context.specifier, display.line_number, display.column_number,
+      ),
+    )
+    .with_range(DiagnosticRange::from_source_range(range, text_info)),
+  );
+}
+
 fn visit_import_attributes(asserts: &ObjectLit, context: &mut Context) {
   let with_token = asserts.previous_token_fast(context.program).unwrap();
   debug_assert!(matches!(
@@ -1,11 +1,17 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
 mod deno_comment_directives;
+mod deno_test;
 mod globals;
+mod import_meta;
 mod imports_exports;
 mod polyfill;
+mod using;
 
 pub use deno_comment_directives::*;
+pub use deno_test::*;
 pub use globals::*;
+pub use import_meta::*;
 pub use imports_exports::*;
 pub use polyfill::*;
+pub use using::*;
@@ -4,7 +4,10 @@ use std::collections::HashSet;
 
 use deno_ast::swc::common::SyntaxContext;
 use deno_ast::view::*;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
 
+use crate::polyfills::NodeBuiltIn;
 use crate::polyfills::Polyfill;
 use crate::polyfills::PolyfillVisitContext;
 
@@ -53,3 +56,52 @@ fn visit_children(node: Node, context: &mut Context) {
     }
   }
 }
+
+pub struct FindUnsupportedBuiltInsParams<'a, 'b> {
+  pub program: Program<'b>,
+  pub unresolved_context: SyntaxContext,
+  pub top_level_decls: &'a HashSet<String>,
+  pub built_ins: &'a [&'static NodeBuiltIn],
+}
+
+/// Gets the first use of each of the provided built-ins in the module.
+pub fn find_unsupported_built_ins(
+  params: &FindUnsupportedBuiltInsParams,
+) -> Vec<(&'static NodeBuiltIn, SourceRange)> {
+  let visit_context = PolyfillVisitContext {
+    program: params.program,
+    unresolved_context: params.unresolved_context,
+    top_level_decls: params.top_level_decls,
+  };
+  let mut searching = params.built_ins.to_vec();
+  let mut found = Vec::new();
+  visit_built_ins(
+    params.program.as_node(),
+    &visit_context,
+    &mut searching,
+    &mut found,
+  );
+  found.sort_by_key(|(_, range)| range.start);
+  found
+}
+
+fn visit_built_ins(
+  node: Node,
+  visit_context: &PolyfillVisitContext,
+  searching: &mut Vec<&'static NodeBuiltIn>,
+  found: &mut Vec<(&'static NodeBuiltIn, SourceRange)>,
+) {
+  if searching.is_empty() {
+    return;
+  }
+
+  for child in node.children() {
+    visit_built_ins(child, visit_context, searching, found);
+  }
+
+  for i in (0..searching.len()).rev() {
+    if searching[i].is_used(node, visit_context) {
+      found.push((searching.remove(i), node.range()));
+    }
+  }
+}
@@ -0,0 +1,327 @@
+// Copyright 2018-2024 the Deno authors. MIT license.
+
+use deno_ast::view::*;
+use deno_ast::ModuleSpecifier;
+use deno_ast::SourcePos;
+use deno_ast::SourceRange;
+use deno_ast::SourceRanged;
+use deno_ast::SourceTextInfoProvider;
+use deno_ast::TextChange;
+
+use super::get_all_ident_names;
+use super::get_unique_name;
+use crate::Diagnostic;
+use crate::DiagnosticCode;
+use crate::DiagnosticRange;
+
+pub struct GetUsingTextChangesParams<'a, 'b> {
+  pub specifier: &'a ModuleSpecifier,
+  pub program: Program<'b>,
+}
+
+pub struct GetUsingTextChangesResult {
+  pub text_changes: Vec<TextChange>,
+  pub diagnostics: Vec<Diagnostic>,
+}
+
+struct Context<'a, 'b> {
+  specifier: &'a ModuleSpecifier,
+  program: Program<'b>,
+  stack_name: String,
+  error_name: String,
+  dispose_error_name: String,
+  text_changes: Vec<TextChange>,
+  diagnostics: Vec<Diagnostic>,
+}
+
+/// Gets the text changes that rewrite `using` and `await using`
+/// declarations to a `DisposableStack` or `AsyncDisposableStack` that's
+/// disposed in a try/finally at the end of the block.
+pub fn get_using_text_changes(
+  params: &GetUsingTextChangesParams,
+) -> GetUsingTextChangesResult {
+  let all_ident_names = get_all_ident_names(params.program);
+  let mut context = Context {
+    specifier: params.specifier,
+    program: params.program,
+    stack_name: get_unique_name("dntStack", &all_ident_names),
+    error_name: get_unique_name("dntError", &all_ident_names),
+    dispose_error_name: get_unique_name("dntDisposeError", &all_ident_names),
+    text_changes: Vec::new(),
+    diagnostics: Vec::new(),
+  };
+
+  visit_node(params.program.into(), &mut context);
+
+  GetUsingTextChangesResult {
+    text_changes: context.text_changes,
+    diagnostics: context.diagnostics,
+  }
+}
+
+fn visit_children(node: Node, context: &mut Context) {
+  for child in node.children() {
+    visit_node(child, context);
+  }
+}
+
+fn visit_node(node: Node, context: &mut Context) {
+  match node {
+    Node::BlockStmt(block) => {
+      let stmts = block.stmts.iter().map(|s| s.into()).collect::<Vec<_>>();
+      visit_statements(&stmts, context);
+    }
+    Node::Module(module) => {
+      let items = module.body.iter().map(|i| i.into()).collect::<Vec<_>>();
+      // moving exports into a try block isn't possible
+      let can_downlevel = get_first_using_index(&items).is_none()
+        || module.body.iter().all(|item| {
+          matches!(
+            item,
+            ModuleItem::Stmt(_) | ModuleItem::ModuleDecl(ModuleDecl::Import(_))
+          )
+        });
+      if can_downlevel {
+        visit_statements(&items, context);
+      } else {
+        visit_children(node, context);
+      }
+    }
+    Node::ForOfStmt(for_of) => match for_of.left {
+      ForHead::UsingDecl(decl) => visit_for_of_using(for_of, decl, context),
+      _ => visit_children(node, context),
+    },
+    Node::UsingDecl(decl) => {
+      add_diagnostic(decl, context);
+      visit_children(node, context);
+    }
+    _ => visit_children(node, context),
+  }
+}
+
+/// Wraps the statements in a try/finally that disposes the stack when
+/// there's a `using` declaration among them.
+///
+/// Like TypeScript's downlevel of a block, the statements before the
+/// first `using` declaration are wrapped as well so the declarations
+/// stay in the same scope and keep their types.
+fn visit_statements(stmts: &[Node], context: &mut Context) {
+  let Some(first_index) = get_first_using_index(stmts) else {
+    for stmt in stmts {
+      visit_node(*stmt, context);
+    }
+    return;
+  };
+  // directives and imports need to stay before the try block
+  let start_index = stmts
+    .iter()
+    .position(|stmt| {
+      !is_directive(stmt) && !matches!(stmt, Node::ImportDecl(_))
+    })
+    .unwrap_or(first_index);
+  if !stmts[start_index..].iter().all(can_be_in_block) {
+    // each using declaration will get a diagnostic
+    for stmt in stmts {
+      visit_node(*stmt, context);
+    }
+    return;
+  }
+  let is_async = stmts[first_index..]
+    .iter()
+    .any(|stmt| matches!(stmt, Node::UsingDecl(decl) if decl.inner.is_await));
+
+  for (index, stmt) in stmts.iter().enumerate() {
+    if index == start_index {
+      let start = stmt.start();
+      let prefix = get_try_prefix(is_async, context);
+      add_text_change(start, start, prefix, context);
+    }
+    match stmt {
+      Node::UsingDecl(decl) if index >= start_index => {
+        visit_using_decl(decl, context);
+      }
+      _ => visit_node(*stmt, context),
+    }
+  }
+
+  let end = stmts.last().unwrap().end();
+  let suffix = get_try_suffix(is_async, context);
+  add_text_change(end, end, suffix, context);
+}
+
+fn is_directive(stmt: &Node) -> bool {
+  matches!(stmt, Node::ExprStmt(stmt) if matches!(stmt.expr, Expr::Lit(Lit::Str(_))))
+}
+
+/// Gets if the statement is allowed within a block, which isn't the
+/// case for imports and ambient declarations.
+fn can_be_in_block(stmt: &Node) -> bool {
+  match stmt {
+    Node::ImportDecl(_) | Node::TsModuleDecl(_) => false,
+    Node::VarDecl(decl) => !decl.declare(),
+    Node::ClassDecl(decl) => !decl.declare(),
+    Node::FnDecl(decl) => !decl.declare(),
+    Node::TsEnumDecl(decl) => !decl.declare(),
+    _ => true,
+  }
+}
+
+/// Changes `using x = value` to `const x = stack.use(value)`.
+fn visit_using_decl(decl: &UsingDecl, context: &mut Context) {
+  add_text_change(
+    decl.start(),
+    decl.decls[0].start(),
+    "const ".to_string(),
+    context,
+  );
+  for declarator in decl.decls {
+    visit_node(declarator.name.into(), context);
+    if let Some(init) = declarator.init {
+      add_text_change(
+        init.start(),
+        init.start(),
+        format!("{}.use(", context.stack_name),
+        context,
+      );
+      visit_node(init.into(), context);
+      add_text_change(init.end(), init.end(), ")".to_string(), context);
+    }
+  }
+}
+
+/// Changes `for (using x of values) body` to
+/// `for (const x of values) { <try prefix> stack.use(x); body <try suffix> }`.
+fn visit_for_of_using(
+  for_of: &ForOfStmt,
+  decl: &UsingDecl,
+  context: &mut Context,
+) {
+  let binding = match decl.decls {
+    [declarator] => match declarator.name {
+      Pat::Ident(binding) => binding,
+      _ => {
+        add_diagnostic(decl, context);
+        visit_children(for_of.into(), context);
+        return;
+      }
+    },
+    _ => {
+      add_diagnostic(decl, context);
+      visit_children(for_of.into(), context);
+      return;
+    }
+  };
+
+  add_text_change(decl.start(), binding.start(), "const ".to_string(), context);
+  visit_node(for_of.right.into(), context);
+
+  let is_async = decl.inner.is_await;
+  let prefix = format!(
+    "{}{}.use({});",
+    get_try_prefix(is_async, context),
+    context.stack_name,
+    binding.id.text_fast(context.program),
+  );
+  let suffix = get_try_suffix(is_async, context);
+  let body_range = for_of.body.range();
+  match for_of.body {
+    Stmt::Block(_) => {
+      let open_brace_end = body_range.start + 1;
+      let close_brace_start = body_range.end - 1;
+      add_text_change(
+        open_brace_end,
+        open_brace_end,
+        format!(" {}", prefix),
+        context,
+      );
+      visit_node(for_of.body.into(), context);
+      add_text_change(close_brace_start, close_brace_start, suffix, context);
+    }
+    _ => {
+      add_text_change(
+        body_range.start,
+        body_range.start,
+        format!("{{ {} ", prefix),
+        context,
+      );
+      visit_node(for_of.body.into(), context);
+      add_text_change(
+        body_range.end,
+        body_range.end,
+        format!("{} }}", suffix),
+        context,
+      );
+    }
+  }
+}
+
+fn get_first_using_index(stmts: &[Node]) -> Option<usize> {
+  stmts
+    .iter()
+    .position(|stmt| matches!(stmt, Node::UsingDecl(_)))
+}
+
+fn get_try_prefix(is_async: bool, context: &Context) -> String {
+  format!(
+    "const {} = new {}(); try {{ ",
+    context.stack_name,
+    if is_async {
+      "AsyncDisposableStack"
+    } else {
+      "DisposableStack"
+    },
+  )
+}
+
+/// The stack is disposed in the catch block so an error from disposing
+/// can be thrown with the original error. Disposing it again in the
+/// finally block does nothing.
+fn get_try_suffix(is_async: bool, context: &Context) -> String {
+  let dispose_text = if is_async {
+    format!("await {}.disposeAsync();", context.stack_name)
+  } else {
+    format!("{}.dispose();", context.stack_name)
+  };
+  format!(
+    concat!(
+      " }} catch ({error}) {{ try {{ {dispose} }} catch ({dispose_error}) ",
+      "{{ throw new SuppressedError({dispose_error}, {error}); }} ",
+      "throw {error}; }} finally {{ {dispose} }}",
+    ),
+    error = context.error_name,
+    dispose_error = context.dispose_error_name,
+    dispose = dispose_text,
+  )
+}
+
+fn add_text_change(
+  start: SourcePos,
+  end: SourcePos,
+  new_text: String,
+  context: &mut Context,
+) {
+  context.text_changes.push(TextChange {
+    range: SourceRange::new(start, end)
+      .as_byte_range(context.program.text_info().range().start),
+    new_text,
+  });
+}
+
+fn add_diagnostic(decl: &UsingDecl, context: &mut Context) {
+  let text_info = context.program.text_info();
+  let display = text_info.line_and_column_display(decl.start());
+  context.diagnostics.push(
+    Diagnostic::warning(
+      DiagnosticCode::UsingDeclaration,
+      Some(context.specifier.clone()),
+      format!(
+        concat!(
+          "Could not downlevel the using declaration at {}:{}:{}, so it ",
+          "requires a runtime that supports it.",
+        ),
+        context.specifier, display.line_number, display.column_number,
+      ),
+    )
+    .with_range(DiagnosticRange::from_source_range(decl.range(), text_info)),
+  );
+}
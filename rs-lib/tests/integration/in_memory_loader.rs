@@ -1,5 +1,6 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
+use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
@@ -12,6 +13,8 @@ use deno_graph::source::LoaderChecksum;
 use futures::Future;
 
 use deno_node_transform::url_to_file_path;
+use deno_node_transform::DirEntry;
+use deno_node_transform::DirEntryKind;
 use deno_node_transform::LoadResponse;
 use deno_node_transform::Loader;
 use deno_node_transform::ModuleSpecifier;
@@ -92,7 +95,7 @@ impl Loader for InMemoryLoader {
     &self,
     specifier: ModuleSpecifier,
     _cache_setting: CacheSetting,
-    _maybe_checksum: Option<LoaderChecksum>,
+    maybe_checksum: Option<LoaderChecksum>,
   ) -> Pin<Box<dyn Future<Output = Result<Option<LoadResponse>>> + 'static>> {
     if specifier.scheme() == "file" {
       let file_path = url_to_file_path(&specifier).unwrap();
@@ -117,10 +120,47 @@ impl Loader for InMemoryLoader {
         Err(err) => Err(err),
       });
     let result = match result {
-      Some(Ok(result)) => Ok(Some(result)),
+      Some(Ok(result)) => match maybe_checksum {
+        Some(checksum) => checksum
+          .check_source(&result.content)
+          .map(|_| Some(result))
+          .map_err(|err| err.into()),
+        None => Ok(Some(result)),
+      },
       Some(Err(err)) => Err(anyhow!("{}", err)),
       None => Ok(None),
     };
     Box::pin(futures::future::ready(result))
   }
+
+  fn read_dir(&self, dir_url: &ModuleSpecifier) -> Vec<DirEntry> {
+    let dir_path = url_to_file_path(dir_url).unwrap();
+    let mut files = BTreeSet::new();
+    let mut dirs = BTreeSet::new();
+    for file_path in self.local_files.keys() {
+      let Ok(relative_path) = file_path.strip_prefix(&dir_path) else {
+        continue;
+      };
+      let mut components = relative_path.components();
+      let Some(first) = components.next() else {
+        continue;
+      };
+      if components.next().is_none() {
+        files.insert(file_path.clone());
+      } else {
+        dirs.insert(dir_path.join(first));
+      }
+    }
+    dirs
+      .into_iter()
+      .map(|dir| DirEntry {
+        kind: DirEntryKind::Dir,
+        url: ModuleSpecifier::from_directory_path(dir).unwrap(),
+      })
+      .chain(files.into_iter().map(|file| DirEntry {
+        kind: DirEntryKind::File,
+        url: ModuleSpecifier::from_file_path(file).unwrap(),
+      }))
+      .collect()
+  }
 }
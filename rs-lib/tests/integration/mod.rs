@@ -27,6 +27,7 @@ macro_rules! assert_files {
       .map(|(file_path, file_text)| deno_node_transform::OutputFile {
         file_path: std::path::PathBuf::from(file_path),
         file_text: file_text.to_string(),
+        source_map: None,
       })
       .collect::<Vec<_>>();
     expected.sort_by(|a, b| a.file_path.cmp(&b.file_path));
@@ -6,12 +6,18 @@ use std::rc::Rc;
 use anyhow::Result;
 use deno_node_transform::transform;
 use deno_node_transform::GlobalName;
+use deno_node_transform::JsrSpecifierMode;
+use deno_node_transform::LockfileOptions;
 use deno_node_transform::MappedSpecifier;
+use deno_node_transform::ModuleKind;
 use deno_node_transform::ModuleSpecifier;
+use deno_node_transform::OutsideRootDirMode;
 use deno_node_transform::PackageMappedSpecifier;
 use deno_node_transform::PackageShim;
+use deno_node_transform::RemoteLayout;
 use deno_node_transform::ScriptTarget;
 use deno_node_transform::Shim;
+use deno_node_transform::TransformCache;
 use deno_node_transform::TransformOptions;
 use deno_node_transform::TransformOutput;
 
@@ -19,14 +25,30 @@ use super::InMemoryLoader;
 
 pub struct TestBuilder {
   loader: InMemoryLoader,
-  entry_point: String,
+  entry_point: Option<String>,
   additional_entry_points: Vec<String>,
   test_entry_points: Vec<String>,
   specifier_mappings: HashMap<ModuleSpecifier, MappedSpecifier>,
   shims: Vec<Shim>,
   test_shims: Vec<Shim>,
   target: ScriptTarget,
+  node_version_range: Option<String>,
+  module_kind: Option<ModuleKind>,
+  node_test: bool,
   import_map: Option<ModuleSpecifier>,
+  config_file: Option<ModuleSpecifier>,
+  error_on_import_map_diagnostics: bool,
+  jsr_specifier_mode: JsrSpecifierMode,
+  jsr_url: Option<ModuleSpecifier>,
+  root_dir: Option<ModuleSpecifier>,
+  outside_root_dir_mode: OutsideRootDirMode,
+  preserve_module_format_extensions: bool,
+  dedupe_remote_modules: bool,
+  remote_layout: RemoteLayout,
+  remote_path_max_length: Option<usize>,
+  source_maps: bool,
+  transform_cache: Option<Rc<dyn TransformCache>>,
+  lockfile: Option<LockfileOptions>,
 }
 
 impl TestBuilder {
@@ -34,14 +56,30 @@ impl TestBuilder {
     let loader = InMemoryLoader::new();
     Self {
       loader,
-      entry_point: "file:///mod.ts".to_string(),
+      entry_point: Some("file:///mod.ts".to_string()),
       additional_entry_points: Vec::new(),
       test_entry_points: Vec::new(),
       specifier_mappings: Default::default(),
       shims: Default::default(),
       test_shims: Default::default(),
       target: ScriptTarget::ES5,
+      node_version_range: None,
+      module_kind: None,
+      node_test: false,
       import_map: None,
+      config_file: None,
+      error_on_import_map_diagnostics: false,
+      jsr_specifier_mode: JsrSpecifierMode::Npm,
+      jsr_url: None,
+      root_dir: None,
+      outside_root_dir_mode: OutsideRootDirMode::Error,
+      preserve_module_format_extensions: false,
+      dedupe_remote_modules: false,
+      remote_layout: RemoteLayout::Mirror,
+      remote_path_max_length: None,
+      source_maps: false,
+      transform_cache: None,
+      lockfile: None,
     }
   }
 
@@ -54,7 +92,12 @@ impl TestBuilder {
   }
 
   pub fn entry_point(&mut self, value: impl AsRef<str>) -> &mut Self {
-    self.entry_point = value.as_ref().to_string();
+    self.entry_point = Some(value.as_ref().to_string());
+    self
+  }
+
+  pub fn no_entry_point(&mut self) -> &mut Self {
+    self.entry_point = None;
     self
   }
 
@@ -75,6 +118,94 @@ impl TestBuilder {
     self
   }
 
+  pub fn set_config_file(&mut self, url: impl AsRef<str>) -> &mut Self {
+    self.config_file = Some(ModuleSpecifier::parse(url.as_ref()).unwrap());
+    self
+  }
+
+  pub fn set_error_on_import_map_diagnostics(
+    &mut self,
+    value: bool,
+  ) -> &mut Self {
+    self.error_on_import_map_diagnostics = value;
+    self
+  }
+
+  pub fn set_jsr_specifier_mode(
+    &mut self,
+    mode: JsrSpecifierMode,
+  ) -> &mut Self {
+    self.jsr_specifier_mode = mode;
+    self
+  }
+
+  pub fn set_jsr_url(&mut self, url: impl AsRef<str>) -> &mut Self {
+    self.jsr_url = Some(ModuleSpecifier::parse(url.as_ref()).unwrap());
+    self
+  }
+
+  pub fn set_root_dir(&mut self, url: impl AsRef<str>) -> &mut Self {
+    self.root_dir = Some(ModuleSpecifier::parse(url.as_ref()).unwrap());
+    self
+  }
+
+  pub fn set_outside_root_dir_mode(
+    &mut self,
+    mode: OutsideRootDirMode,
+  ) -> &mut Self {
+    self.outside_root_dir_mode = mode;
+    self
+  }
+
+  pub fn set_preserve_module_format_extensions(
+    &mut self,
+    value: bool,
+  ) -> &mut Self {
+    self.preserve_module_format_extensions = value;
+    self
+  }
+
+  pub fn set_dedupe_remote_modules(&mut self, value: bool) -> &mut Self {
+    self.dedupe_remote_modules = value;
+    self
+  }
+
+  pub fn set_remote_layout(&mut self, layout: RemoteLayout) -> &mut Self {
+    self.remote_layout = layout;
+    self
+  }
+
+  pub fn set_remote_path_max_length(&mut self, value: usize) -> &mut Self {
+    self.remote_path_max_length = Some(value);
+    self
+  }
+
+  pub fn set_source_maps(&mut self, value: bool) -> &mut Self {
+    self.source_maps = value;
+    self
+  }
+
+  pub fn set_transform_cache(
+    &mut self,
+    cache: Rc<dyn TransformCache>,
+  ) -> &mut Self {
+    self.transform_cache = Some(cache);
+    self
+  }
+
+  pub fn set_lockfile(
+    &mut self,
+    specifier: impl AsRef<str>,
+    write: bool,
+  ) -> &mut Self {
+    self.lockfile = Some(LockfileOptions {
+      specifier: ModuleSpecifier::parse(specifier.as_ref()).unwrap(),
+      text: None,
+      write,
+    });
+    self
+  }
+
   pub fn add_default_shims(&mut self) -> &mut Self {
     let deno_shim = Shim::Package(PackageShim {
       package: PackageMappedSpecifier {
@@ -164,9 +295,27 @@ impl TestBuilder {
     self
   }
 
+  pub fn set_node_version_range(&mut self, value: &str) -> &mut Self {
+    self.node_version_range = Some(value.to_string());
+    self
+  }
+
+  pub fn set_module_kind(&mut self, module_kind: ModuleKind) -> &mut Self {
+    self.module_kind = Some(module_kind);
+    self
+  }
+
+  pub fn set_node_test(&mut self, value: bool) -> &mut Self {
+    self.node_test = value;
+    self
+  }
+
   pub async fn transform(&self) -> Result<TransformOutput> {
-    let mut entry_points =
-      vec![ModuleSpecifier::parse(&self.entry_point).unwrap()];
+    let mut entry_points = self
+      .entry_point
+      .iter()
+      .map(|p| ModuleSpecifier::parse(p).unwrap())
+      .collect::<Vec<_>>();
     entry_points.extend(
       self
         .additional_entry_points
@@ -185,7 +334,23 @@ impl TestBuilder {
       loader: Some(Rc::new(self.loader.clone())),
       specifier_mappings: self.specifier_mappings.clone(),
       target: self.target,
+      node_version_range: self.node_version_range.clone(),
+      module_kind: self.module_kind,
+      node_test: self.node_test,
       import_map: self.import_map.clone(),
+      config_file: self.config_file.clone(),
+      error_on_import_map_diagnostics: self.error_on_import_map_diagnostics,
+      jsr_specifier_mode: self.jsr_specifier_mode,
+      jsr_url: self.jsr_url.clone(),
+      root_dir: self.root_dir.clone(),
+      outside_root_dir_mode: self.outside_root_dir_mode,
+      preserve_module_format_extensions: self.preserve_module_format_extensions,
+      dedupe_remote_modules: self.dedupe_remote_modules,
+      remote_layout: self.remote_layout,
+      remote_path_max_length: self.remote_path_max_length,
+      source_maps: self.source_maps,
+      transform_cache: self.transform_cache.clone(),
+      lockfile: self.lockfile.clone(),
     })
     .await
   }
@@ -1,14 +1,35 @@
 // Copyright 2018-2024 the Deno authors. MIT license.
 
+use std::cell::Cell;
 use std::path::PathBuf;
+use std::rc::Rc;
 
 use deno_node_transform::Dependency;
+use deno_node_transform::Diagnostic;
+use deno_node_transform::DiagnosticCode;
+use deno_node_transform::DiagnosticPosition;
+use deno_node_transform::DiagnosticRange;
+use deno_node_transform::DiagnosticSeverity;
+use deno_node_transform::FileShimUsage;
 use deno_node_transform::GlobalName;
+use deno_node_transform::InMemoryTransformCache;
+use deno_node_transform::JsrSpecifierMode;
+use deno_node_transform::LoaderChecksum;
+use deno_node_transform::ModuleFailure;
+use deno_node_transform::ModuleFailureKind;
+use deno_node_transform::ModuleFailureReferrer;
+use deno_node_transform::ModuleGraphError;
+use deno_node_transform::ModuleKind;
 use deno_node_transform::ModuleShim;
+use deno_node_transform::ModuleSpecifier;
+use deno_node_transform::OutsideRootDirMode;
 use deno_node_transform::PackageMappedSpecifier;
 use deno_node_transform::PackageShim;
+use deno_node_transform::RemoteLayout;
 use deno_node_transform::ScriptTarget;
 use deno_node_transform::Shim;
+use deno_node_transform::ShimUsage;
+use deno_node_transform::TransformCache;
 use pretty_assertions::assert_eq;
 
 #[macro_use]
@@ -244,14 +265,11 @@ async fn transform_shim_custom_shims() {
             "import { default as DOMException } from \"domexception\";\n",
             "export { default as DOMException } from \"domexception\";\n",
             "import { Blob } from \"buffer\";\n",
-            "export { Blob, type Other } from \"buffer\";\n",
-            "export { type TypeOnly } from \"type-only\";\n",
+            "export { Blob } from \"buffer\";\n",
             "import { BareModule } from \"bare-module\";\n",
             "export { BareModule } from \"bare-module\";\n",
             "import { LocalShim } from \"./local_shim.js\";\n",
             "export { LocalShim } from \"./local_shim.js\";\n",
-            "import { RemoteShim } from \"./deps/localhost/remote_shim.js\";\n",
-            "export { RemoteShim } from \"./deps/localhost/remote_shim.js\";\n",
             "\n",
             "const dntGlobals = {\n",
             "  fetch,\n",
@@ -260,7 +278,6 @@ async fn transform_shim_custom_shims() {
             "  Blob,\n",
             "  BareModule,\n",
             "  LocalShim,\n",
-            "  RemoteShim,\n",
             "};\n",
             "export const dntGlobalThis = createMergeProxy(globalThis, dntGlobals);\n",
           ).to_string(),
@@ -315,6 +332,248 @@ async fn transform_shim_custom_shims() {
   );
 }
 
+#[tokio::test]
+async fn transform_shim_members() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "const text = await Deno.readTextFile(\"a\");\n",
+          "Deno.env.get(\"x\");\n",
+          "let file: Deno.FsFile;\n",
+          "Deno.exit();",
+        ),
+      );
+    })
+    .add_shim(Shim::Package(PackageShim {
+      package: PackageMappedSpecifier {
+        name: "@deno/shim-deno-fs".to_string(),
+        version: Some("^0.1.0".to_string()),
+        sub_path: None,
+        peer_dependency: false,
+      },
+      types_package: None,
+      global_names: vec![
+        GlobalName {
+          name: "Deno.readTextFile".to_string(),
+          export_name: None,
+          type_only: false,
+        },
+        GlobalName {
+          name: "Deno.FsFile".to_string(),
+          export_name: None,
+          type_only: true,
+        },
+        GlobalName {
+          name: "Deno.writeTextFile".to_string(),
+          export_name: None,
+          type_only: false,
+        },
+      ],
+    }))
+    .add_shim(Shim::Module(ModuleShim {
+      module: "node-env-shim".to_string(),
+      global_names: vec![GlobalName {
+        name: "Deno.env".to_string(),
+        export_name: Some("environment".to_string()),
+        type_only: false,
+      }],
+    }))
+    .add_shim(Shim::Package(PackageShim {
+      package: PackageMappedSpecifier {
+        name: "@deno/shim-deno-command".to_string(),
+        version: Some("^0.1.0".to_string()),
+        sub_path: None,
+        peer_dependency: false,
+      },
+      types_package: None,
+      global_names: vec![GlobalName {
+        name: "Deno.Command".to_string(),
+        export_name: None,
+        type_only: false,
+      }],
+    }))
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "_dnt.shims.ts",
+        get_shim_file_text(
+          concat!(
+            "import { readTextFile as Deno_readTextFile } from \"@deno/shim-deno-fs\";\n",
+            "export { readTextFile as Deno_readTextFile, type FsFile as Deno_FsFile } from \"@deno/shim-deno-fs\";\n",
+            "import { environment as Deno_env } from \"node-env-shim\";\n",
+            "export { environment as Deno_env } from \"node-env-shim\";\n",
+            "\n",
+            "const dntGlobals = {\n",
+            "};\n",
+            "export const dntGlobalThis = createMergeProxy(globalThis, dntGlobals);\n",
+          )
+          .to_string(),
+        ),
+      ),
+      (
+        "mod.ts",
+        concat!(
+          "import * as dntShim from \"./_dnt.shims.js\";\n",
+          "const text = await dntShim.Deno_readTextFile(\"a\");\n",
+          "dntShim.Deno_env.get(\"x\");\n",
+          "let file: dntShim.Deno_FsFile;\n",
+          "Deno.exit();",
+        )
+        .to_string(),
+      ),
+    ]
+  );
+  assert_eq!(
+    result.main.dependencies,
+    vec![Dependency {
+      name: "@deno/shim-deno-fs".to_string(),
+      version: "^0.1.0".to_string(),
+      peer_dependency: false,
+    }]
+  );
+}
+
+#[tokio::test]
+async fn transform_shim_members_with_global() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        "Deno.readTextFile(\"a\"); Deno.exit(); globalThis.Deno;",
+      );
+    })
+    .add_shim(Shim::Module(ModuleShim {
+      module: "fs-shim".to_string(),
+      global_names: vec![GlobalName {
+        name: "Deno.readTextFile".to_string(),
+        export_name: Some("readFile".to_string()),
+        type_only: false,
+      }],
+    }))
+    .add_shim(Shim::Module(ModuleShim {
+      module: "deno-shim".to_string(),
+      global_names: vec![GlobalName {
+        name: "Deno".to_string(),
+        export_name: None,
+        type_only: false,
+      }],
+    }))
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "_dnt.shims.ts",
+        get_shim_file_text(
+          concat!(
+            "import { readFile as Deno_readTextFile } from \"fs-shim\";\n",
+            "export { readFile as Deno_readTextFile } from \"fs-shim\";\n",
+            "import { Deno } from \"deno-shim\";\n",
+            "export { Deno } from \"deno-shim\";\n",
+            "\n",
+            "const dntGlobals = {\n",
+            "  Deno,\n",
+            "};\n",
+            "export const dntGlobalThis = createMergeProxy(globalThis, dntGlobals);\n",
+          )
+          .to_string(),
+        ),
+      ),
+      (
+        "mod.ts",
+        concat!(
+          "import * as dntShim from \"./_dnt.shims.js\";\n",
+          "dntShim.Deno_readTextFile(\"a\"); dntShim.Deno.exit(); dntShim.dntGlobalThis.Deno;",
+        )
+        .to_string(),
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_shim_usage() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import \"./other.ts\";\n",
+            "Deno.cwd();\n",
+            "setTimeout(() => {}, Deno.pid);\n",
+            "globalThis.setTimeout;\n",
+          ),
+        )
+        .add_local_file("/other.ts", "export const a = 1;")
+        .add_local_file(
+          "/mod.test.ts",
+          "import \"./mod.ts\";\nsetInterval(() => {});",
+        );
+    })
+    .add_default_shims()
+    .add_test_entry_point("file:///mod.test.ts")
+    .transform()
+    .await
+    .unwrap();
+
+  fn range(start: (usize, usize), end: (usize, usize)) -> DiagnosticRange {
+    DiagnosticRange {
+      start: DiagnosticPosition {
+        line: start.0,
+        character: start.1,
+      },
+      end: DiagnosticPosition {
+        line: end.0,
+        character: end.1,
+      },
+    }
+  }
+
+  assert_eq!(
+    result.main.shim_usage,
+    vec![FileShimUsage {
+      file_path: PathBuf::from("mod.ts"),
+      specifier: ModuleSpecifier::parse("file:///mod.ts").unwrap(),
+      shims: vec![
+        ShimUsage {
+          name: "Deno".to_string(),
+          ranges: vec![range((1, 0), (1, 4)), range((2, 21), (2, 25))],
+        },
+        ShimUsage {
+          name: "globalThis".to_string(),
+          ranges: vec![range((3, 0), (3, 10))],
+        },
+        ShimUsage {
+          name: "setTimeout".to_string(),
+          ranges: vec![range((2, 0), (2, 10))],
+        },
+      ],
+    }]
+  );
+  assert_eq!(
+    result.test.shim_usage,
+    vec![FileShimUsage {
+      file_path: PathBuf::from("mod.test.ts"),
+      specifier: ModuleSpecifier::parse("file:///mod.test.ts").unwrap(),
+      shims: vec![ShimUsage {
+        name: "setInterval".to_string(),
+        ranges: vec![range((1, 0), (1, 11))],
+      }],
+    }]
+  );
+}
+
 #[tokio::test]
 async fn transform_shim_node_custom_shims() {
   let result = TestBuilder::new()
@@ -379,7 +638,25 @@ async fn transform_legacy_deno_shim_ignore_warnings() {
     .await
     .unwrap();
 
-  assert_eq!(result.warnings, vec!["deno-shim-ignore has been renamed to dnt-shim-ignore. Please rename it in file:///mod.ts"]);
+  assert_eq!(
+    result.diagnostics,
+    vec![Diagnostic {
+      code: DiagnosticCode::DenoShimIgnoreRenamed,
+      severity: DiagnosticSeverity::Warning,
+      specifier: Some(ModuleSpecifier::parse("file:///mod.ts").unwrap()),
+      range: Some(DiagnosticRange {
+        start: DiagnosticPosition {
+          line: 0,
+          character: 0,
+        },
+        end: DiagnosticPosition {
+          line: 0,
+          character: 19,
+        },
+      }),
+      message: "deno-shim-ignore has been renamed to dnt-shim-ignore. Please rename it in file:///mod.ts".to_string(),
+    }]
+  );
   assert_files!(
     result.main.files,
     &[("mod.ts", "// deno-shim-ignore\nDeno.readTextFile();")]
@@ -671,30 +948,36 @@ async fn transform_remote_files() {
 }
 
 #[tokio::test]
-async fn transform_remote_declaration_files() {
+async fn transform_dedupe_remote_modules() {
   let result = TestBuilder::new()
     .with_loader(|loader| {
       loader
         .add_local_file(
           "/mod.ts",
-          "import * as other from 'http://localhost/mod.js';",
+          concat!(
+            "import 'https://cdn-a.com/lib/mod.ts';\n",
+            "import 'https://cdn-b.com/lib/mod.ts';\n",
+            "import 'https://cdn-c.com/lib/mod.ts';\n",
+          ),
         )
-        .add_remote_file_with_headers(
-          "http://localhost/mod.js",
-          "export {}",
-          &[("x-typescript-types", "./declarations.d.ts")],
+        .add_remote_file(
+          "https://cdn-a.com/lib/mod.ts",
+          "import './dep.ts';\nexport const a = 1;",
         )
         .add_remote_file(
-          "http://localhost/declarations.d.ts",
-          "import type * as myOther from './other.d.ts';",
+          "https://cdn-b.com/lib/mod.ts",
+          "import './dep.ts';\nexport const a = 1;",
         )
-        .add_remote_file_with_headers(
-          "http://localhost/other.d.ts",
-          "export class Test {}",
-          // references itself
-          &[("x-typescript-types", "./other.d.ts")],
-        );
+        .add_remote_file(
+          "https://cdn-c.com/lib/mod.ts",
+          "import './dep.ts';\nexport const a = 1;",
+        )
+        .add_remote_file("https://cdn-a.com/lib/dep.ts", "export const b = 1;")
+        .add_remote_file("https://cdn-b.com/lib/dep.ts", "export const b = 1;")
+        // different, so the module importing it isn't a duplicate either
+        .add_remote_file("https://cdn-c.com/lib/dep.ts", "export const b = 2;");
     })
+    .set_dedupe_remote_modules(true)
     .transform()
     .await
     .unwrap();
@@ -704,30 +987,53 @@ async fn transform_remote_declaration_files() {
     &[
       (
         "mod.ts",
-        "import * as other from './deps/localhost/mod.js';",
+        concat!(
+          "import './deps/cdn-a.com/lib/mod.js';\n",
+          "import './deps/cdn-a.com/lib/mod.js';\n",
+          "import './deps/cdn-c.com/lib/mod.js';\n",
+        )
       ),
-      ("deps/localhost/mod.js", "export {}"),
       (
-        "deps/localhost/mod.d.ts",
-        "import type * as myOther from './other';"
+        "deps/cdn-a.com/lib/mod.ts",
+        "import './dep.js';\nexport const a = 1;"
       ),
-      ("deps/localhost/other.d.ts", "export class Test {}"),
+      ("deps/cdn-a.com/lib/dep.ts", "export const b = 1;"),
+      (
+        "deps/cdn-c.com/lib/mod.ts",
+        "import './dep.js';\nexport const a = 1;"
+      ),
+      ("deps/cdn-c.com/lib/dep.ts", "export const b = 2;"),
     ]
   );
 }
 
 #[tokio::test]
-async fn transform_handle_local_deps_folder() {
+async fn transform_remote_layout_flat() {
   let result = TestBuilder::new()
     .with_loader(|loader| {
       loader
         .add_local_file(
           "/mod.ts",
-          "import 'http://localhost/mod.ts';\nimport './deps/localhost/mod.ts'",
+          concat!(
+            "import 'https://deno.land/x/oak@v12.6.0/mod.ts';\n",
+            "import 'https://cdn.example.com/@std/path@1.0.0/mod.ts';\n",
+          ),
         )
-        .add_local_file("/deps/localhost/mod.ts", "local;")
-        .add_remote_file("http://localhost/mod.ts", "remote;");
+        .add_remote_file(
+          "https://deno.land/x/oak@v12.6.0/mod.ts",
+          "import './lib/router.ts';",
+        )
+        .add_remote_file(
+          "https://deno.land/x/oak@v12.6.0/lib/router.ts",
+          "export class Router {}",
+        )
+        .add_remote_file(
+          "https://cdn.example.com/@std/path@1.0.0/mod.ts",
+          "export const sep = '/';",
+        );
     })
+    .set_remote_layout(RemoteLayout::Flat)
+    .set_remote_path_max_length(60)
     .transform()
     .await
     .unwrap();
@@ -737,52 +1043,404 @@ async fn transform_handle_local_deps_folder() {
     &[
       (
         "mod.ts",
-        "import './deps_2/localhost/mod.js';\nimport './deps/localhost/mod.js'"
+        concat!(
+          "import './deps/oak@v12.6.0_fc131005/mod.js';\n",
+          "import './deps/std__path@1.0.0_ad166ff6/mod.js';\n",
+        )
+      ),
+      (
+        "deps/oak@v12.6.0_fc131005/mod.ts",
+        "import './lib/router.js';"
+      ),
+      (
+        "deps/oak@v12.6.0_fc131005/lib/router.ts",
+        "export class Router {}"
+      ),
+      (
+        "deps/std__path@1.0.0_ad166ff6/mod.ts",
+        "export const sep = '/';"
       ),
-      ("deps/localhost/mod.ts", "local;"),
-      ("deps_2/localhost/mod.ts", "remote;"),
     ]
   );
 }
 
 #[tokio::test]
-async fn transform_local_file_not_exists() {
-  let err_message = TestBuilder::new()
-    .with_loader(|loader| {
-      loader.add_local_file("/mod.ts", "import * as other from './other.ts';");
-    })
+async fn transform_root_dir() {
+  fn builder() -> TestBuilder {
+    let mut builder = TestBuilder::new();
+    builder
+      .with_loader(|loader| {
+        loader
+          .add_local_file("/project/src/mod.ts", "export const a = 1;")
+          .add_local_file(
+            "/project/src/mod.test.ts",
+            "import './mod.ts';\nimport '../fixtures/data.ts';",
+          )
+          .add_local_file("/project/fixtures/data.ts", "export const b = 1;");
+      })
+      .entry_point("file:///project/src/mod.ts")
+      .add_test_entry_point("file:///project/src/mod.test.ts");
+    builder
+  }
+
+  // without a root directory, the output is relative to the project
+  let result = builder().transform().await.unwrap();
+  assert_eq!(result.main.entry_points, &[PathBuf::from("src/mod.ts")]);
+
+  let err_message = builder()
+    .set_root_dir("file:///project/src/")
     .transform()
     .await
     .err()
     .unwrap();
-
   assert_eq!(
     err_message.to_string(),
-    "Module not found \"file:///other.ts\".\n    at file:///mod.ts:1:24"
+    concat!(
+      "The following local modules are outside the root directory /project/src/:\n",
+      "  * file:///project/fixtures/data.ts",
+    )
   );
-}
 
-#[tokio::test]
-async fn transform_remote_file_not_exists() {
-  let err_message = TestBuilder::new()
-    .with_loader(|loader| {
-      loader.add_remote_file(
-        "http://localhost/mod.ts",
-        "import * as other from './other.ts';",
-      );
-    })
-    .entry_point("http://localhost/mod.ts")
+  let result = builder()
+    .set_root_dir("file:///project/src")
+    .set_outside_root_dir_mode(OutsideRootDirMode::External)
     .transform()
     .await
-    .err()
     .unwrap();
-
-  assert_eq!(
-    err_message.to_string(),
-    "Module not found \"http://localhost/other.ts\".\n    at http://localhost/mod.ts:1:24"
-  );
-}
-
+  assert_files!(result.main.files, &[("mod.ts", "export const a = 1;")]);
+  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
+  assert_files!(
+    result.test.files,
+    &[
+      (
+        "mod.test.ts",
+        "import './mod.js';\nimport './_external/fixtures/data.js';"
+      ),
+      ("_external/fixtures/data.ts", "export const b = 1;"),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_remote_declaration_files() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          "import * as other from 'http://localhost/mod.js';",
+        )
+        .add_remote_file_with_headers(
+          "http://localhost/mod.js",
+          "export {}",
+          &[("x-typescript-types", "./declarations.d.ts")],
+        )
+        .add_remote_file(
+          "http://localhost/declarations.d.ts",
+          "import type * as myOther from './other.d.ts';",
+        )
+        .add_remote_file_with_headers(
+          "http://localhost/other.d.ts",
+          "export class Test {}",
+          // references itself
+          &[("x-typescript-types", "./other.d.ts")],
+        );
+    })
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        "import * as other from './deps/localhost/mod.js';",
+      ),
+      ("deps/localhost/mod.js", "export {}"),
+      (
+        "deps/localhost/mod.d.ts",
+        "import type * as myOther from './other';"
+      ),
+      ("deps/localhost/other.d.ts", "export class Test {}"),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_preserve_module_format_extensions() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import './esm.mts';\n",
+            "import './cjs.cts';\n",
+            "import type { A } from './types.d.mts';\n",
+            "import 'http://localhost/lib.mjs';",
+          ),
+        )
+        .add_local_file("/esm.mts", "export const a = 1;")
+        .add_local_file("/cjs.cts", "export const b = 1;")
+        .add_local_file("/types.d.mts", "export interface A {}")
+        .add_remote_file_with_headers(
+          "http://localhost/lib.mjs",
+          "export {}",
+          &[("x-typescript-types", "./lib.d.mts")],
+        )
+        .add_remote_file("http://localhost/lib.d.mts", "export {};");
+    })
+    .set_preserve_module_format_extensions(true)
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import './esm.mjs';\n",
+          "import './cjs.cjs';\n",
+          "import type { A } from './types.mjs';\n",
+          "import './deps/localhost/lib.mjs';",
+        )
+      ),
+      ("esm.mts", "export const a = 1;"),
+      ("cjs.cts", "export const b = 1;"),
+      ("types.d.mts", "export interface A {}"),
+      ("deps/localhost/lib.mjs", "export {}"),
+      ("deps/localhost/lib.d.mts", "export {};"),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_module_format_extensions_not_preserved() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import './esm.mts';\n",
+            "import './cjs.cts';\n",
+            "import type { A } from './types.d.mts';\n",
+            "import 'http://localhost/lib.mjs';",
+          ),
+        )
+        .add_local_file("/esm.mts", "export const a = 1;")
+        .add_local_file("/cjs.cts", "export const b = 1;")
+        .add_local_file("/types.d.mts", "export interface A {}")
+        .add_remote_file_with_headers(
+          "http://localhost/lib.mjs",
+          "export {}",
+          &[("x-typescript-types", "./lib.d.mts")],
+        )
+        .add_remote_file("http://localhost/lib.d.mts", "export {};");
+    })
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import './esm.js';\n",
+          "import './cjs.js';\n",
+          "import type { A } from './types.js';\n",
+          "import './deps/localhost/lib.js';",
+        )
+      ),
+      ("esm.js", "export const a = 1;"),
+      ("cjs.cts", "export const b = 1;"),
+      ("types.d.mts", "export interface A {}"),
+      ("deps/localhost/lib.js", "export {}"),
+      ("deps/localhost/lib.d.ts", "export {};"),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_handle_local_deps_folder() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          "import 'http://localhost/mod.ts';\nimport './deps/localhost/mod.ts'",
+        )
+        .add_local_file("/deps/localhost/mod.ts", "local;")
+        .add_remote_file("http://localhost/mod.ts", "remote;");
+    })
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        "import './deps_2/localhost/mod.js';\nimport './deps/localhost/mod.js'"
+      ),
+      ("deps/localhost/mod.ts", "local;"),
+      ("deps_2/localhost/mod.ts", "remote;"),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_local_file_not_exists() {
+  let err_message = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", "import * as other from './other.ts';");
+    })
+    .transform()
+    .await
+    .err()
+    .unwrap();
+
+  assert_eq!(
+    err_message.to_string(),
+    "Module not found \"file:///other.ts\".\n    at file:///mod.ts:1:24"
+  );
+}
+
+#[tokio::test]
+async fn transform_remote_file_not_exists() {
+  let err_message = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_remote_file(
+        "http://localhost/mod.ts",
+        "import * as other from './other.ts';",
+      );
+    })
+    .entry_point("http://localhost/mod.ts")
+    .transform()
+    .await
+    .err()
+    .unwrap();
+
+  assert_eq!(
+    err_message.to_string(),
+    "Module not found \"http://localhost/other.ts\".\n    at http://localhost/mod.ts:1:24"
+  );
+}
+
+#[tokio::test]
+async fn transform_module_graph_error() {
+  let err = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          "import './other.ts';\nimport * as parse from './parse.ts';",
+        )
+        .add_local_file("/parse.ts", "test test test");
+    })
+    .transform()
+    .await
+    .err()
+    .unwrap();
+
+  let error = err.downcast_ref::<ModuleGraphError>().unwrap();
+  assert_eq!(
+    error.failures,
+    vec![
+      ModuleFailure {
+        kind: ModuleFailureKind::NotFound,
+        specifier: ModuleSpecifier::parse("file:///other.ts").unwrap(),
+        referrer: Some(ModuleFailureReferrer {
+          specifier: ModuleSpecifier::parse("file:///mod.ts").unwrap(),
+          range: DiagnosticRange {
+            start: DiagnosticPosition {
+              line: 0,
+              character: 7,
+            },
+            end: DiagnosticPosition {
+              line: 0,
+              character: 19,
+            },
+          },
+        }),
+        code_frame: Some(
+          "  import './other.ts';\n         ~~~~~~~~~~~~".to_string()
+        ),
+        message: "Module not found \"file:///other.ts\".\n    at file:///mod.ts:1:8".to_string(),
+      },
+      ModuleFailure {
+        kind: ModuleFailureKind::Parse,
+        specifier: ModuleSpecifier::parse("file:///parse.ts").unwrap(),
+        referrer: None,
+        code_frame: Some("  test test test\n       ~~~~".to_string()),
+        message: concat!(
+          "The module's source code could not be parsed: Expected ';', '}' or <eof> at file:///parse.ts:1:6\n",
+          "\n",
+          "  test test test\n",
+          "       ~~~~",
+        ).to_string(),
+      },
+    ]
+  );
+  assert_eq!(
+    err.to_string(),
+    format!(
+      "{}\n\n{}",
+      error.failures[0].message, error.failures[1].message
+    )
+  );
+}
+
+#[tokio::test]
+async fn transform_module_graph_resolution_error() {
+  let err = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "import './other.ts';")
+        .add_local_file("/other.ts", "export * from 'chalk';");
+    })
+    .transform()
+    .await
+    .err()
+    .unwrap();
+
+  let error = err.downcast_ref::<ModuleGraphError>().unwrap();
+  assert_eq!(
+    error.failures,
+    vec![ModuleFailure {
+      kind: ModuleFailureKind::Resolution,
+      specifier: ModuleSpecifier::parse("file:///other.ts").unwrap(),
+      referrer: Some(ModuleFailureReferrer {
+        specifier: ModuleSpecifier::parse("file:///other.ts").unwrap(),
+        range: DiagnosticRange {
+          start: DiagnosticPosition {
+            line: 0,
+            character: 14,
+          },
+          end: DiagnosticPosition {
+            line: 0,
+            character: 21,
+          },
+        },
+      }),
+      code_frame: Some(
+        "  export * from 'chalk';\n                ~~~~~~~".to_string()
+      ),
+      message: concat!(
+        "Relative import path \"chalk\" not prefixed with / or ./ or ../\n",
+        "    at file:///other.ts:1:15",
+      )
+      .to_string(),
+    }]
+  );
+}
+
 #[tokio::test]
 async fn transform_remote_file_error() {
   let err_message = TestBuilder::new()
@@ -926,7 +1584,7 @@ async fn transform_deno_types_and_type_ref_for_same_file() {
     })
     .transform().await.unwrap();
 
-  assert!(result.warnings.is_empty());
+  assert!(result.diagnostics.is_empty());
   assert_files!(
     result.main.files,
     &[
@@ -961,7 +1619,11 @@ async fn transform_deno_types_and_type_ref_for_different_local_file() {
     .transform().await.unwrap();
 
   assert_eq!(
-    result.warnings,
+    result
+      .diagnostics
+      .iter()
+      .map(|d| d.message.as_str())
+      .collect::<Vec<_>>(),
     vec![
       concat!(
         "Duplicate declaration file found for file:///file.js\n",
@@ -1016,7 +1678,11 @@ async fn transform_deno_types_and_type_ref_for_different_remote_file() {
   let result = setup().transform().await.unwrap();
 
   assert_eq!(
-    result.warnings,
+    result
+      .diagnostics
+      .iter()
+      .map(|d| d.message.as_str())
+      .collect::<Vec<_>>(),
     vec![
       concat!(
         "Duplicate declaration file found for http://localhost/file.js\n",
@@ -1063,7 +1729,7 @@ async fn transform_deno_types_and_type_ref_for_different_remote_file() {
   });
   let result = test_builder.transform().await.unwrap();
 
-  assert!(result.warnings.is_empty());
+  assert!(result.diagnostics.is_empty());
   assert_eq!(result.main.files.len(), 5);
   assert_eq!(
     result
@@ -1404,37 +2070,294 @@ async fn transform_import_map() {
 }
 
 #[tokio::test]
-async fn transform_multiple_entry_points() {
+async fn transform_import_map_diagnostics() {
   let result = TestBuilder::new()
     .with_loader(|loader| {
       loader
-        .add_local_file("/mod.ts", "import './ref.ts';mod1;")
-        .add_local_file("/mod2.ts", "import './ref.ts';mod2;")
-        .add_local_file("/ref.ts", "export const test = 5;");
+        .add_local_file("/mod.ts", "import * as other from 'other';")
+        .add_local_file(
+          "/import_map.json",
+          r#"{
+  "imports": {
+    "other": "./other.ts",
+    "invalid": 5
+  },
+  "unknown": {}
+}"#,
+        )
+        .add_local_file("/other.ts", "export function test() {}");
     })
-    .add_entry_point("file:///mod2.ts")
+    .set_import_map("file:///import_map.json")
     .transform()
     .await
     .unwrap();
 
+  assert_eq!(
+    result
+      .diagnostics
+      .iter()
+      .map(|d| d.message.as_str())
+      .collect::<Vec<_>>(),
+    vec![
+      "Import map diagnostic in file:///import_map.json: Invalid address \"5\" for the specifier key \"invalid\". Addresses must be strings.",
+      "Import map diagnostic in file:///import_map.json: Invalid top-level key \"unknown\". Only \"imports\" and \"scopes\" can be present.",
+    ]
+  );
+  assert!(result
+    .diagnostics
+    .iter()
+    .all(|d| d.code == DiagnosticCode::ImportMap
+      && d.specifier.as_ref().map(|s| s.as_str())
+        == Some("file:///import_map.json")));
   assert_files!(
     result.main.files,
     &[
-      ("mod.ts", "import './ref.js';mod1;"),
-      ("mod2.ts", "import './ref.js';mod2;"),
-      ("ref.ts", "export const test = 5;"),
+      ("mod.ts", "import * as other from './other.js';",),
+      ("other.ts", "export function test() {}",)
     ]
   );
 }
 
 #[tokio::test]
-async fn test_entry_points() {
-  let result = TestBuilder::new()
+async fn transform_import_map_diagnostics_error() {
+  let err_message = TestBuilder::new()
     .with_loader(|loader| {
       loader
+        .add_local_file("/mod.ts", "import * as other from 'other';")
         .add_local_file(
-          "/mod.ts",
-          "import package1 from 'https://cdn.skypack.dev/preact@^10.5.0';\n",
+          "/deno.json",
+          r#"{ "imports": { "other": "./other.ts", "invalid": 5 } }"#,
+        )
+        .add_local_file("/other.ts", "export function test() {}");
+    })
+    .set_config_file("file:///deno.json")
+    .set_error_on_import_map_diagnostics(true)
+    .transform()
+    .await
+    .err()
+    .unwrap();
+
+  assert_eq!(
+    err_message.to_string(),
+    "Import map diagnostics in file:///deno.json:\n  - Invalid address \"5\" for the specifier key \"invalid\". Addresses must be strings."
+  );
+}
+
+#[tokio::test]
+async fn transform_config_file_imports() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          "import * as remote from 'localhost/mod.ts';",
+        )
+        .add_local_file(
+          "/deno.jsonc",
+          r#"{
+  // test comments
+  "imports": {
+    "localhost/": "./subdir/"
+  },
+  "scopes": {
+    "./subdir/": {
+      "other": "./subdir/other.ts"
+    }
+  }
+}"#,
+        )
+        .add_local_file("/subdir/mod.ts", "import * as myOther from 'other';")
+        .add_local_file("/subdir/other.ts", "export function test() {}");
+    })
+    .set_config_file("file:///deno.jsonc")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      ("mod.ts", "import * as remote from './subdir/mod.js';",),
+      ("subdir/mod.ts", "import * as myOther from './other.js';",),
+      ("subdir/other.ts", "export function test() {}",)
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_config_file_import_map_property() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "import * as other from 'other';")
+        .add_local_file("/deno.json", r#"{ "importMap": "./import_map.json" }"#)
+        .add_local_file(
+          "/import_map.json",
+          r#"{ "imports": { "other": "./other.ts" } }"#,
+        )
+        .add_local_file("/other.ts", "export function test() {}");
+    })
+    .set_config_file("file:///deno.json")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      ("mod.ts", "import * as other from './other.js';",),
+      ("other.ts", "export function test() {}",)
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_config_file_import_map_option_precedence() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "import * as other from 'other';")
+        .add_local_file(
+          "/deno.json",
+          r#"{ "imports": { "other": "./config.ts" } }"#,
+        )
+        .add_local_file(
+          "/import_map.json",
+          r#"{ "imports": { "other": "./other.ts" } }"#,
+        )
+        .add_local_file("/other.ts", "export function test() {}");
+    })
+    .set_config_file("file:///deno.json")
+    .set_import_map("file:///import_map.json")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      ("mod.ts", "import * as other from './other.js';",),
+      ("other.ts", "export function test() {}",)
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_config_file_exports() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "export const mod = 5;")
+        .add_local_file("/src/other.ts", "export const other = 5;")
+        .add_local_file(
+          "/deno.json",
+          r#"{ "exports": { ".": "./mod.ts", "./other": "./src/other.ts" } }"#,
+        );
+    })
+    .no_entry_point()
+    .set_config_file("file:///deno.json")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_eq!(
+    result.main.entry_points,
+    vec![PathBuf::from("mod.ts"), PathBuf::from("src/other.ts")]
+  );
+  assert_files!(
+    result.main.files,
+    &[
+      ("mod.ts", "export const mod = 5;",),
+      ("src/other.ts", "export const other = 5;",)
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_config_file_jsx_import_source() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.tsx", "export const el = <div />;")
+        .add_local_file(
+          "/deno.json",
+          r#"{
+  "imports": { "preact": "npm:preact@^10.19.0" },
+  "compilerOptions": { "jsxImportSource": "preact" }
+}"#,
+        );
+    })
+    .entry_point("file:///mod.tsx")
+    .set_config_file("file:///deno.json")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_eq!(
+    result.main.dependencies,
+    vec![Dependency {
+      name: "preact".to_string(),
+      version: "^10.19.0".to_string(),
+      peer_dependency: false,
+    }]
+  );
+  assert_files!(
+    result.main.files,
+    &[("mod.tsx", "export const el = <div />;")]
+  );
+}
+
+#[tokio::test]
+async fn transform_config_file_no_exports_or_entry_points() {
+  let err_message = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/deno.json", r#"{ "imports": {} }"#);
+    })
+    .no_entry_point()
+    .set_config_file("file:///deno.json")
+    .transform()
+    .await
+    .err()
+    .unwrap();
+
+  assert_eq!(
+    err_message.to_string(),
+    "at least one entry point must be specified"
+  );
+}
+
+#[tokio::test]
+async fn transform_multiple_entry_points() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "import './ref.ts';mod1;")
+        .add_local_file("/mod2.ts", "import './ref.ts';mod2;")
+        .add_local_file("/ref.ts", "export const test = 5;");
+    })
+    .add_entry_point("file:///mod2.ts")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      ("mod.ts", "import './ref.js';mod1;"),
+      ("mod2.ts", "import './ref.js';mod2;"),
+      ("ref.ts", "export const test = 5;"),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn test_entry_points() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          "import package1 from 'https://cdn.skypack.dev/preact@^10.5.0';\n",
         )
         .add_local_file(
           "/mod.test.ts",
@@ -1446,116 +2369,935 @@ async fn test_entry_points() {
           ),
         );
     })
-    .add_test_entry_point("file:///mod.test.ts")
-    .add_default_shims()
+    .add_test_entry_point("file:///mod.test.ts")
+    .add_default_shims()
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[("mod.ts", "import package1 from 'preact';\n",)]
+  );
+  assert_eq!(
+    result.main.dependencies,
+    &[Dependency {
+      name: "preact".to_string(),
+      version: "^10.5.0".to_string(),
+      peer_dependency: false,
+    },]
+  );
+  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
+
+  assert_files!(
+    result.test.files,
+    &[
+      (
+        "mod.test.ts",
+        concat!(
+          "import * as dntShim from \"./_dnt.test_shims.js\";\n",
+          "import './mod.js';\n",
+          "import package1 from 'preact';\n",
+          "import package3 from 'react';\n",
+          "dntShim.Deno.writeTextFile('test', 'test')"
+        )
+        .to_string(),
+      ),
+      (
+        "_dnt.test_shims.ts",
+        get_shim_file_text(
+          concat!(
+            "import { Deno } from \"@deno/shim-deno\";\n",
+            "export { Deno } from \"@deno/shim-deno\";\n",
+            "\n",
+            "const dntGlobals = {\n",
+            "  Deno,\n",
+            "};\n",
+            "export const dntGlobalThis = createMergeProxy(globalThis, dntGlobals);\n",
+          )
+          .to_string(),
+        ),
+      )
+    ]
+  );
+  assert_eq!(
+    result.test.dependencies,
+    &[
+      Dependency {
+        name: "react".to_string(),
+        version: "17.0.2".to_string(),
+        peer_dependency: false,
+      },
+      Dependency {
+        name: "@deno/shim-deno".to_string(),
+        version: "^0.1.0".to_string(),
+        peer_dependency: false,
+      },
+    ]
+  );
+  assert_eq!(result.test.entry_points, &[PathBuf::from("mod.test.ts")]);
+}
+
+#[tokio::test]
+async fn test_entry_points_same_module_multiple_places() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "export * from 'https://deno.land/std@0.181.0/path.ts';\n",
+            "import * as deps from './deps.ts';",
+          ),
+        )
+        // ensure that the path.ts in this file being already analyzed
+        // doesn't cause flags.ts to not be analyzed
+        .add_local_file(
+          "/deps.ts",
+          concat!(
+            "export * from 'https://deno.land/std@0.181.0/path.ts';\n",
+            "export * from 'https://deno.land/std@0.181.0/flags.ts';",
+          ),
+        )
+        .add_remote_file(
+          "https://deno.land/std@0.181.0/flags.ts",
+          "export class Flags {}",
+        )
+        .add_remote_file(
+          "https://deno.land/std@0.181.0/path.ts",
+          "export class Path {}",
+        )
+        .add_local_file("/mod.test.ts", "import * as deps from './deps.ts';");
+    })
+    .add_test_entry_point("file:///mod.test.ts")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "export * from './deps/deno.land/std@0.181.0/path.js';\n",
+          "import * as deps from './deps.js';",
+        )
+      ),
+      (
+        "deps.ts",
+        concat!(
+          "export * from './deps/deno.land/std@0.181.0/path.js';\n",
+          "export * from './deps/deno.land/std@0.181.0/flags.js';",
+        )
+      ),
+      (
+        "deps/deno.land/std@0.181.0/flags.ts",
+        "export class Flags {}"
+      ),
+      ("deps/deno.land/std@0.181.0/path.ts", "export class Path {}")
+    ]
+  );
+  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
+
+  assert_files!(
+    result.test.files,
+    &[("mod.test.ts", "import * as deps from './deps.js';",)]
+  );
+  assert_eq!(result.test.entry_points, &[PathBuf::from("mod.test.ts")]);
+}
+
+#[tokio::test]
+async fn transform_import_meta_common_js() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "const url = new URL(import.meta.url);\n",
+          "if (import.meta.main) {}\n",
+          "import.meta.resolve(import.meta.url + '/other.ts');\n",
+          "import.meta.resolve(\"./a.ts\", \"b\");\n",
+          "import.meta.other;",
+        ),
+      );
+    })
+    .set_module_kind(ModuleKind::CommonJs)
+    .transform()
+    .await
+    .unwrap();
+
+  // the call with two arguments isn't rewritten, so it needs the polyfill
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import \"./_dnt.polyfills.js\";\n",
+          "const url = new URL(require(\"url\").pathToFileURL(__filename).href);\n",
+          "if ((require.main === module)) {}\n",
+          "new URL(require(\"url\").pathToFileURL(__filename).href + '/other.ts', require(\"url\").pathToFileURL(__filename).href).href;\n",
+          "import.meta.resolve(\"./a.ts\", \"b\");\n",
+          "import.meta.other;",
+        ),
+      ),
+      (
+        "_dnt.polyfills.ts",
+        include_str!("../src/polyfills/scripts/deno.import-meta.ts"),
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_import_meta_esm() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "const url = new URL(import.meta.url);\n",
+          "if (import.meta.main) {}\n",
+          "import.meta.resolve(specifier);",
+        ),
+      );
+    })
+    .set_module_kind(ModuleKind::Esm)
+    .transform()
+    .await
+    .unwrap();
+
+  // the import-meta polyfill isn't necessary
+  assert_files!(
+    result.main.files,
+    &[(
+      "mod.ts",
+      concat!(
+        "const url = new URL(import.meta.url);\n",
+        "if ((import.meta.url === (\"file:///\" + process.argv[1].replace(/\\\\/g, \"/\")).replace(/\\/{3,}/, \"///\"))) {}\n",
+        "new URL(specifier, import.meta.url).href;",
+      ),
+    )]
+  );
+}
+
+#[tokio::test]
+async fn transform_import_meta_esm_not_rewritten() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "const resolve = import.meta.resolve;\n",
+          "import.meta.resolve(specifier);",
+        ),
+      );
+    })
+    .set_module_kind(ModuleKind::Esm)
+    .transform()
+    .await
+    .unwrap();
+
+  // the declarations are still necessary for the uses that aren't rewritten
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import \"./_dnt.polyfills.js\";\n",
+          "const resolve = import.meta.resolve;\n",
+          "new URL(specifier, import.meta.url).href;",
+        ),
+      ),
+      (
+        "_dnt.polyfills.ts",
+        include_str!("../src/polyfills/scripts/deno.import-meta.ts"),
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_node_test() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "export const a = 5;")
+        .add_local_file(
+          "/mod.test.ts",
+          concat!(
+            "import { a } from \"./mod.ts\";\n",
+            "Deno.test(\"adds\", () => {});\n",
+            "Deno.test(function named() {});\n",
+            "Deno.test(\"options\", { ignore: Deno.build.os === \"windows\", permissions: { read: true } }, () => {});\n",
+            "Deno.test({ sanitizeOps: false, only: true }, async function other() {});\n",
+            "Deno.test.ignore(\"ignored\", () => {});\n",
+            "Deno.test.only(\"only\", () => {});\n",
+            "Deno.test({\n",
+            "  name: \"definition\",\n",
+            "  ignore,\n",
+            "  sanitizeResources: false,\n",
+            "  async fn(t) {\n",
+            "    await t.step(\"step\", async (t2) => {\n",
+            "      await t2.step({ name: \"nested\", fn: () => {} });\n",
+            "    });\n",
+            "    await t.step({ name: \"ignored step\", ignore: true, fn: () => {} });\n",
+            "    console.log(a);\n",
+            "  },\n",
+            "});\n",
+            "Deno.test({ name: \"no options\", fn: () => {} });\n",
+          ),
+        );
+    })
+    .add_test_entry_point("file:///mod.test.ts")
+    .add_default_shims()
+    .set_node_test(true)
+    .transform()
+    .await
+    .unwrap();
+
+  assert!(result.diagnostics.is_empty());
+  assert_files!(
+    result.test.files,
+    &[
+      (
+        "mod.test.ts",
+        concat!(
+          "import * as dntShim from \"./_dnt.test_shims.js\";\n",
+          "import { test } from \"node:test\";\n",
+          "import { a } from \"./mod.js\";\n",
+          "test(\"adds\", () => {});\n",
+          "test(function named() {});\n",
+          "test(\"options\", { skip: dntShim.Deno.build.os === \"windows\" }, () => {});\n",
+          "test({ only: true }, async function other() {});\n",
+          "test.skip(\"ignored\", () => {});\n",
+          "test.only(\"only\", () => {});\n",
+          "test(\"definition\", { skip: ignore }, async function(t) {\n",
+          "    await t.test(\"step\", async (t2) => {\n",
+          "      await t2.test(\"nested\", () => {});\n",
+          "    });\n",
+          "    await t.test(\"ignored step\", { skip: true }, () => {});\n",
+          "    console.log(a);\n",
+          "  });\n",
+          "test(\"no options\", () => {});\n",
+        ),
+      ),
+      (
+        "_dnt.test_shims.ts",
+        &get_shim_file_text(
+          concat!(
+            "import { Deno } from \"@deno/shim-deno\";\n",
+            "export { Deno } from \"@deno/shim-deno\";\n",
+            "\n",
+            "const dntGlobals = {\n",
+            "  Deno,\n",
+            "};\n",
+            "export const dntGlobalThis = createMergeProxy(globalThis, dntGlobals);\n",
+          )
+          .to_string(),
+        ),
+      ),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn transform_node_test_shadowed_name() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "export const a = 5;")
+        .add_local_file(
+          "/mod.test.ts",
+          concat!(
+            "function register(test: string) {\n",
+            "  Deno.test(test, () => {});\n",
+            "}\n",
+            "register(\"a\");\n",
+          ),
+        );
+    })
+    .add_test_entry_point("file:///mod.test.ts")
+    .set_node_test(true)
+    .transform()
+    .await
+    .unwrap();
+
+  assert!(result.diagnostics.is_empty());
+  assert_files!(
+    result.test.files,
+    &[(
+      "mod.test.ts",
+      concat!(
+        "import { test as test1 } from \"node:test\";\n",
+        "function register(test: string) {\n",
+        "  test1(test, () => {});\n",
+        "}\n",
+        "register(\"a\");\n",
+      ),
+    )]
+  );
+}
+
+#[tokio::test]
+async fn transform_node_test_not_analyzable() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "export const a = 5;")
+        .add_local_file(
+          "/mod.test.ts",
+          concat!(
+            "const test = 5;\n",
+            "Deno.test(\"a\", () => {});\n",
+            "Deno.test(definition);\n",
+            "Deno.test({ fn: () => {}, name: \"b\" });",
+          ),
+        );
+    })
+    .add_test_entry_point("file:///mod.test.ts")
+    .set_node_test(true)
+    .transform()
+    .await
+    .unwrap();
+
+  assert_eq!(
+    result
+      .diagnostics
+      .iter()
+      .map(|d| (d.code, d.message.as_str()))
+      .collect::<Vec<_>>(),
+    vec![(
+      DiagnosticCode::DenoTest,
+      "Could not convert the Deno.test call at file:///mod.test.ts:4:1 to node:test.",
+    )]
+  );
+  assert_files!(
+    result.test.files,
+    &[(
+      "mod.test.ts",
+      concat!(
+        "import { test as test1 } from \"node:test\";\n",
+        "const test = 5;\n",
+        "test1(\"a\", () => {});\n",
+        "test1(definition);\n",
+        "Deno.test({ fn: () => {}, name: \"b\" });",
+      ),
+    )]
+  );
+}
+
+#[tokio::test]
+async fn polyfills_all() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "export const test = (obj) => Object.hasOwn(obj, 'test');\n",
+            "try {\n",
+            "} catch (err) {\n",
+            "  err.cause = new Error();\n",
+            "}\n",
+            "''.replaceAll('test', 'other');\n",
+            "[].findLast(() => true);\n",
+            "import.meta.main;\n",
+          ),
+        )
+        .add_local_file("/mod.test.ts", "import * as mod from './mod.ts';");
+    })
+    .add_test_entry_point("file:///mod.test.ts")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import \"./_dnt.polyfills.js\";\n",
+          "export const test = (obj) => Object.hasOwn(obj, 'test');\n",
+          "try {\n",
+          "} catch (err) {\n",
+          "  err.cause = new Error();\n",
+          "}\n",
+          "''.replaceAll('test', 'other');\n",
+          "[].findLast(() => true);\n",
+          "import.meta.main;\n",
+        ),
+      ),
+      (
+        "_dnt.polyfills.ts",
+        concat!(
+          include_str!("../src/polyfills/scripts/esnext.object-has-own.ts"),
+          include_str!("../src/polyfills/scripts/esnext.error-cause.ts"),
+          include_str!("../src/polyfills/scripts/es2021.string-replaceAll.ts"),
+          include_str!("../src/polyfills/scripts/esnext.array-findLast.ts"),
+          include_str!("../src/polyfills/scripts/deno.import-meta.ts"),
+        )
+      ),
+    ]
+  );
+  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
+
+  assert_files!(
+    result.test.files,
+    &[("mod.test.ts", concat!("import * as mod from './mod.js';",),)]
+  );
+  assert_eq!(result.test.entry_points, &[PathBuf::from("mod.test.ts")]);
+}
+
+#[tokio::test]
+async fn polyfills_string_replaceall_target() {
+  test_string_replace_all_polyfill(ScriptTarget::ES2020, true).await;
+  test_string_replace_all_polyfill(ScriptTarget::ES2021, false).await;
+}
+
+async fn test_string_replace_all_polyfill(
+  target: ScriptTarget,
+  should_have_polyfill: bool,
+) {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "''.replaceAll('test', 'other');\n")
+        .add_local_file("/mod.test.ts", "import * as mod from './mod.ts';");
+    })
+    .add_test_entry_point("file:///mod.test.ts")
+    .set_target(target)
+    .transform()
+    .await
+    .unwrap();
+
+  if should_have_polyfill {
+    assert_files!(
+      result.main.files,
+      &[
+        (
+          "mod.ts",
+          concat!(
+            "import \"./_dnt.polyfills.js\";\n",
+            "''.replaceAll('test', 'other');\n",
+          ),
+        ),
+        (
+          "_dnt.polyfills.ts",
+          concat!(include_str!(
+            "../src/polyfills/scripts/es2021.string-replaceAll.ts"
+          ),)
+        ),
+      ]
+    );
+  } else {
+    assert_files!(
+      result.main.files,
+      &[("mod.ts", "''.replaceAll('test', 'other');\n",)]
+    );
+  }
+  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
+}
+
+#[tokio::test]
+async fn polyfills_array_change_by_copy_target() {
+  test_array_change_by_copy_polyfill(ScriptTarget::ES2022, true).await;
+  test_array_change_by_copy_polyfill(ScriptTarget::ES2023, false).await;
+}
+
+async fn test_array_change_by_copy_polyfill(
+  target: ScriptTarget,
+  should_have_polyfill: bool,
+) {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        "[2, 1].toSorted();
+",
+      );
+    })
+    .set_target(target)
+    .transform()
+    .await
+    .unwrap();
+
+  if should_have_polyfill {
+    assert_files!(
+      result.main.files,
+      &[
+        (
+          "mod.ts",
+          concat!("import \"./_dnt.polyfills.js\";\n", "[2, 1].toSorted();\n",),
+        ),
+        (
+          "_dnt.polyfills.ts",
+          include_str!("../src/polyfills/scripts/es2023.array-changeByCopy.ts")
+        ),
+      ]
+    );
+  } else {
+    assert_files!(result.main.files, &[("mod.ts", "[2, 1].toSorted();\n")]);
+  }
+}
+
+#[tokio::test]
+async fn polyfills_set_methods_and_group_by() {
+  let code = concat!(
+    "new Set([1]).union(new Set([2]));\n",
+    "Map.groupBy([1, 2], (n) => n % 2);\n",
+  );
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", code);
+    })
+    .set_target(ScriptTarget::ES2023)
+    .transform()
+    .await
+    .unwrap();
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import \"./_dnt.polyfills.js\";\n",
+          "new Set([1]).union(new Set([2]));\n",
+          "Map.groupBy([1, 2], (n) => n % 2);\n",
+        ),
+      ),
+      (
+        "_dnt.polyfills.ts",
+        concat!(
+          include_str!("../src/polyfills/scripts/es2025.set-methods.ts"),
+          include_str!("../src/polyfills/scripts/es2024.groupBy.ts"),
+        )
+      ),
+    ]
+  );
+
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", code);
+    })
+    .set_target(ScriptTarget::Latest)
+    .transform()
+    .await
+    .unwrap();
+  assert_files!(result.main.files, &[("mod.ts", code)]);
+}
+
+#[tokio::test]
+async fn polyfills_node_version_range() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "Object.hasOwn({}, 'a');\n",
+          "[].findLast(() => true);\n",
+          "[].toSorted();\n",
+          "new WeakRef({});\n",
+          "''.isWellFormed();\n",
+        ),
+      );
+    })
+    .set_node_version_range("^18.2 || >=20")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import \"./_dnt.polyfills.js\";\n",
+          "Object.hasOwn({}, 'a');\n",
+          "[].findLast(() => true);\n",
+          "[].toSorted();\n",
+          "new WeakRef({});\n",
+          "''.isWellFormed();\n",
+        ),
+      ),
+      (
+        "_dnt.polyfills.ts",
+        include_str!("../src/polyfills/scripts/es2023.array-changeByCopy.ts")
+      ),
+    ]
+  );
+  assert_eq!(
+    result.diagnostics,
+    vec![Diagnostic {
+      code: DiagnosticCode::UnsupportedBuiltIn,
+      severity: DiagnosticSeverity::Warning,
+      specifier: Some(ModuleSpecifier::parse("file:///mod.ts").unwrap()),
+      range: Some(DiagnosticRange {
+        start: DiagnosticPosition {
+          line: 4,
+          character: 0,
+        },
+        end: DiagnosticPosition {
+          line: 4,
+          character: 17,
+        },
+      }),
+      message: concat!(
+        "String.prototype.isWellFormed was added in Node.js 20.0.0, so it's ",
+        "missing in some versions of the range ^18.2 || >=20 and it can't be ",
+        "polyfilled.",
+      )
+      .to_string(),
+    }]
+  );
+
+  let err = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", "");
+    })
+    .set_node_version_range("latest")
+    .transform()
+    .await
+    .err()
+    .unwrap();
+  assert_eq!(err.to_string(), "Invalid Node.js version range: latest");
+}
+
+#[tokio::test]
+async fn polyfills_node_version_range_unknown_receivers() {
+  // the methods may be user defined on other values
+  let code = concat!(
+    "const list = { at(i) { return i; } };\n",
+    "list.at(0);\n",
+    "getText().isWellFormed();\n",
+    "(`a`).toWellFormed();\n",
+  );
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", code);
+    })
+    .set_node_version_range(">=16")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_eq!(
+    result
+      .diagnostics
+      .iter()
+      .map(|d| d.message.as_str())
+      .collect::<Vec<_>>(),
+    vec![concat!(
+      "String.prototype.toWellFormed was added in Node.js 20.0.0, so it's ",
+      "missing in some versions of the range >=16 and it can't be ",
+      "polyfilled.",
+    )]
+  );
+}
+
+#[tokio::test]
+async fn transform_using_declarations() {
+  let code = concat!(
+    "function read() {\n",
+    "  using file = open(), other = open();\n",
+    "  return file.read();\n",
+    "}\n",
+    "async function connect() {\n",
+    "  console.log(1);\n",
+    "  await using conn = await connect();\n",
+    "  using lock = conn.lock()\n",
+    "}\n",
+    "for (using item of items) {\n",
+    "  item.run();\n",
+    "}\n",
+    "for await (await using item of items) item.run();\n",
+  );
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", code);
+    })
+    .set_target(ScriptTarget::ES2022)
+    .transform()
+    .await
+    .unwrap();
+
+  let try_suffix = concat!(
+    "} catch (dntError) { try { dntStack.dispose(); } catch ",
+    "(dntDisposeError) { throw new SuppressedError(dntDisposeError, ",
+    "dntError); } throw dntError; } finally { dntStack.dispose(); }",
+  );
+  let async_try_suffix = concat!(
+    "} catch (dntError) { try { await dntStack.disposeAsync(); } catch ",
+    "(dntDisposeError) { throw new SuppressedError(dntDisposeError, ",
+    "dntError); } throw dntError; } finally { ",
+    "await dntStack.disposeAsync(); }",
+  );
+  let expected = format!(
+    concat!(
+      "import \"./_dnt.polyfills.js\";\n",
+      "function read() {{\n",
+      "  const dntStack = new DisposableStack(); try {{ ",
+      "const file = dntStack.use(open()), other = dntStack.use(open());\n",
+      "  return file.read(); {sync}\n",
+      "}}\n",
+      "async function connect() {{\n",
+      "  const dntStack = new AsyncDisposableStack(); try {{ ",
+      "console.log(1);\n",
+      "  const conn = dntStack.use(await connect());\n",
+      "  const lock = dntStack.use(conn.lock()) {async}\n",
+      "}}\n",
+      "for (const item of items) {{ const dntStack = new DisposableStack(); ",
+      "try {{ dntStack.use(item);\n",
+      "  item.run();\n",
+      " {sync}}}\n",
+      "for await (const item of items) {{ ",
+      "const dntStack = new AsyncDisposableStack(); try {{ ",
+      "dntStack.use(item); item.run(); {async} }}\n",
+    ),
+    sync = try_suffix,
+    async = async_try_suffix,
+  );
+  assert_files!(
+    result.main.files,
+    &[
+      ("mod.ts", expected.as_str()),
+      (
+        "_dnt.polyfills.ts",
+        include_str!("../src/polyfills/scripts/esnext.disposable.ts")
+      ),
+    ]
+  );
+  assert_eq!(result.diagnostics, Vec::new());
+
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", code);
+    })
+    .set_target(ScriptTarget::Latest)
+    .transform()
+    .await
+    .unwrap();
+  assert_files!(result.main.files, &[("mod.ts", code)]);
+}
+
+#[tokio::test]
+async fn transform_using_declarations_earlier_statements() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "function load() {\n",
+          "  \"use strict\";\n",
+          "  function getConfig() {\n",
+          "    return new Config(config);\n",
+          "  }\n",
+          "  using res = open();\n",
+          "  const config: Deno.Env = res.read();\n",
+          "  const read = () => res.read();\n",
+          "  class Config {}\n",
+          "  return getConfig();\n",
+          "}\n",
+        ),
+      );
+    })
+    .add_default_shims()
+    .set_target(ScriptTarget::ES2022)
+    .transform()
+    .await
+    .unwrap();
+
+  let expected = concat!(
+    "import \"./_dnt.polyfills.js\";\n",
+    "import * as dntShim from \"./_dnt.shims.js\";\n",
+    "function load() {\n",
+    "  \"use strict\";\n",
+    "  const dntStack = new DisposableStack(); try { ",
+    "function getConfig() {\n",
+    "    return new Config(config);\n",
+    "  }\n",
+    "  const res = dntStack.use(open());\n",
+    "  const config: dntShim.Deno.Env = res.read();\n",
+    "  const read = () => res.read();\n",
+    "  class Config {}\n",
+    "  return getConfig(); } catch (dntError) { try { dntStack.dispose(); } ",
+    "catch (dntDisposeError) { throw new SuppressedError(dntDisposeError, ",
+    "dntError); } throw dntError; } finally { dntStack.dispose(); }\n",
+    "}\n",
+  );
+  let file = result
+    .main
+    .files
+    .iter()
+    .find(|f| f.file_path == PathBuf::from("mod.ts"))
+    .unwrap();
+  assert_eq!(file.file_text, expected);
+  assert_eq!(result.diagnostics, Vec::new());
+}
+
+#[tokio::test]
+async fn transform_using_declarations_not_in_block() {
+  let code = concat!("using file = open();\n", "namespace ns {}\n",);
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", code);
+    })
+    .set_target(ScriptTarget::ES2022)
     .transform()
     .await
     .unwrap();
 
+  let expected = format!("import \"./_dnt.polyfills.js\";\n{}", code);
   assert_files!(
     result.main.files,
-    &[("mod.ts", "import package1 from 'preact';\n",)]
-  );
-  assert_eq!(
-    result.main.dependencies,
-    &[Dependency {
-      name: "preact".to_string(),
-      version: "^10.5.0".to_string(),
-      peer_dependency: false,
-    },]
-  );
-  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
-
-  assert_files!(
-    result.test.files,
     &[
+      ("mod.ts", expected.as_str()),
       (
-        "mod.test.ts",
-        concat!(
-          "import * as dntShim from \"./_dnt.test_shims.js\";\n",
-          "import './mod.js';\n",
-          "import package1 from 'preact';\n",
-          "import package3 from 'react';\n",
-          "dntShim.Deno.writeTextFile('test', 'test')"
-        )
-        .to_string(),
+        "_dnt.polyfills.ts",
+        include_str!("../src/polyfills/scripts/esnext.disposable.ts")
       ),
-      (
-        "_dnt.test_shims.ts",
-        get_shim_file_text(
-          concat!(
-            "import { Deno } from \"@deno/shim-deno\";\n",
-            "export { Deno } from \"@deno/shim-deno\";\n",
-            "import { setTimeout, setInterval } from \"@deno/shim-timers\";\n",
-            "export { setTimeout, setInterval } from \"@deno/shim-timers\";\n",
-            "\n",
-            "const dntGlobals = {\n",
-            "  Deno,\n",
-            "  setTimeout,\n",
-            "  setInterval,\n",
-            "};\n",
-            "export const dntGlobalThis = createMergeProxy(globalThis, dntGlobals);\n",
-          )
-          .to_string(),
-        ),
-      )
     ]
   );
   assert_eq!(
-    result.test.dependencies,
-    &[
-      Dependency {
-        name: "react".to_string(),
-        version: "17.0.2".to_string(),
-        peer_dependency: false,
-      },
-      Dependency {
-        name: "@deno/shim-deno".to_string(),
-        version: "^0.1.0".to_string(),
-        peer_dependency: false,
-      },
-      Dependency {
-        name: "@deno/shim-timers".to_string(),
-        version: "^0.1.0".to_string(),
-        peer_dependency: false,
-      }
-    ]
+    result.diagnostics,
+    vec![Diagnostic {
+      code: DiagnosticCode::UsingDeclaration,
+      severity: DiagnosticSeverity::Warning,
+      specifier: Some(ModuleSpecifier::parse("file:///mod.ts").unwrap()),
+      range: Some(DiagnosticRange {
+        start: DiagnosticPosition {
+          line: 0,
+          character: 0,
+        },
+        end: DiagnosticPosition {
+          line: 0,
+          character: 19,
+        },
+      }),
+      message: concat!(
+        "Could not downlevel the using declaration at file:///mod.ts:1:1, ",
+        "so it requires a runtime that supports it.",
+      )
+      .to_string(),
+    }]
   );
-  assert_eq!(result.test.entry_points, &[PathBuf::from("mod.test.ts")]);
 }
 
 #[tokio::test]
-async fn test_entry_points_same_module_multiple_places() {
+async fn transform_using_declarations_not_downleveled() {
+  let code = concat!(
+    "using file = open();\n",
+    "export const text = file.read();\n",
+  );
   let result = TestBuilder::new()
     .with_loader(|loader| {
-      loader
-        .add_local_file(
-          "/mod.ts",
-          concat!(
-            "export * from 'https://deno.land/std@0.181.0/path.ts';\n",
-            "import * as deps from './deps.ts';",
-          ),
-        )
-        // ensure that the path.ts in this file being already analyzed
-        // doesn't cause flags.ts to not be analyzed
-        .add_local_file(
-          "/deps.ts",
-          concat!(
-            "export * from 'https://deno.land/std@0.181.0/path.ts';\n",
-            "export * from 'https://deno.land/std@0.181.0/flags.ts';",
-          ),
-        )
-        .add_remote_file(
-          "https://deno.land/std@0.181.0/flags.ts",
-          "export class Flags {}",
-        )
-        .add_remote_file(
-          "https://deno.land/std@0.181.0/path.ts",
-          "export class Path {}",
-        )
-        .add_local_file("/mod.test.ts", "import * as deps from './deps.ts';");
+      loader.add_local_file("/mod.ts", code);
     })
-    .add_test_entry_point("file:///mod.test.ts")
+    .set_target(ScriptTarget::ES2022)
     .transform()
     .await
     .unwrap();
@@ -1566,54 +3308,57 @@ async fn test_entry_points_same_module_multiple_places() {
       (
         "mod.ts",
         concat!(
-          "export * from './deps/deno.land/std@0.181.0/path.js';\n",
-          "import * as deps from './deps.js';",
-        )
-      ),
-      (
-        "deps.ts",
-        concat!(
-          "export * from './deps/deno.land/std@0.181.0/path.js';\n",
-          "export * from './deps/deno.land/std@0.181.0/flags.js';",
+          "import \"./_dnt.polyfills.js\";\n",
+          "using file = open();\n",
+          "export const text = file.read();\n",
         )
       ),
       (
-        "deps/deno.land/std@0.181.0/flags.ts",
-        "export class Flags {}"
+        "_dnt.polyfills.ts",
+        include_str!("../src/polyfills/scripts/esnext.disposable.ts")
       ),
-      ("deps/deno.land/std@0.181.0/path.ts", "export class Path {}")
     ]
   );
-  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
-
-  assert_files!(
-    result.test.files,
-    &[("mod.test.ts", "import * as deps from './deps.js';",)]
+  assert_eq!(
+    result.diagnostics,
+    vec![Diagnostic {
+      code: DiagnosticCode::UsingDeclaration,
+      severity: DiagnosticSeverity::Warning,
+      specifier: Some(ModuleSpecifier::parse("file:///mod.ts").unwrap()),
+      range: Some(DiagnosticRange {
+        start: DiagnosticPosition {
+          line: 0,
+          character: 0,
+        },
+        end: DiagnosticPosition {
+          line: 0,
+          character: 19,
+        },
+      }),
+      message: concat!(
+        "Could not downlevel the using declaration at file:///mod.ts:1:1, ",
+        "so it requires a runtime that supports it.",
+      )
+      .to_string(),
+    }]
   );
-  assert_eq!(result.test.entry_points, &[PathBuf::from("mod.test.ts")]);
 }
 
 #[tokio::test]
-async fn polyfills_all() {
+async fn transform_using_declarations_top_level() {
   let result = TestBuilder::new()
     .with_loader(|loader| {
-      loader
-        .add_local_file(
-          "/mod.ts",
-          concat!(
-            "export const test = (obj) => Object.hasOwn(obj, 'test');\n",
-            "try {\n",
-            "} catch (err) {\n",
-            "  err.cause = new Error();\n",
-            "}\n",
-            "''.replaceAll('test', 'other');\n",
-            "[].findLast(() => true);\n",
-            "import.meta.main;\n",
-          ),
-        )
-        .add_local_file("/mod.test.ts", "import * as mod from './mod.ts';");
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "import { open } from \"./file.ts\";\n",
+          "using file = open();\n",
+          "console.log(file.read());\n",
+        ),
+      );
+      loader.add_local_file("/file.ts", "export function open() {}");
     })
-    .add_test_entry_point("file:///mod.test.ts")
+    .set_target(ScriptTarget::ES2022)
     .transform()
     .await
     .unwrap();
@@ -1625,85 +3370,83 @@ async fn polyfills_all() {
         "mod.ts",
         concat!(
           "import \"./_dnt.polyfills.js\";\n",
-          "export const test = (obj) => Object.hasOwn(obj, 'test');\n",
-          "try {\n",
-          "} catch (err) {\n",
-          "  err.cause = new Error();\n",
-          "}\n",
-          "''.replaceAll('test', 'other');\n",
-          "[].findLast(() => true);\n",
-          "import.meta.main;\n",
-        ),
+          "import { open } from \"./file.js\";\n",
+          "const dntStack = new DisposableStack(); try { ",
+          "const file = dntStack.use(open());\n",
+          "console.log(file.read()); } catch (dntError) { try { ",
+          "dntStack.dispose(); } catch (dntDisposeError) { throw new ",
+          "SuppressedError(dntDisposeError, dntError); } throw dntError; } ",
+          "finally { dntStack.dispose(); }\n",
+        )
       ),
+      ("file.ts", "export function open() {}"),
       (
         "_dnt.polyfills.ts",
-        concat!(
-          include_str!("../src/polyfills/scripts/esnext.object-has-own.ts"),
-          include_str!("../src/polyfills/scripts/esnext.error-cause.ts"),
-          include_str!("../src/polyfills/scripts/es2021.string-replaceAll.ts"),
-          include_str!("../src/polyfills/scripts/esnext.array-findLast.ts"),
-          include_str!("../src/polyfills/scripts/deno.import-meta.ts"),
-        )
+        include_str!("../src/polyfills/scripts/esnext.disposable.ts")
       ),
     ]
   );
-  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
-
-  assert_files!(
-    result.test.files,
-    &[("mod.test.ts", concat!("import * as mod from './mod.js';",),)]
-  );
-  assert_eq!(result.test.entry_points, &[PathBuf::from("mod.test.ts")]);
+  assert_eq!(result.diagnostics, Vec::new());
 }
 
 #[tokio::test]
-async fn polyfills_string_replaceall_target() {
-  test_string_replace_all_polyfill(ScriptTarget::ES2020, true).await;
-  test_string_replace_all_polyfill(ScriptTarget::ES2021, false).await;
-}
-
-async fn test_string_replace_all_polyfill(
-  target: ScriptTarget,
-  should_have_polyfill: bool,
-) {
+async fn transform_using_declarations_multiple_modules() {
+  // the polyfill is only found in one of the modules, but both downlevel
   let result = TestBuilder::new()
     .with_loader(|loader| {
-      loader
-        .add_local_file("/mod.ts", "''.replaceAll('test', 'other');\n")
-        .add_local_file("/mod.test.ts", "import * as mod from './mod.ts';");
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "import { read } from \"./file.ts\";\n",
+          "using file = open();\n",
+          "read(file);\n",
+        ),
+      );
+      loader.add_local_file(
+        "/file.ts",
+        "export function read(file) {\n  using lock = file.lock();\n}\n",
+      );
     })
-    .add_test_entry_point("file:///mod.test.ts")
-    .set_target(target)
+    .set_target(ScriptTarget::ES2022)
     .transform()
     .await
     .unwrap();
 
-  if should_have_polyfill {
-    assert_files!(
-      result.main.files,
-      &[
-        (
-          "mod.ts",
-          concat!(
-            "import \"./_dnt.polyfills.js\";\n",
-            "''.replaceAll('test', 'other');\n",
-          ),
-        ),
-        (
-          "_dnt.polyfills.ts",
-          concat!(include_str!(
-            "../src/polyfills/scripts/es2021.string-replaceAll.ts"
-          ),)
-        ),
-      ]
-    );
-  } else {
-    assert_files!(
-      result.main.files,
-      &[("mod.ts", "''.replaceAll('test', 'other');\n",)]
-    );
-  }
-  assert_eq!(result.main.entry_points, &[PathBuf::from("mod.ts")]);
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        concat!(
+          "import \"./_dnt.polyfills.js\";\n",
+          "import { read } from \"./file.js\";\n",
+          "const dntStack = new DisposableStack(); try { ",
+          "const file = dntStack.use(open());\n",
+          "read(file); } catch (dntError) { try { ",
+          "dntStack.dispose(); } catch (dntDisposeError) { throw new ",
+          "SuppressedError(dntDisposeError, dntError); } throw dntError; } ",
+          "finally { dntStack.dispose(); }\n",
+        )
+      ),
+      (
+        "file.ts",
+        concat!(
+          "export function read(file) {\n",
+          "  const dntStack = new DisposableStack(); try { ",
+          "const lock = dntStack.use(file.lock()); } catch (dntError) { try { ",
+          "dntStack.dispose(); } catch (dntDisposeError) { throw new ",
+          "SuppressedError(dntDisposeError, dntError); } throw dntError; } ",
+          "finally { dntStack.dispose(); }\n",
+          "}\n",
+        )
+      ),
+      (
+        "_dnt.polyfills.ts",
+        include_str!("../src/polyfills/scripts/esnext.disposable.ts")
+      ),
+    ]
+  );
+  assert_eq!(result.diagnostics, Vec::new());
 }
 
 #[tokio::test]
@@ -1862,15 +3605,39 @@ async fn redirect_not_found() {
 }
 
 #[tokio::test]
-async fn json_module_import_default() {
+async fn json_module_import_default() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          r#"import jsonData from './data.json' assert { type: 'json' };"#,
+        )
+        .add_local_file("/data.json", "\u{FEFF}{ \"prop\": 5 }");
+    })
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      ("mod.ts", r#"import jsonData from './data.js';"#),
+      ("data.js", r#"export default { "prop": 5 };"#)
+    ]
+  );
+}
+
+#[tokio::test]
+async fn json_module_dynamic_import() {
   let result = TestBuilder::new()
     .with_loader(|loader| {
       loader
         .add_local_file(
           "/mod.ts",
-          r#"import jsonData from './data.json' assert { type: 'json' };"#,
+          r#"const jsonData = (await import('./data.json', { assert: { type: 'json' } })).default;"#
         )
-        .add_local_file("/data.json", "\u{FEFF}{ \"prop\": 5 }");
+        .add_local_file("/data.json", r#"{ "prop": 5 }"#);
     })
     .transform()
     .await
@@ -1879,37 +3646,112 @@ async fn json_module_import_default() {
   assert_files!(
     result.main.files,
     &[
-      ("mod.ts", r#"import jsonData from './data.js';"#),
+      (
+        "mod.ts",
+        r#"const jsonData = (await import('./data.js')).default;"#
+      ),
       ("data.js", r#"export default { "prop": 5 };"#)
     ]
   );
 }
 
 #[tokio::test]
-async fn json_module_dynamic_import() {
+async fn dynamic_import_template_literal() {
   let result = TestBuilder::new()
     .with_loader(|loader| {
       loader
         .add_local_file(
           "/mod.ts",
-          r#"const jsonData = (await import('./data.json', { assert: { type: 'json' } })).default;"#
+          concat!(
+            "const messages = await import(`./locales/${lang}.ts`);\n",
+            "const other = await import(`./other.ts`);\n",
+            "const data = await import(`./data/${name}.json`, { with: { type: 'json' } });",
+          ),
         )
-        .add_local_file("/data.json", r#"{ "prop": 5 }"#);
+        .add_local_file("/locales/en.ts", "export default 'en';")
+        .add_local_file("/locales/fr.ts", "export default 'fr';")
+        .add_local_file("/locales/nested/de.ts", "export default 'de';")
+        .add_local_file("/locales/readme.md", "not a module")
+        .add_local_file("/other.ts", "export default 'other';")
+        .add_local_file("/data/a.json", "{}");
     })
     .transform()
     .await
     .unwrap();
 
+  assert!(result.diagnostics.is_empty());
   assert_files!(
     result.main.files,
     &[
       (
         "mod.ts",
-        r#"const jsonData = (await import('./data.js')).default;"#
+        concat!(
+          "const messages = await import(`./locales/${lang}.js`);\n",
+          "const other = await import(`./other.js`);\n",
+          "const data = await import(`./data/${name}.js`);",
+        ),
+      ),
+      ("locales/en.ts", "export default 'en';"),
+      ("locales/fr.ts", "export default 'fr';"),
+      ("locales/nested/de.ts", "export default 'de';"),
+      ("other.ts", "export default 'other';"),
+      ("data/a.js", "export default {};"),
+    ]
+  );
+}
+
+#[tokio::test]
+async fn dynamic_import_not_analyzable() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        concat!(
+          "const a = await import(specifier);\n",
+          "const b = await import('./' + name + '.ts');\n",
+          "const c = await import(`${base}/mod.ts`);",
+        ),
+      );
+    })
+    .transform()
+    .await
+    .unwrap();
+
+  assert_eq!(
+    result
+      .diagnostics
+      .iter()
+      .map(|d| (d.code, d.range.unwrap().start.line, d.message.as_str()))
+      .collect::<Vec<_>>(),
+    vec![
+      (
+        DiagnosticCode::DynamicImport,
+        0,
+        "Could not analyze the dynamic import at file:///mod.ts:1:24. The module it imports might not be in the output or its specifier might not be correct.",
+      ),
+      (
+        DiagnosticCode::DynamicImport,
+        1,
+        "Could not analyze the dynamic import at file:///mod.ts:2:24. The module it imports might not be in the output or its specifier might not be correct.",
+      ),
+      (
+        DiagnosticCode::DynamicImport,
+        2,
+        "Could not analyze the dynamic import at file:///mod.ts:3:24. The module it imports might not be in the output or its specifier might not be correct.",
       ),
-      ("data.js", r#"export default { "prop": 5 };"#)
     ]
   );
+  assert_files!(
+    result.main.files,
+    &[(
+      "mod.ts",
+      concat!(
+        "const a = await import(specifier);\n",
+        "const b = await import('./' + name + '.ts');\n",
+        "const c = await import(`${base}/mod.ts`);",
+      ),
+    )]
+  );
 }
 
 #[tokio::test]
@@ -2101,6 +3943,411 @@ async fn npm_specifier() {
   );
 }
 
+#[tokio::test]
+async fn jsr_specifier() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "import { join } from 'jsr:@std/path@^1/join';\n",
+            "import * as assert from 'jsr:@std/assert@1';\n",
+            "console.log(join, assert);",
+          ),
+        )
+        .add_local_file(
+          "/mod.test.ts",
+          "import { delay } from 'jsr:@std/async@^1.0.1/delay';",
+        );
+    })
+    .add_test_entry_point("file:///mod.test.ts")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[(
+      "mod.ts",
+      concat!(
+        "import { join } from '@jsr/std__path/join';\n",
+        "import * as assert from '@jsr/std__assert';\n",
+        "console.log(join, assert);",
+      ),
+    )]
+  );
+  assert_eq!(
+    result.main.dependencies,
+    vec![
+      Dependency {
+        name: "@jsr/std__assert".to_string(),
+        version: "1".to_string(),
+        peer_dependency: false,
+      },
+      Dependency {
+        name: "@jsr/std__path".to_string(),
+        version: "^1".to_string(),
+        peer_dependency: false,
+      },
+    ]
+  );
+  assert_files!(
+    result.test.files,
+    &[(
+      "mod.test.ts",
+      "import { delay } from '@jsr/std__async/delay';"
+    )]
+  );
+  assert_eq!(
+    result.test.dependencies,
+    vec![Dependency {
+      name: "@jsr/std__async".to_string(),
+      version: "^1.0.1".to_string(),
+      peer_dependency: false,
+    }]
+  );
+}
+
+#[tokio::test]
+async fn jsr_specifier_mapping() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file(
+        "/mod.ts",
+        "import { join } from 'jsr:@std/path@^1/join'; console.log(join);",
+      );
+    })
+    .add_package_specifier_mapping("jsr:@std/path@^1/join", "path", None, None)
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[("mod.ts", "import { join } from 'path'; console.log(join);")]
+  );
+  assert_eq!(result.main.dependencies, vec![]);
+}
+
+#[tokio::test]
+async fn jsr_specifier_vendor() {
+  let join_text =
+    "import { normalize } from './normalize.ts'; export function join() {}";
+  let normalize_text = "export function normalize() {}";
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          "import { join } from 'jsr:@std/path@^1/join'; console.log(join);",
+        )
+        .add_remote_file(
+          "http://localhost:4545/jsr/@std/path/meta.json",
+          r#"{ "versions": { "1.0.0": {}, "1.1.0": {}, "2.0.0": {} } }"#,
+        )
+        .add_remote_file(
+          "http://localhost:4545/jsr/@std/path/1.1.0_meta.json",
+          format!(
+            r#"{{
+              "exports": {{ ".": "./mod.ts", "./join": "./join.ts" }},
+              "manifest": {{
+                "/join.ts": {{ "size": 0, "checksum": "sha256-{}" }},
+                "/normalize.ts": {{ "size": 0, "checksum": "sha256-{}" }}
+              }}
+            }}"#,
+            LoaderChecksum::gen(join_text.as_bytes()),
+            LoaderChecksum::gen(normalize_text.as_bytes()),
+          ),
+        )
+        .add_remote_file(
+          "http://localhost:4545/jsr/@std/path/1.1.0/join.ts",
+          join_text,
+        )
+        .add_remote_file(
+          "http://localhost:4545/jsr/@std/path/1.1.0/normalize.ts",
+          normalize_text,
+        );
+    })
+    .set_jsr_specifier_mode(JsrSpecifierMode::Vendor)
+    .set_jsr_url("http://localhost:4545/jsr")
+    .transform()
+    .await
+    .unwrap();
+
+  assert_files!(
+    result.main.files,
+    &[
+      (
+        "mod.ts",
+        "import { join } from './deps/localhost_4545/jsr/@std/path/1.1.0/join.js'; console.log(join);",
+      ),
+      (
+        "deps/localhost_4545/jsr/@std/path/1.1.0/join.ts",
+        "import { normalize } from './normalize.js'; export function join() {}",
+      ),
+      (
+        "deps/localhost_4545/jsr/@std/path/1.1.0/normalize.ts",
+        "export function normalize() {}",
+      ),
+    ]
+  );
+  assert_eq!(result.main.dependencies, vec![]);
+}
+
+#[tokio::test]
+async fn source_maps() {
+  let result = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          concat!(
+            "// @deno-types=\"./a.d.ts\"\n",
+            "import { a } from \"./a.js\";\n",
+            "import data from \"./data.json\" with { type: \"json\" };\n",
+            "[].findLast(() => true);\n",
+            "Deno.cwd(a, data);\n",
+          ),
+        )
+        .add_local_file("/a.js", "export const a = 1;")
+        .add_local_file("/a.d.ts", "export const a: number;")
+        .add_local_file("/data.json", "\n  5\n");
+    })
+    .add_default_shims()
+    .set_target(ScriptTarget::ES2015)
+    .set_source_maps(true)
+    .transform()
+    .await
+    .unwrap();
+
+  let mod_file = result
+    .main
+    .files
+    .iter()
+    .find(|f| f.file_path == PathBuf::from("mod.ts"))
+    .unwrap();
+  assert_eq!(
+    mod_file.file_text,
+    concat!(
+      "import \"./_dnt.polyfills.js\";\n",
+      "\n",
+      "import * as dntShim from \"./_dnt.shims.js\";\n",
+      "\n",
+      "import { a } from \"./a.js\";\n",
+      "import data from \"./data.js\";\n",
+      "[].findLast(() => true);\n",
+      "dntShim.Deno.cwd(a, data);\n",
+    )
+  );
+  let source_map = sourcemap::SourceMap::from_slice(
+    mod_file.source_map.as_ref().unwrap().as_bytes(),
+  )
+  .unwrap();
+  assert_eq!(source_map.get_source(0), Some("file:///mod.ts"));
+  let lookup = |line: u32, col: u32| {
+    let token = source_map.lookup_token(line, col).unwrap();
+    (token.get_src_line(), token.get_src_col())
+  };
+  assert_eq!(lookup(4, 9), (1, 9));
+  assert_eq!(lookup(5, 7), (2, 7));
+  assert_eq!(lookup(6, 3), (3, 3));
+  assert_eq!(lookup(7, 13), (4, 5));
+  assert_eq!(lookup(7, 20), (4, 12));
+
+  let json_file = result
+    .main
+    .files
+    .iter()
+    .find(|f| f.file_path == PathBuf::from("data.js"))
+    .unwrap();
+  assert_eq!(json_file.file_text, "export default 5;");
+  let source_map = sourcemap::SourceMap::from_slice(
+    json_file.source_map.as_ref().unwrap().as_bytes(),
+  )
+  .unwrap();
+  let token = source_map.lookup_token(0, 15).unwrap();
+  assert_eq!((token.get_src_line(), token.get_src_col()), (1, 2));
+
+  // synthetic files don't have a source map
+  let polyfill_file = result
+    .main
+    .files
+    .iter()
+    .find(|f| f.file_path == PathBuf::from("_dnt.polyfills.ts"))
+    .unwrap();
+  assert_eq!(polyfill_file.source_map, None);
+}
+
+#[tokio::test]
+async fn transform_cache() {
+  #[derive(Default)]
+  struct CountingTransformCache {
+    inner: InMemoryTransformCache,
+    hits: Cell<usize>,
+  }
+
+  impl TransformCache for CountingTransformCache {
+    fn get(&self, key: &str) -> Option<Vec<u8>> {
+      let data = self.inner.get(key);
+      if data.is_some() {
+        self.hits.set(self.hits.get() + 1);
+      }
+      data
+    }
+
+    fn set(&self, key: &str, data: Vec<u8>) {
+      self.inner.set(key, data)
+    }
+  }
+
+  let cache = Rc::new(CountingTransformCache::default());
+  let mut builder = TestBuilder::new();
+  builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file(
+          "/mod.ts",
+          "import { a } from './a.ts';\n[].findLast(() => Deno.cwd());",
+        )
+        .add_local_file("/a.ts", "export const a = Object.hasOwn({}, 'a');");
+    })
+    .add_default_shims()
+    .set_transform_cache(cache.clone());
+  let first_result = builder.transform().await.unwrap();
+  assert_eq!(cache.hits.get(), 0);
+  assert_eq!(cache.inner.len(), 2);
+
+  let second_result = builder.transform().await.unwrap();
+  assert_eq!(cache.hits.get(), 2);
+  assert_eq!(cache.inner.len(), 2);
+  assert_eq!(first_result, second_result);
+
+  // changing a module only invalidates that module
+  builder.with_loader(|loader| {
+    loader.add_local_file("/a.ts", "export const a = 5;");
+  });
+  let third_result = builder.transform().await.unwrap();
+  assert_eq!(cache.hits.get(), 3);
+  assert_eq!(cache.inner.len(), 3);
+  let get_file_text = |file_path: &str| {
+    third_result
+      .main
+      .files
+      .iter()
+      .find(|f| f.file_path == PathBuf::from(file_path))
+      .map(|f| f.file_text.as_str())
+  };
+  assert_eq!(
+    get_file_text("mod.ts"),
+    Some(concat!(
+      "import \"./_dnt.polyfills.js\";\n",
+      "import * as dntShim from \"./_dnt.shims.js\";\n",
+      "import { a } from './a.js';\n",
+      "[].findLast(() => dntShim.Deno.cwd());",
+    ))
+  );
+  assert_eq!(get_file_text("a.ts"), Some("export const a = 5;"));
+  assert_eq!(
+    get_file_text("_dnt.polyfills.ts"),
+    Some(include_str!(
+      "../src/polyfills/scripts/esnext.array-findLast.ts"
+    ))
+  );
+}
+
+#[tokio::test]
+async fn lockfile_checksum_mismatch() {
+  let err_message = TestBuilder::new()
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "import 'http://localhost/mod.ts';")
+        .add_local_file(
+          "/deno.lock",
+          r#"{
+            "version": "4",
+            "remote": { "http://localhost/mod.ts": "abcd" }
+          }"#,
+        )
+        .add_remote_file("http://localhost/mod.ts", "remote;");
+    })
+    .set_lockfile("file:///deno.lock", false)
+    .transform()
+    .await
+    .err()
+    .unwrap();
+
+  let error = err_message.downcast_ref::<ModuleGraphError>().unwrap();
+  assert_eq!(error.failures.len(), 1);
+  assert_eq!(error.failures[0].kind, ModuleFailureKind::ChecksumMismatch);
+  assert_eq!(
+    err_message.to_string(),
+    format!(
+      concat!(
+        "The source of http://localhost/mod.ts does not match its checksum in the lockfile at file:///deno.lock. ",
+        "If the modification is expected, remove the module's entry from the lockfile.\n\n",
+        "Integrity check failed.\n\n",
+        "Actual: {}\n",
+        "Expected: abcd\n",
+        "    at file:///mod.ts:1:8",
+      ),
+      LoaderChecksum::gen(b"remote;"),
+    )
+  );
+}
+
+#[tokio::test]
+async fn lockfile_write() {
+  let mut builder = TestBuilder::new();
+  builder
+    .with_loader(|loader| {
+      loader
+        .add_local_file("/mod.ts", "import 'http://localhost/mod.ts';")
+        .add_remote_file("http://localhost/mod.ts", "import './other.ts';")
+        .add_remote_file("http://localhost/other.ts", "other;");
+    })
+    .set_lockfile("file:///deno.lock", true);
+  let result = builder.transform().await.unwrap();
+  let lockfile_text = result.lockfile_text.unwrap();
+  assert_eq!(
+    lockfile_text,
+    format!(
+      r#"{{
+  "version": "4",
+  "remote": {{
+    "http://localhost/mod.ts": "{}",
+    "http://localhost/other.ts": "{}"
+  }}
+}}
+"#,
+      LoaderChecksum::gen(b"import './other.ts';"),
+      LoaderChecksum::gen(b"other;"),
+    )
+  );
+
+  // nothing to write when the lockfile is up to date
+  builder.with_loader(|loader| {
+    loader.add_local_file("/deno.lock", &lockfile_text);
+  });
+  let result = builder.transform().await.unwrap();
+  assert_eq!(result.lockfile_text, None);
+
+  // errors when not writing and the lockfile doesn't exist
+  let err_message = TestBuilder::new()
+    .with_loader(|loader| {
+      loader.add_local_file("/mod.ts", "");
+    })
+    .set_lockfile("file:///deno.lock", false)
+    .transform()
+    .await
+    .err()
+    .unwrap();
+  assert_eq!(
+    err_message.to_string(),
+    "Could not find lockfile file:///deno.lock"
+  );
+}
+
 fn get_shim_file_text(mut text: String) -> String {
   text.push('\n');
   text.push_str(
@@ -37,10 +37,14 @@ export interface PackageMappedSpecifier {
 }
 
 export interface GlobalName {
-  /** Name to use as the global name. */
+  /** Name to use as the global name.
+   * @remarks This may also be a member of a global (ex. `"Deno.readTextFile"`)
+   * in order to only shim that member when it's accessed.
+   */
   name: string;
   /** Name of the export from the package.
-   * @remarks Defaults to the name. Specify `"default"` to use the default export.
+   * @remarks Defaults to the name or the last part of a member name.
+   * Specify `"default"` to use the default export.
    */
   exportName?: string;
   /** Whether this is a name that only exists as a type declaration. */
@@ -72,8 +76,91 @@ export interface TransformOptions {
   testShims?: Shim[];
   mappings?: SpecifierMappings;
   target: ScriptTarget;
+  /** Range of Node.js versions the output should run on (ex. `">=18"`), in
+   * the format of package.json's `engines.node`. When provided, the polyfills
+   * are chosen based on the built-ins of these versions instead of the
+   * `target`, and built-ins that can't be polyfilled are warned about. */
+  nodeVersionRange?: string;
+  /** Module system of the output code. When provided, `import.meta.url`,
+   * `import.meta.main` and `import.meta.resolve` are rewritten to work in
+   * Node for it. Otherwise, `import.meta` is left as is. */
+  moduleKind?: "esm" | "commonJs";
+  /** Convert `Deno.test` and `t.step` registrations in the test modules to
+   * `node:test`, so the tests can run with `node --test`.
+   * @default false
+   */
+  nodeTest?: boolean;
   /// Path or url to the import map.
   importMap?: string;
+  /** Path or url to a deno.json or deno.jsonc file. Its `imports` and `scopes`
+   * are used when no `importMap` is provided, `compilerOptions.jsxImportSource`
+   * is the default JSX import source, and its `exports` are the entry points
+   * when no entry points are provided. */
+  configFile?: string;
+  /** Throw when the import map has diagnostics (ex. invalid addresses)
+   * instead of providing them as warnings.
+   * @default false
+   */
+  errorOnImportMapDiagnostics?: boolean;
+  /** How to handle `jsr:` specifiers.
+   *
+   * * `"npm"` - Map them to packages on JSR's npm compatibility layer (ex. `@jsr/std__path`).
+   * * `"vendor"` - Download the package's modules from the registry and include them in the output.
+   * @default "npm"
+   */
+  jsrSpecifierMode?: "npm" | "vendor";
+  /** Url of the JSR registry to use when vendoring. Defaults to `https://jsr.io/`. */
+  jsrUrl?: string;
+  /** Directory the output paths of local modules are relative to.
+   * Defaults to the closest directory all the local modules share. */
+  rootDir?: string;
+  /** What to do with local modules that are outside of `rootDir`.
+   *
+   * * `"error"` - Throw an error listing them.
+   * * `"external"` - Write them to an `_external` directory, at their path from the closest directory
+   *   they share with `rootDir` (ex. `_external/fixtures/data.json`).
+   * @default "error"
+   */
+  outsideRootDirMode?: "error" | "external";
+  /** Keep the `.mts`, `.cts`, `.mjs` and `.cjs` extensions of modules in their output paths and in the
+   * specifiers that import them, so each module keeps its module format. Otherwise, `.mts` and `.mjs`
+   * modules are output as `.js` files and imported with a `.js` extension.
+   * @default false
+   */
+  preserveModuleFormatExtensions?: boolean;
+  /** Write byte-identical remote modules that are reachable through several
+   * urls (ex. mirrors of a CDN) to a single output file instead of one per url.
+   * @default false
+   */
+  dedupeRemoteModules?: boolean;
+  /** How the output paths of remote modules in the `deps` directory are chosen.
+   *
+   * * `"mirror"` - Mirror the host and path of the url (ex. `deps/deno.land/x/oak@v12.6.0/mod.ts`).
+   *   Adding a module may rename others when their paths collide or are shortened.
+   * * `"contentHash"` - Use a directory named with a short hash of the module's url and source
+   *   (ex. `deps/3f2a9c1b0d/mod.ts`).
+   * * `"flat"` - Use a directory for each package, suffixed with a short hash of the package's url,
+   *   with the path within the package (ex. `deps/oak@v12.6.0_3f2a9c1b/mod.ts`). Paths with uppercase
+   *   letters are suffixed with a hash of the url.
+   *
+   * The `"contentHash"` and `"flat"` layouts keep the paths of existing modules when modules from other
+   * packages are added.
+   * @default "mirror"
+   */
+  remoteLayout?: "mirror" | "contentHash" | "flat";
+  /** Maximum length of a remote module's path within the `deps` directory.
+   * @default 180
+   */
+  remotePathMaxLength?: number;
+  /** Whether to create a source map for each transformed module.
+   * @default false
+   */
+  sourceMaps?: boolean;
+  /** Directory to cache the analysis of each module in, which allows
+   * unchanged modules to be skipped on subsequent runs. */
+  transformCacheDir?: string;
+  /** Lockfile (deno.lock) to verify the checksums of remote modules against. */
+  lockfile?: LockfileOptions;
   internalWasmUrl?: string;
 }
 
@@ -87,21 +174,119 @@ export interface Dependency {
   peerDependency?: boolean;
 }
 
+export interface LockfileOptions {
+  /** Path or url to the lockfile. */
+  path: string;
+  /** Text of the lockfile. When not provided, it's loaded from the path. */
+  text?: string;
+  /** Add the checksums of remote modules that aren't in the lockfile and
+   * provide the result in `lockfileText` of the output.
+   * @default false
+   */
+  write?: boolean;
+}
+
 export interface TransformOutput {
   main: TransformOutputEnvironment;
   test: TransformOutputEnvironment;
-  warnings: string[];
+  /** Problems found while transforming that didn't stop the transform. */
+  diagnostics: Diagnostic[];
+  /** Updated text of the lockfile, when checksums were added to it and
+   * writing was requested. */
+  lockfileText?: string;
+}
+
+export interface Diagnostic {
+  /** Stable code of the kind of diagnostic (ex. `"import-map"`). */
+  code:
+    | "import-map"
+    | "duplicate-declaration-file"
+    | "deno-shim-ignore-renamed"
+    | "dynamic-import"
+    | "deno-test"
+    | "unsupported-built-in"
+    | "using-declaration";
+  severity: "error" | "warning";
+  /** Module or file the diagnostic originated from. */
+  specifier?: string;
+  /** Zero-indexed range in the module. */
+  range?: {
+    start: { line: number; character: number };
+    end: { line: number; character: number };
+  };
+  message: string;
+}
+
+/** Error thrown by `transform` when one or more modules in the module graph failed. */
+export class ModuleGraphError extends Error {
+  failures: ModuleFailure[];
+
+  constructor(message: string, failures: ModuleFailure[]) {
+    super(message);
+    this.name = "ModuleGraphError";
+    this.failures = failures;
+  }
+}
+
+export interface ModuleFailure {
+  kind:
+    | "notFound"
+    | "parse"
+    | "resolution"
+    | "checksumMismatch"
+    | "load"
+    | "unsupported";
+  /** Specifier of the module that failed. For an import that couldn't be
+   * resolved, this is the module with the import. */
+  specifier: string;
+  /** Where the failed module was imported. */
+  referrer?: {
+    specifier: string;
+    /** Zero-indexed range in the module. */
+    range: {
+      start: { line: number; character: number };
+      end: { line: number; character: number };
+    };
+  };
+  /** Source code of the import, or of the parse error, with the range underlined. */
+  codeFrame?: string;
+  message: string;
 }
 
 export interface TransformOutputEnvironment {
   entryPoints: string[];
   dependencies: Dependency[];
   files: OutputFile[];
+  /** Files that use the shims, in the same order as `files`. */
+  shimUsage: FileShimUsage[];
+}
+
+/** Where an output file uses the globals of the environment's shims. */
+export interface FileShimUsage {
+  filePath: string;
+  /** Module the file was created from. */
+  specifier: string;
+  /** Shimmed global names used in the file, sorted by name. */
+  shims: ShimUsage[];
+}
+
+export interface ShimUsage {
+  /** Shim global name (ex. `"Deno"` or `"Deno.readTextFile"`) or
+   * `"globalThis"` when it was replaced with the shim's `dntGlobalThis`. */
+  name: string;
+  /** Zero-indexed ranges in the original module where the name was used. */
+  ranges: {
+    start: { line: number; character: number };
+    end: { line: number; character: number };
+  }[];
 }
 
 export interface OutputFile {
   filePath: string;
   fileText: string;
+  /** Source map that maps the output back to the original module.
+   * Only provided when `sourceMaps` is `true`. */
+  sourceMap?: string;
 }
 
 /** Analyzes the provided entry point to get all the dependended on modules and
@@ -110,7 +295,7 @@ export interface OutputFile {
 export async function transform(
   options: TransformOptions,
 ): Promise<TransformOutput> {
-  if (options.entryPoints.length === 0) {
+  if (options.entryPoints.length === 0 && options.configFile == null) {
     throw new Error("Specify one or more entry points.");
   }
   const newOptions = {
@@ -128,11 +313,30 @@ export async function transform(
     importMap: options.importMap == null
       ? undefined
       : valueToUrl(options.importMap),
+    configFile: options.configFile == null
+      ? undefined
+      : valueToUrl(options.configFile),
+    rootDir: options.rootDir == null ? undefined : valueToUrl(options.rootDir),
+    lockfile: options.lockfile == null ? undefined : {
+      specifier: valueToUrl(options.lockfile.path),
+      text: options.lockfile.text,
+      write: options.lockfile.write ?? false,
+    },
   };
   const wasmFuncs = await instantiate({
     url: options.internalWasmUrl ? new URL(options.internalWasmUrl) : undefined,
   });
-  return wasmFuncs.transform(newOptions);
+  try {
+    return await wasmFuncs.transform(newOptions);
+  } catch (err) {
+    if (err instanceof Error && err.name === "ModuleGraphError") {
+      throw new ModuleGraphError(
+        err.message,
+        (err as Error & { failures: ModuleFailure[] }).failures,
+      );
+    }
+    throw err;
+  }
 }
 
 type SerializableMappedSpecifier = {
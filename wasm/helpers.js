@@ -10,6 +10,48 @@ export function fetch_specifier(specifier, cacheSettingVal, checksum) {
   );
 }
 
+export function get_transform_cache_entry(dir, key) {
+  try {
+    return Deno.readFileSync(join(dir, key));
+  } catch {
+    return undefined;
+  }
+}
+
+export function set_transform_cache_entry(dir, key, data) {
+  // the cache is only an optimization, so ignore any errors
+  try {
+    Deno.mkdirSync(dir, { recursive: true });
+    const tempFilePath = join(dir, `${key}.tmp`);
+    Deno.writeFileSync(tempFilePath, data);
+    Deno.renameSync(tempFilePath, join(dir, key));
+  } catch {
+    // ignore
+  }
+}
+
+export function read_dir(dirUrl) {
+  const url = new URL(dirUrl);
+  if (url.protocol !== "file:") {
+    return [];
+  }
+  const dirPath = dirUrl.endsWith("/") ? url : new URL(`${dirUrl}/`);
+  try {
+    return Array.from(Deno.readDirSync(url)).map((entry) => ({
+      url: new URL(entry.name, dirPath).href,
+      isDirectory: entry.isDirectory,
+    }));
+  } catch {
+    return [];
+  }
+}
+
+function join(dir, name) {
+  return dir.endsWith("/") || dir.endsWith("\\")
+    ? `${dir}${name}`
+    : `${dir}/${name}`;
+}
+
 function getCacheSetting(val) {
   // WARNING: ensure this matches wasm/src/lib.rs
   switch (val) {
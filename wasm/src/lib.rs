@@ -7,8 +7,13 @@ use std::future::Future;
 use std::rc::Rc;
 
 use anyhow::Result;
+use dnt::JsrSpecifierMode;
+use dnt::LockfileOptions;
 use dnt::MappedSpecifier;
+use dnt::ModuleKind;
 use dnt::ModuleSpecifier;
+use dnt::OutsideRootDirMode;
+use dnt::RemoteLayout;
 use dnt::ScriptTarget;
 use dnt::Shim;
 use serde::Deserialize;
@@ -22,6 +27,23 @@ extern "C" {
     cache_setting: u8,
     maybe_checksum: Option<String>,
   ) -> JsValue;
+  fn get_transform_cache_entry(dir: &str, key: &str) -> Option<Vec<u8>>;
+  fn set_transform_cache_entry(dir: &str, key: &str, data: Vec<u8>);
+  fn read_dir(dir_url: String) -> JsValue;
+}
+
+struct JsTransformCache {
+  dir: String,
+}
+
+impl dnt::TransformCache for JsTransformCache {
+  fn get(&self, key: &str) -> Option<Vec<u8>> {
+    get_transform_cache_entry(&self.dir, key)
+  }
+
+  fn set(&self, key: &str, data: Vec<u8>) {
+    set_transform_cache_entry(&self.dir, key, data)
+  }
 }
 
 struct JsLoader;
@@ -57,6 +79,32 @@ impl dnt::Loader for JsLoader {
       Ok(Some(load_response))
     })
   }
+
+  fn read_dir(&self, dir_url: &dnt::ModuleSpecifier) -> Vec<dnt::DirEntry> {
+    let entries: Vec<JsDirEntry> =
+      serde_wasm_bindgen::from_value(read_dir(dir_url.to_string()))
+        .unwrap_or_default();
+    entries
+      .into_iter()
+      .filter_map(|entry| {
+        Some(dnt::DirEntry {
+          kind: if entry.is_directory {
+            dnt::DirEntryKind::Dir
+          } else {
+            dnt::DirEntryKind::File
+          },
+          url: ModuleSpecifier::parse(&entry.url).ok()?,
+        })
+      })
+      .collect()
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JsDirEntry {
+  url: String,
+  is_directory: bool,
 }
 
 #[derive(Deserialize)]
@@ -68,7 +116,31 @@ pub struct TransformOptions {
   pub test_shims: Vec<Shim>,
   pub mappings: HashMap<ModuleSpecifier, MappedSpecifier>,
   pub target: ScriptTarget,
+  pub node_version_range: Option<String>,
+  pub module_kind: Option<ModuleKind>,
+  #[serde(default)]
+  pub node_test: bool,
   pub import_map: Option<ModuleSpecifier>,
+  pub config_file: Option<ModuleSpecifier>,
+  #[serde(default)]
+  pub error_on_import_map_diagnostics: bool,
+  #[serde(default)]
+  pub jsr_specifier_mode: JsrSpecifierMode,
+  pub jsr_url: Option<ModuleSpecifier>,
+  pub root_dir: Option<ModuleSpecifier>,
+  #[serde(default)]
+  pub outside_root_dir_mode: OutsideRootDirMode,
+  #[serde(default)]
+  pub preserve_module_format_extensions: bool,
+  #[serde(default)]
+  pub dedupe_remote_modules: bool,
+  #[serde(default)]
+  pub remote_layout: RemoteLayout,
+  pub remote_path_max_length: Option<usize>,
+  #[serde(default)]
+  pub source_maps: bool,
+  pub transform_cache_dir: Option<String>,
+  pub lockfile: Option<LockfileOptions>,
 }
 
 #[wasm_bindgen]
@@ -90,14 +162,50 @@ pub async fn transform(options: JsValue) -> Result<JsValue, JsValue> {
     loader: Some(Rc::new(JsLoader {})),
     specifier_mappings: options.mappings,
     target: options.target,
+    node_version_range: options.node_version_range,
+    module_kind: options.module_kind,
+    node_test: options.node_test,
     import_map: options.import_map,
+    config_file: options.config_file,
+    error_on_import_map_diagnostics: options.error_on_import_map_diagnostics,
+    jsr_specifier_mode: options.jsr_specifier_mode,
+    jsr_url: options.jsr_url,
+    root_dir: options.root_dir,
+    outside_root_dir_mode: options.outside_root_dir_mode,
+    preserve_module_format_extensions: options
+      .preserve_module_format_extensions,
+    dedupe_remote_modules: options.dedupe_remote_modules,
+    remote_layout: options.remote_layout,
+    remote_path_max_length: options.remote_path_max_length,
+    source_maps: options.source_maps,
+    transform_cache: options.transform_cache_dir.map(|dir| {
+      Rc::new(JsTransformCache { dir }) as Rc<dyn dnt::TransformCache>
+    }),
+    lockfile: options.lockfile,
   })
   .await
-  .map_err(|err| format!("{:#}", err))?; // need to include the anyhow context
+  .map_err(|err| match err.downcast_ref::<dnt::ModuleGraphError>() {
+    Some(graph_error) => module_graph_error_to_js(graph_error),
+    None => format!("{:#}", err).into(), // need to include the anyhow context
+  })?;
 
   Ok(serde_wasm_bindgen::to_value(&result).unwrap())
 }
 
+/// Converts the error to a JS error with the `failures` as a property,
+/// so the typed failures are available in JS.
+fn module_graph_error_to_js(error: &dnt::ModuleGraphError) -> JsValue {
+  let js_error = js_sys::Error::new(&error.to_string());
+  js_error.set_name("ModuleGraphError");
+  js_sys::Reflect::set(
+    &js_error,
+    &JsValue::from_str("failures"),
+    &serde_wasm_bindgen::to_value(&error.failures).unwrap(),
+  )
+  .unwrap();
+  js_error.into()
+}
+
 fn parse_module_specifiers(
   values: Vec<String>,
 ) -> Result<Vec<ModuleSpecifier>, JsValue> {